@@ -0,0 +1,99 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use embedded_graphics::{
+    image::{Image, ImageRaw},
+    pixelcolor::{raw::BigEndian, Rgb565},
+    prelude::*,
+    primitives::Rectangle,
+};
+use embedded_graphics_simulator::SimulatorDisplay;
+
+const SIZE: Size = Size::new(480, 320);
+
+/// Draw target that only forwards `draw_iter`.
+///
+/// Used as the baseline to compare the accelerated methods against, because all other methods
+/// fall back to the default implementations, which draw every pixel individually.
+struct DrawIterOnly<'a>(&'a mut SimulatorDisplay<Rgb565>);
+
+impl DrawTarget for DrawIterOnly<'_> {
+    type Color = Rgb565;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.0.draw_iter(pixels)
+    }
+}
+
+impl OriginDimensions for DrawIterOnly<'_> {
+    fn size(&self) -> Size {
+        self.0.size()
+    }
+}
+
+fn clear(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clear");
+    let mut display = SimulatorDisplay::<Rgb565>::new(SIZE);
+
+    group.bench_function("accelerated", |b| {
+        b.iter(|| display.clear(black_box(Rgb565::RED)).unwrap())
+    });
+
+    group.bench_function("draw_iter", |b| {
+        b.iter(|| {
+            DrawIterOnly(&mut display)
+                .clear(black_box(Rgb565::RED))
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+fn fill_solid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fill_solid");
+    let mut display = SimulatorDisplay::<Rgb565>::new(SIZE);
+    let area = Rectangle::new(Point::new(-20, 40), Size::new(300, 200));
+
+    group.bench_function("accelerated", |b| {
+        b.iter(|| display.fill_solid(&area, black_box(Rgb565::GREEN)).unwrap())
+    });
+
+    group.bench_function("draw_iter", |b| {
+        b.iter(|| {
+            DrawIterOnly(&mut display)
+                .fill_solid(&area, black_box(Rgb565::GREEN))
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+fn image(c: &mut Criterion) {
+    let mut group = c.benchmark_group("image");
+    let mut display = SimulatorDisplay::<Rgb565>::new(SIZE);
+
+    let data = (0..200u32 * 150 * 2).map(|i| i as u8).collect::<Vec<_>>();
+    let raw = ImageRaw::<Rgb565, BigEndian>::new(&data, 200);
+    let image = Image::new(&raw, Point::new(330, 100));
+
+    group.bench_function("accelerated", |b| {
+        b.iter(|| black_box(&image).draw(&mut display).unwrap())
+    });
+
+    group.bench_function("draw_iter", |b| {
+        b.iter(|| {
+            black_box(&image)
+                .draw(&mut DrawIterOnly(&mut display))
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, clear, fill_solid, image);
+criterion_main!(benches);
@@ -14,7 +14,7 @@ use embedded_graphics::{
     primitives::{Circle, PrimitiveStyle},
 };
 use embedded_graphics_simulator::{
-    sdl2::Keycode, OutputSettings, SimulatorDisplay, SimulatorEvent, Window,
+    input::Keycode, OutputSettings, SimulatorDisplay, SimulatorEvent, Window,
 };
 
 const BACKGROUND_COLOR: Rgb888 = Rgb888::BLACK;
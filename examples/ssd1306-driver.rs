@@ -0,0 +1,64 @@
+//! Runs the unchanged `ssd1306` driver crate on an emulated SSD1306 controller.
+//!
+//! This example requires the `display-interface` feature:
+//!
+//! ```bash
+//! cargo run --example ssd1306-driver --features display-interface
+//! ```
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle},
+    text::{Baseline, Text},
+};
+use embedded_graphics_simulator::{
+    controller::{Controller, SimulatorInterface, Ssd1306},
+    BinaryColorTheme, OutputSettingsBuilder, SimulatorEvent, Window,
+};
+use ssd1306::{prelude::*, Ssd1306 as Ssd1306Driver};
+
+fn main() {
+    let interface = SimulatorInterface::new(Ssd1306::new(Size::new(128, 64)));
+    let controller = interface.controller();
+
+    let mut display = Ssd1306Driver::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
+        .into_buffered_graphics_mode();
+    display.init().unwrap();
+
+    let text_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+    let circle_style = PrimitiveStyle::with_fill(BinaryColor::On);
+
+    let mut window = Window::new("SSD1306 driver", &OutputSettingsBuilder::new().build());
+
+    for x in (0..112).cycle() {
+        display.clear_buffer();
+        Text::with_baseline(
+            "Hello from ssd1306!",
+            Point::zero(),
+            text_style,
+            Baseline::Top,
+        )
+        .draw(&mut display)
+        .unwrap();
+        Circle::new(Point::new(x, 32), 16)
+            .into_styled(circle_style)
+            .draw(&mut display)
+            .unwrap();
+        display.flush().unwrap();
+
+        let mut controller = controller.borrow_mut();
+        window.set_output_settings(
+            &OutputSettingsBuilder::new()
+                .scale(2)
+                .theme(controller.apply_contrast(BinaryColorTheme::OledBlue))
+                .build(),
+        );
+        window.update(controller.display());
+
+        if window.events().any(|e| e == SimulatorEvent::Quit) {
+            break;
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::{cell::RefCell, rc::Rc};
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+use crate::controller::Controller;
+
+/// Display interface that sends commands and data to an emulated controller.
+///
+/// `SimulatorInterface` implements the [`WriteOnlyDataCommand`] trait of the
+/// [`display-interface`](display_interface) crate, which makes it possible to use unchanged
+/// display driver crates with the simulator. The driver usually takes ownership of the interface,
+/// which is why the controller is shared with the interface by using an [`Rc<RefCell<C>>`]. A
+/// handle to the controller can be obtained by calling [`controller`](Self::controller) before
+/// the interface is passed to the driver.
+///
+/// 16 bit data is converted to bytes in the byte order requested by the driver.
+///
+/// This type requires the `display-interface` feature.
+///
+/// ```
+/// use embedded_graphics::prelude::*;
+/// use embedded_graphics_simulator::controller::{Controller, SimulatorInterface, Ssd1306};
+///
+/// let interface = SimulatorInterface::new(Ssd1306::new(Size::new(128, 64)));
+/// let controller = interface.controller();
+///
+/// // Pass `interface` to the display driver and use the driver to draw to the display.
+///
+/// let display = controller.borrow_mut().display().clone();
+/// ```
+#[derive(Debug)]
+pub struct SimulatorInterface<C> {
+    controller: Rc<RefCell<C>>,
+}
+
+impl<C: Controller> SimulatorInterface<C> {
+    /// Creates a new interface for the given controller.
+    pub fn new(controller: C) -> Self {
+        Self {
+            controller: Rc::new(RefCell::new(controller)),
+        }
+    }
+
+    /// Creates a new interface for a controller that is shared with other interfaces.
+    pub fn from_shared(controller: Rc<RefCell<C>>) -> Self {
+        Self { controller }
+    }
+
+    /// Returns a handle to the controller.
+    pub fn controller(&self) -> Rc<RefCell<C>> {
+        Rc::clone(&self.controller)
+    }
+}
+
+/// Calls `f` with the bytes in a data format.
+fn with_bytes(data: DataFormat<'_>, mut f: impl FnMut(&[u8])) -> Result<(), DisplayError> {
+    match data {
+        DataFormat::U8(bytes) => f(bytes),
+        DataFormat::U16(words) => words.iter().for_each(|word| f(&word.to_ne_bytes())),
+        DataFormat::U16BE(words) => words.iter().for_each(|word| f(&word.to_be_bytes())),
+        DataFormat::U16LE(words) => words.iter().for_each(|word| f(&word.to_le_bytes())),
+        DataFormat::U8Iter(iter) => iter.for_each(|byte| f(&[byte])),
+        DataFormat::U16BEIter(iter) => iter.for_each(|word| f(&word.to_be_bytes())),
+        DataFormat::U16LEIter(iter) => iter.for_each(|word| f(&word.to_le_bytes())),
+        _ => return Err(DisplayError::DataFormatNotImplemented),
+    }
+
+    Ok(())
+}
+
+impl<C: Controller> WriteOnlyDataCommand for SimulatorInterface<C> {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let mut controller = self.controller.borrow_mut();
+
+        with_bytes(cmd, |bytes| controller.write_commands(bytes))
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let mut controller = self.controller.borrow_mut();
+
+        with_bytes(buf, |bytes| controller.write_data(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+
+    use super::*;
+    use crate::controller::MipiDcs;
+
+    fn pixels(interface: &SimulatorInterface<MipiDcs<Rgb565>>) -> Vec<Rgb565> {
+        let controller = interface.controller();
+        let mut controller = controller.borrow_mut();
+        let display = controller.display();
+
+        display
+            .bounding_box()
+            .points()
+            .map(|p| display.get_pixel(p))
+            .collect()
+    }
+
+    #[test]
+    fn data_formats() {
+        let mut interface = SimulatorInterface::new(MipiDcs::<Rgb565>::new(Size::new(6, 1)));
+        interface
+            .send_commands(DataFormat::U8(&[0x11, 0x29, 0x3A]))
+            .unwrap();
+        interface.send_data(DataFormat::U8(&[0x55])).unwrap();
+        interface.send_commands(DataFormat::U8(&[0x2C])).unwrap();
+
+        let red = Rgb565::RED.into_storage();
+        let green = Rgb565::GREEN.into_storage();
+        let blue = Rgb565::BLUE.into_storage();
+
+        interface
+            .send_data(DataFormat::U8(&red.to_be_bytes()))
+            .unwrap();
+        interface
+            .send_data(DataFormat::U16BE(&mut [green]))
+            .unwrap();
+        interface
+            .send_data(DataFormat::U16LE(&mut [blue.swap_bytes()]))
+            .unwrap();
+        interface
+            .send_data(DataFormat::U16(&[u16::from_be(red)]))
+            .unwrap();
+        interface
+            .send_data(DataFormat::U16BEIter(&mut [green].into_iter()))
+            .unwrap();
+        interface
+            .send_data(DataFormat::U8Iter(&mut blue.to_be_bytes().into_iter()))
+            .unwrap();
+
+        assert_eq!(
+            pixels(&interface),
+            [
+                Rgb565::RED,
+                Rgb565::GREEN,
+                Rgb565::BLUE,
+                Rgb565::RED,
+                Rgb565::GREEN,
+                Rgb565::BLUE
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_controller() {
+        let interface = SimulatorInterface::new(MipiDcs::<Rgb565>::new(Size::new(1, 1)));
+        let mut other = SimulatorInterface::from_shared(interface.controller());
+
+        other.send_commands(DataFormat::U8(&[0x11, 0x29])).unwrap();
+
+        assert!(interface.controller().borrow().is_display_on());
+    }
+}
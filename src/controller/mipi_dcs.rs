@@ -0,0 +1,616 @@
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Rgb565, Rgb666},
+    prelude::*,
+};
+
+use crate::{controller::Controller, display::SimulatorDisplay};
+
+/// `MADCTL` bit that reverses the row address order.
+const MADCTL_MY: u8 = 0x80;
+/// `MADCTL` bit that reverses the column address order.
+const MADCTL_MX: u8 = 0x40;
+/// `MADCTL` bit that exchanges rows and columns.
+const MADCTL_MV: u8 = 0x20;
+/// `MADCTL` bit that selects the BGR color order.
+const MADCTL_BGR: u8 = 0x08;
+
+/// Pixel format of the data written to the display RAM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PixelFormat {
+    /// 16 bit per pixel, transferred as 2 bytes.
+    Rgb565,
+    /// 18 bit per pixel, transferred as 3 bytes with the color in the upper 6 bits.
+    Rgb666,
+}
+
+impl PixelFormat {
+    /// Returns the number of bytes per pixel.
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgb565 => 2,
+            PixelFormat::Rgb666 => 3,
+        }
+    }
+
+    /// Decodes the bytes of one pixel.
+    fn decode(self, bytes: &[u8]) -> Rgb666 {
+        match self {
+            PixelFormat::Rgb565 => {
+                let raw = u16::from_be_bytes([bytes[0], bytes[1]]);
+                let r = (raw >> 11) as u8;
+                let g = (raw >> 5) as u8 & 0x3F;
+                let b = raw as u8 & 0x1F;
+
+                // The controller expands 5 bit channels by copying the MSB into the LSB.
+                Rgb666::new(r << 1 | r >> 4, g, b << 1 | b >> 4)
+            }
+            PixelFormat::Rgb666 => Rgb666::new(bytes[0] >> 2, bytes[1] >> 2, bytes[2] >> 2),
+        }
+    }
+}
+
+/// Emulated MIPI DCS display controller, like the ILI9341 or ST7789.
+///
+/// The emulator maintains the display RAM and supports the commands that are used by typical
+/// display drivers to configure the controller and to write pixel data:
+///
+/// | Command | Description |
+/// |---------|-------------|
+/// | `0x01` `SWRESET` | software reset |
+/// | `0x10`/`0x11` `SLPIN`/`SLPOUT` | enter and exit sleep mode |
+/// | `0x20`/`0x21` `INVOFF`/`INVON` | color inversion |
+/// | `0x28`/`0x29` `DISPOFF`/`DISPON` | turn the display off or on |
+/// | `0x2A`/`0x2B` `CASET`/`RASET` | set the column and row address window |
+/// | `0x2C`/`0x3C` `RAMWR`/`RAMWRC` | write pixel data, starting at the window start or continuing at the current position |
+/// | `0x36` `MADCTL` | memory access control: row/column order and exchange, BGR order |
+/// | `0x38`/`0x39` `IDMOFF`/`IDMON` | idle mode with 8 colors |
+/// | `0x3A` `COLMOD` | 16 bit (`0x55`) or 18 bit (`0x66`) pixel format |
+///
+/// Other commands are accepted and ignored. Commands are sent with
+/// [`write_commands`](Controller::write_commands) and their parameters and pixel data with
+/// [`write_data`](Controller::write_data), like on a controller with a D/C signal.
+///
+/// By default the emulated panel shows the display RAM without mirroring, uses the RGB color order
+/// and isn't inverted. Panels that are wired differently, like the many ILI9341 modules with a BGR
+/// panel or ST7789 modules with an IPS panel that requires `INVON`, can be emulated by using
+/// [`with_panel_mirroring`](Self::with_panel_mirroring), [`with_bgr_panel`](Self::with_bgr_panel)
+/// and [`with_inverted_panel`](Self::with_inverted_panel). A display that is turned off or in
+/// sleep mode is shown as black.
+///
+/// The emulator can render to a [`SimulatorDisplay<Rgb565>`] or a [`SimulatorDisplay<Rgb666>`].
+/// Colors are stored with 18 bit per pixel internally, which means that 16 bit colors are shown
+/// unchanged in a `Rgb565` display.
+#[derive(Debug, Clone)]
+pub struct MipiDcs<C = Rgb565> {
+    size: Size,
+    ram: Vec<Rgb666>,
+
+    mirror_x: bool,
+    mirror_y: bool,
+    bgr_panel: bool,
+    inverted_panel: bool,
+
+    madctl: u8,
+    pixel_format: PixelFormat,
+    sleeping: bool,
+    display_on: bool,
+    inverted: bool,
+    idle: bool,
+    columns: (u16, u16),
+    rows: (u16, u16),
+    column: u16,
+    row: u16,
+
+    /// Current command.
+    command: Option<u8>,
+    /// Parameters of the current command or the bytes of an incomplete pixel.
+    parameters: Vec<u8>,
+    display: SimulatorDisplay<C>,
+}
+
+impl<C> MipiDcs<C>
+where
+    C: PixelColor + From<BinaryColor> + From<Rgb666>,
+{
+    /// Creates a new emulated MIPI DCS controller.
+    ///
+    /// The size is the size of the panel in its native orientation, e.g. 240x320 pixels for a
+    /// typical ILI9341 module. The controller is initialized to the reset state, which means that
+    /// it is in sleep mode with the display turned off.
+    pub fn new(size: Size) -> Self {
+        let mut controller = Self {
+            size,
+            ram: vec![Rgb666::BLACK; size.width as usize * size.height as usize],
+
+            mirror_x: false,
+            mirror_y: false,
+            bgr_panel: false,
+            inverted_panel: false,
+
+            madctl: 0,
+            pixel_format: PixelFormat::Rgb666,
+            sleeping: true,
+            display_on: false,
+            inverted: false,
+            idle: false,
+            columns: (0, 0),
+            rows: (0, 0),
+            column: 0,
+            row: 0,
+
+            command: None,
+            parameters: Vec::new(),
+            display: SimulatorDisplay::new(size),
+        };
+        controller.reset();
+
+        controller
+    }
+
+    /// Sets the mirroring of the emulated panel.
+    pub fn with_panel_mirroring(mut self, mirror_x: bool, mirror_y: bool) -> Self {
+        self.mirror_x = mirror_x;
+        self.mirror_y = mirror_y;
+
+        self
+    }
+
+    /// Sets if the emulated panel uses the BGR color order.
+    ///
+    /// Colors are only shown correctly on a BGR panel if the BGR bit in `MADCTL` is set.
+    pub fn with_bgr_panel(mut self, bgr_panel: bool) -> Self {
+        self.bgr_panel = bgr_panel;
+
+        self
+    }
+
+    /// Sets if the emulated panel inverts the colors.
+    ///
+    /// Colors are only shown correctly on an inverted panel if `INVON` is used.
+    pub fn with_inverted_panel(mut self, inverted_panel: bool) -> Self {
+        self.inverted_panel = inverted_panel;
+
+        self
+    }
+
+    /// Resets the controller, like a pulse on the reset pin.
+    ///
+    /// The display RAM content isn't changed by a reset.
+    pub fn reset(&mut self) {
+        self.madctl = 0;
+        self.pixel_format = PixelFormat::Rgb666;
+        self.sleeping = true;
+        self.display_on = false;
+        self.inverted = false;
+        self.idle = false;
+        self.columns = (0, self.size.width.saturating_sub(1) as u16);
+        self.rows = (0, self.size.height.saturating_sub(1) as u16);
+        self.column = 0;
+        self.row = 0;
+        self.command = None;
+        self.parameters.clear();
+    }
+
+    /// Returns the current `MADCTL` value.
+    pub fn madctl(&self) -> u8 {
+        self.madctl
+    }
+
+    /// Returns `true` if the display is turned on and not in sleep mode.
+    pub fn is_display_on(&self) -> bool {
+        self.display_on && !self.sleeping
+    }
+
+    /// Returns the size of the display RAM in the current memory access order.
+    fn logical_size(&self) -> (u16, u16) {
+        let (width, height) = (self.size.width as u16, self.size.height as u16);
+
+        if self.madctl & MADCTL_MV != 0 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Returns the display RAM index of a column and row in the current memory access order.
+    fn ram_index(&self, column: u16, row: u16) -> Option<usize> {
+        let (width, height) = self.logical_size();
+        if column >= width || row >= height {
+            return None;
+        }
+
+        let (mut x, mut y) = if self.madctl & MADCTL_MV != 0 {
+            (row, column)
+        } else {
+            (column, row)
+        };
+        if self.madctl & MADCTL_MX != 0 {
+            x = self.size.width as u16 - 1 - x;
+        }
+        if self.madctl & MADCTL_MY != 0 {
+            y = self.size.height as u16 - 1 - y;
+        }
+
+        Some(usize::from(y) * self.size.width as usize + usize::from(x))
+    }
+
+    fn execute(&mut self, command: u8) {
+        match command {
+            0x01 => self.reset(),
+            0x10 | 0x11 => self.sleeping = command == 0x10,
+            0x20 | 0x21 => self.inverted = command == 0x21,
+            0x28 | 0x29 => self.display_on = command == 0x29,
+            0x2C => {
+                self.column = self.columns.0;
+                self.row = self.rows.0;
+            }
+            0x38 | 0x39 => self.idle = command == 0x39,
+            _ => {}
+        }
+    }
+
+    fn write_parameter(&mut self, command: u8, byte: u8) {
+        self.parameters.push(byte);
+        let parameters = &self.parameters;
+
+        match (command, parameters.len()) {
+            (0x2A | 0x2B, 4) => {
+                let start = u16::from_be_bytes([parameters[0], parameters[1]]);
+                let end = u16::from_be_bytes([parameters[2], parameters[3]]);
+
+                if command == 0x2A {
+                    self.columns = (start, end);
+                } else {
+                    self.rows = (start, end);
+                }
+            }
+            (0x36, 1) => self.madctl = byte,
+            (0x3A, 1) => {
+                self.pixel_format = match byte & 0x07 {
+                    5 => PixelFormat::Rgb565,
+                    _ => PixelFormat::Rgb666,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn write_pixel_data(&mut self, byte: u8) {
+        self.parameters.push(byte);
+        if self.parameters.len() < self.pixel_format.bytes_per_pixel() {
+            return;
+        }
+
+        let color = self.pixel_format.decode(&self.parameters);
+        self.parameters.clear();
+
+        // Pixels outside of the display RAM are discarded.
+        if let Some(index) = self.ram_index(self.column, self.row) {
+            self.ram[index] = color;
+        }
+
+        if self.column >= self.columns.1 {
+            self.column = self.columns.0;
+            self.row = if self.row >= self.rows.1 {
+                self.rows.0
+            } else {
+                self.row + 1
+            };
+        } else {
+            self.column += 1;
+        }
+    }
+
+    /// Returns the color that is shown on the panel for a display RAM color.
+    fn output_color(&self, color: Rgb666) -> Rgb666 {
+        if !self.is_display_on() {
+            return Rgb666::BLACK;
+        }
+
+        let mut color = color;
+        if self.idle {
+            let msb = |c: u8| if c & 0x20 != 0 { 0x3F } else { 0 };
+            color = Rgb666::new(msb(color.r()), msb(color.g()), msb(color.b()));
+        }
+        if self.inverted != self.inverted_panel {
+            color = Rgb666::new(0x3F - color.r(), 0x3F - color.g(), 0x3F - color.b());
+        }
+        if (self.madctl & MADCTL_BGR != 0) != self.bgr_panel {
+            color = Rgb666::new(color.b(), color.g(), color.r());
+        }
+
+        color
+    }
+
+    fn render(&mut self) {
+        let width = self.size.width as usize;
+        let height = self.size.height as usize;
+
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let ram_x = if self.mirror_x { width - 1 - x } else { x };
+                let ram_y = if self.mirror_y { height - 1 - y } else { y };
+                let color = self.output_color(self.ram[ram_y * width + ram_x]);
+
+                Pixel(Point::new(x as i32, y as i32), C::from(color))
+            })
+            .filter(|Pixel(point, color)| self.display.get_pixel(*point) != *color)
+            .collect::<Vec<_>>();
+
+        self.display.draw_iter(pixels).unwrap();
+    }
+}
+
+impl<C> Controller for MipiDcs<C>
+where
+    C: PixelColor + From<BinaryColor> + From<Rgb666>,
+{
+    type Color = C;
+
+    fn write_commands(&mut self, commands: &[u8]) {
+        for command in commands {
+            self.parameters.clear();
+            self.command = Some(*command);
+            self.execute(*command);
+        }
+    }
+
+    fn write_data(&mut self, data: &[u8]) {
+        let Some(command) = self.command else {
+            return;
+        };
+
+        for byte in data {
+            match command {
+                0x2C | 0x3C => self.write_pixel_data(*byte),
+                _ => self.write_parameter(command, *byte),
+            }
+        }
+    }
+
+    fn display(&mut self) -> &SimulatorDisplay<C> {
+        self.render();
+
+        &self.display
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller(size: Size) -> MipiDcs<Rgb565> {
+        let mut controller = MipiDcs::new(size);
+        controller.write_commands(&[0x11, 0x3A]);
+        controller.write_data(&[0x55]);
+        controller.write_commands(&[0x29]);
+
+        controller
+    }
+
+    fn set_window(controller: &mut MipiDcs<Rgb565>, x: (u16, u16), y: (u16, u16)) {
+        controller.write_commands(&[0x2A]);
+        controller.write_data(&[x.0.to_be_bytes(), x.1.to_be_bytes()].concat());
+        controller.write_commands(&[0x2B]);
+        controller.write_data(&[y.0.to_be_bytes(), y.1.to_be_bytes()].concat());
+    }
+
+    fn write_pixels(controller: &mut MipiDcs<Rgb565>, colors: &[Rgb565]) {
+        let data = colors
+            .iter()
+            .flat_map(|color| color.into_storage().to_be_bytes())
+            .collect::<Vec<_>>();
+
+        controller.write_commands(&[0x2C]);
+        controller.write_data(&data);
+    }
+
+    fn pixels(controller: &mut MipiDcs<Rgb565>) -> Vec<Pixel<Rgb565>> {
+        let display = controller.display();
+
+        display
+            .bounding_box()
+            .points()
+            .map(|p| Pixel(p, display.get_pixel(p)))
+            .filter(|Pixel(_, color)| *color != Rgb565::BLACK)
+            .collect()
+    }
+
+    #[test]
+    fn display_off_after_reset() {
+        let mut controller = MipiDcs::<Rgb565>::new(Size::new(4, 3));
+        controller.write_commands(&[0x2C]);
+        controller.write_data(&[0xFF; 4 * 3 * 3]);
+
+        assert!(!controller.is_display_on());
+        assert_eq!(pixels(&mut controller), vec![]);
+
+        controller.write_commands(&[0x29]);
+        assert_eq!(pixels(&mut controller), vec![]);
+
+        controller.write_commands(&[0x11]);
+        assert_eq!(pixels(&mut controller).len(), 4 * 3);
+    }
+
+    #[test]
+    fn address_window() {
+        let mut controller = controller(Size::new(4, 3));
+        set_window(&mut controller, (1, 2), (1, 2));
+        write_pixels(
+            &mut controller,
+            &[Rgb565::RED, Rgb565::GREEN, Rgb565::BLUE, Rgb565::WHITE],
+        );
+
+        assert_eq!(
+            pixels(&mut controller),
+            vec![
+                Pixel(Point::new(1, 1), Rgb565::RED),
+                Pixel(Point::new(2, 1), Rgb565::GREEN),
+                Pixel(Point::new(1, 2), Rgb565::BLUE),
+                Pixel(Point::new(2, 2), Rgb565::WHITE),
+            ]
+        );
+
+        // RAMWR restarts at the window start and RAMWRC continues at the current position
+        write_pixels(&mut controller, &[Rgb565::YELLOW]);
+        controller.write_commands(&[0x3C]);
+        controller.write_data(&Rgb565::CYAN.into_storage().to_be_bytes());
+
+        assert_eq!(
+            controller.display().get_pixel(Point::new(1, 1)),
+            Rgb565::YELLOW
+        );
+        assert_eq!(
+            controller.display().get_pixel(Point::new(2, 1)),
+            Rgb565::CYAN
+        );
+    }
+
+    #[test]
+    fn window_outside_of_ram() {
+        let mut controller = controller(Size::new(4, 3));
+        set_window(&mut controller, (3, 4), (0, 0));
+        write_pixels(&mut controller, &[Rgb565::RED, Rgb565::GREEN]);
+
+        assert_eq!(
+            pixels(&mut controller),
+            vec![Pixel(Point::new(3, 0), Rgb565::RED)]
+        );
+    }
+
+    #[test]
+    fn madctl_rotation() {
+        let mut controller = controller(Size::new(4, 3));
+
+        // 90 degree clockwise rotation
+        controller.write_commands(&[0x36]);
+        controller.write_data(&[MADCTL_MV | MADCTL_MX]);
+        set_window(&mut controller, (0, 1), (0, 0));
+        write_pixels(&mut controller, &[Rgb565::RED, Rgb565::GREEN]);
+
+        assert_eq!(
+            pixels(&mut controller),
+            vec![
+                Pixel(Point::new(3, 0), Rgb565::RED),
+                Pixel(Point::new(3, 1), Rgb565::GREEN),
+            ]
+        );
+    }
+
+    #[test]
+    fn madctl_mirroring() {
+        let mut controller = controller(Size::new(4, 3));
+        controller.write_commands(&[0x36]);
+        controller.write_data(&[MADCTL_MX | MADCTL_MY]);
+        set_window(&mut controller, (0, 0), (0, 0));
+        write_pixels(&mut controller, &[Rgb565::RED]);
+
+        assert_eq!(
+            pixels(&mut controller),
+            vec![Pixel(Point::new(3, 2), Rgb565::RED)]
+        );
+    }
+
+    #[test]
+    fn panel_mirroring() {
+        let mut controller = controller(Size::new(4, 3)).with_panel_mirroring(true, false);
+        set_window(&mut controller, (0, 0), (0, 0));
+        write_pixels(&mut controller, &[Rgb565::RED]);
+
+        assert_eq!(
+            pixels(&mut controller),
+            vec![Pixel(Point::new(3, 0), Rgb565::RED)]
+        );
+    }
+
+    #[test]
+    fn bgr() {
+        let mut controller = controller(Size::new(1, 1));
+        write_pixels(&mut controller, &[Rgb565::RED]);
+
+        controller.write_commands(&[0x36]);
+        controller.write_data(&[MADCTL_BGR]);
+        assert_eq!(controller.display().get_pixel(Point::zero()), Rgb565::BLUE);
+
+        let mut controller = controller.with_bgr_panel(true);
+        assert_eq!(controller.display().get_pixel(Point::zero()), Rgb565::RED);
+    }
+
+    #[test]
+    fn pixel_formats() {
+        let color = Rgb565::new(0x15, 0x2A, 0x0B);
+        let data_16bit = color.into_storage().to_be_bytes();
+        let data_18bit = [0x10 << 2, 0x20 << 2, 0x30 << 2];
+
+        let mut rgb565 = controller(Size::new(2, 1));
+        rgb565.write_commands(&[0x2C]);
+        rgb565.write_data(&data_16bit);
+        rgb565.write_commands(&[0x3A]);
+        rgb565.write_data(&[0x66]);
+        rgb565.write_commands(&[0x3C]);
+        rgb565.write_data(&data_18bit);
+
+        let display = rgb565.display();
+        assert_eq!(display.get_pixel(Point::new(0, 0)), color);
+        assert_eq!(
+            display.get_pixel(Point::new(1, 0)),
+            Rgb565::new(0x08, 0x20, 0x18)
+        );
+
+        let mut rgb666 = MipiDcs::<Rgb666>::new(Size::new(2, 1));
+        rgb666.write_commands(&[0x11, 0x29, 0x3A]);
+        rgb666.write_data(&[0x55]);
+        rgb666.write_commands(&[0x2C]);
+        rgb666.write_data(&data_16bit);
+        rgb666.write_commands(&[0x3A]);
+        rgb666.write_data(&[0x66]);
+        rgb666.write_commands(&[0x3C]);
+        rgb666.write_data(&data_18bit);
+
+        let display = rgb666.display();
+        assert_eq!(
+            display.get_pixel(Point::new(0, 0)),
+            Rgb666::new(0x2B, 0x2A, 0x16)
+        );
+        assert_eq!(
+            display.get_pixel(Point::new(1, 0)),
+            Rgb666::new(0x10, 0x20, 0x30)
+        );
+    }
+
+    #[test]
+    fn inversion_and_idle_mode() {
+        let mut controller = controller(Size::new(1, 1));
+        write_pixels(&mut controller, &[Rgb565::new(0x10, 0x10, 0x0F)]);
+
+        controller.write_commands(&[0x39]);
+        assert_eq!(
+            controller.display().get_pixel(Point::zero()),
+            Rgb565::new(0x1F, 0x00, 0x00)
+        );
+
+        controller.write_commands(&[0x21]);
+        assert_eq!(
+            controller.display().get_pixel(Point::zero()),
+            Rgb565::new(0x00, 0x3F, 0x1F)
+        );
+
+        let mut controller = controller.with_inverted_panel(true);
+        assert_eq!(
+            controller.display().get_pixel(Point::zero()),
+            Rgb565::new(0x1F, 0x00, 0x00)
+        );
+    }
+
+    #[test]
+    fn software_reset() {
+        let mut controller = controller(Size::new(1, 1));
+        controller.write_commands(&[0x36]);
+        controller.write_data(&[0xFF]);
+        controller.write_commands(&[0x01]);
+
+        assert_eq!(controller.madctl(), 0);
+        assert!(!controller.is_display_on());
+    }
+}
@@ -0,0 +1,53 @@
+//! Display controller emulation.
+//!
+//! The controllers in this module emulate the command set of common display controllers, which
+//! makes it possible to test display drivers that send raw command and data bytes to the
+//! display. The emulated display content can be shown by passing the display returned by
+//! [`Controller::display`] to [`Window::update`](crate::Window::update).
+//!
+//! Display drivers that use the `display-interface` crate can be connected to an emulated
+//! controller by using `SimulatorInterface`, which requires the `display-interface` feature.
+//!
+//! ```
+//! use embedded_graphics::prelude::*;
+//! use embedded_graphics_simulator::controller::{Controller, Ssd1306};
+//!
+//! let mut controller = Ssd1306::new(Size::new(128, 64));
+//!
+//! // display on, horizontal addressing mode
+//! controller.write_commands(&[0xAF, 0x20, 0x00]);
+//! controller.write_data(&[0xFF; 128 * 8]);
+//!
+//! let display = controller.display();
+//! ```
+
+use embedded_graphics::prelude::*;
+
+use crate::display::SimulatorDisplay;
+
+#[cfg(feature = "display-interface")]
+mod interface;
+mod mipi_dcs;
+mod ssd1306;
+
+#[cfg(feature = "display-interface")]
+pub use interface::SimulatorInterface;
+pub use mipi_dcs::MipiDcs;
+pub use ssd1306::Ssd1306;
+
+/// Emulated display controller.
+pub trait Controller {
+    /// Color type of the emulated display.
+    type Color: PixelColor;
+
+    /// Handles bytes that were sent in command mode, e.g. with the D/C signal low.
+    ///
+    /// Commands can be split across multiple calls.
+    fn write_commands(&mut self, commands: &[u8]);
+
+    /// Handles bytes that were sent in data mode, e.g. with the D/C signal high.
+    fn write_data(&mut self, data: &[u8]);
+
+    /// Returns the display with the content that is currently shown by the emulated panel.
+    fn display(&mut self) -> &SimulatorDisplay<Self::Color>;
+}
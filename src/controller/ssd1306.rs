@@ -0,0 +1,684 @@
+use std::time::Duration;
+
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Rgb888},
+    prelude::*,
+};
+
+use crate::{controller::Controller, display::SimulatorDisplay, theme::BinaryColorTheme};
+
+/// Number of columns in the display RAM.
+const COLUMNS: usize = 128;
+/// Number of pages in the display RAM.
+const PAGES: usize = 8;
+/// Number of rows in the display RAM.
+const ROWS: u8 = 64;
+
+/// Frequency of the internal oscillator in Hz.
+const OSCILLATOR_FREQUENCY: f64 = 370_000.0;
+
+/// Number of frames between scroll steps, indexed by the time interval setting.
+const SCROLL_INTERVALS: [u32; 8] = [5, 64, 128, 256, 3, 4, 25, 2];
+
+/// Memory addressing mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AddressingMode {
+    Horizontal,
+    Vertical,
+    Page,
+}
+
+/// Continuous scroll settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Scroll {
+    /// Scrolls to the left if `true`.
+    left: bool,
+    start_page: usize,
+    end_page: usize,
+    /// Number of frames between scroll steps.
+    interval: u32,
+    /// Number of rows the display is scrolled vertically in each step.
+    vertical_offset: u8,
+}
+
+/// Configuration of the COM and SEG outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OutputConfig {
+    segment_remap: bool,
+    com_remap: bool,
+    /// COM pins hardware configuration, as set by the `0xDA` command.
+    com_pins: u8,
+}
+
+impl OutputConfig {
+    /// Returns the COM pin that is driven by a row counter value.
+    fn com_pin(&self, row: u8, multiplex_ratio: u8) -> u8 {
+        let index = if self.com_remap {
+            multiplex_ratio - 1 - row
+        } else {
+            row
+        };
+
+        let pin = if self.com_pins & 0x10 != 0 {
+            // alternative COM pin configuration
+            if index % 2 == 0 {
+                index / 2
+            } else {
+                ROWS / 2 + index / 2
+            }
+        } else {
+            index
+        };
+
+        if self.com_pins & 0x20 != 0 {
+            // COM left/right remap
+            (pin + ROWS / 2) % ROWS
+        } else {
+            pin
+        }
+    }
+}
+
+/// Emulated SSD1306 OLED controller.
+///
+/// The emulator maintains the 128x64 pixel display RAM (GDDRAM) and supports the fundamental,
+/// addressing, scrolling and hardware configuration commands of the SSD1306. Commands and their
+/// arguments are sent with [`write_commands`](Controller::write_commands) and display RAM data
+/// with [`write_data`](Controller::write_data).
+///
+/// The emulated panel is wired like a typical SSD1306 module, which shows an upright image if the
+/// segment remap (`0xA1`) and reversed COM scan direction (`0xC8`) commands are used. The default
+/// COM pin configuration of the panel is the alternative configuration (`0xDA 0x12`) for panels
+/// that are higher than 32 pixels and the sequential configuration (`0xDA 0x02`) otherwise. If a
+/// driver uses different settings the image is mirrored or garbled in the same way as on a real
+/// module. [`with_panel_config`](Self::with_panel_config) can be used to emulate modules with a
+/// different wiring.
+///
+/// Continuous scrolling is emulated by calling [`advance`](Self::advance) with the elapsed time,
+/// e.g. by using [`Window::frame_time`](crate::Window::frame_time). Like on the real controller,
+/// horizontal scrolling modifies the display RAM content.
+///
+/// The contrast setting (`0x81`) can't be represented by [`BinaryColor`] and isn't included in the
+/// display returned by [`display`](Controller::display). A window that shows this display ignores
+/// the contrast and always uses the full brightness of its theme, unless the theme is updated with
+/// [`apply_contrast`](Self::apply_contrast) before each update.
+#[derive(Debug, Clone)]
+pub struct Ssd1306 {
+    size: Size,
+    ram: [[u8; COLUMNS]; PAGES],
+
+    addressing_mode: AddressingMode,
+    column: usize,
+    page: usize,
+    column_range: (usize, usize),
+    page_range: (usize, usize),
+
+    display_on: bool,
+    entire_display_on: bool,
+    inverted: bool,
+    contrast: u8,
+    start_line: u8,
+    display_offset: u8,
+    multiplex_ratio: u8,
+    config: OutputConfig,
+    panel_config: OutputConfig,
+    clock_divide_ratio: u8,
+    precharge_period: u8,
+
+    scroll: Option<Scroll>,
+    scroll_active: bool,
+    /// Fixed rows and scrolled rows of the vertical scroll area.
+    vertical_scroll_area: (u8, u8),
+    vertical_scroll: u8,
+    /// Number of display frames since the last scroll step.
+    scroll_frames: f64,
+
+    /// Partially received command.
+    command: Vec<u8>,
+    display: SimulatorDisplay<BinaryColor>,
+}
+
+impl Ssd1306 {
+    /// Creates a new emulated SSD1306 controller.
+    ///
+    /// The controller is initialized to the reset state described in the datasheet, which means
+    /// that the display is turned off.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is larger than 128x64 pixels.
+    pub fn new(size: Size) -> Self {
+        assert!(
+            size.width as usize <= COLUMNS && size.height <= ROWS as u32,
+            "SSD1306 displays can't be larger than 128x64 pixels"
+        );
+
+        Self {
+            size,
+            ram: [[0; COLUMNS]; PAGES],
+
+            addressing_mode: AddressingMode::Page,
+            column: 0,
+            page: 0,
+            column_range: (0, COLUMNS - 1),
+            page_range: (0, PAGES - 1),
+
+            display_on: false,
+            entire_display_on: false,
+            inverted: false,
+            contrast: 0x7F,
+            start_line: 0,
+            display_offset: 0,
+            multiplex_ratio: ROWS,
+            config: OutputConfig {
+                segment_remap: false,
+                com_remap: false,
+                com_pins: 0x12,
+            },
+            panel_config: OutputConfig {
+                segment_remap: true,
+                com_remap: true,
+                com_pins: if size.height > 32 { 0x12 } else { 0x02 },
+            },
+            clock_divide_ratio: 0x80,
+            precharge_period: 0x22,
+
+            scroll: None,
+            scroll_active: false,
+            vertical_scroll_area: (0, ROWS),
+            vertical_scroll: 0,
+            scroll_frames: 0.0,
+
+            command: Vec::new(),
+            display: SimulatorDisplay::new(size),
+        }
+    }
+
+    /// Sets the wiring of the emulated panel.
+    ///
+    /// The parameters are the settings that result in an upright image on the panel: the segment
+    /// remap (`0xA0`/`0xA1`), the COM scan direction (`0xC0`/`0xC8`) and the COM pins hardware
+    /// configuration (`0xDA`).
+    pub fn with_panel_config(mut self, segment_remap: bool, com_remap: bool, com_pins: u8) -> Self {
+        self.panel_config = OutputConfig {
+            segment_remap,
+            com_remap,
+            com_pins,
+        };
+
+        self
+    }
+
+    /// Returns the current contrast setting.
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    /// Returns `true` if the display is turned on.
+    pub fn is_display_on(&self) -> bool {
+        self.display_on
+    }
+
+    /// Applies the contrast setting to a binary color theme.
+    ///
+    /// Returns a custom theme with the "on" color dimmed according to the current contrast
+    /// setting. The brightness is approximated as a linear function of the contrast, with the
+    /// lowest contrast setting still being visible like on a real OLED panel.
+    pub fn apply_contrast(&self, theme: BinaryColorTheme) -> BinaryColorTheme {
+        let color_off = theme.convert(Rgb888::BLACK);
+        let color_on = theme.convert(Rgb888::WHITE);
+
+        let brightness = 0.2 + 0.8 * f32::from(self.contrast) / 255.0;
+        let mix = |off: u8, on: u8| {
+            (f32::from(off) + (f32::from(on) - f32::from(off)) * brightness).round() as u8
+        };
+
+        BinaryColorTheme::Custom {
+            color_off,
+            color_on: Rgb888::new(
+                mix(color_off.r(), color_on.r()),
+                mix(color_off.g(), color_on.g()),
+                mix(color_off.b(), color_on.b()),
+            ),
+        }
+    }
+
+    /// Advances the continuous scrolling by the given time.
+    ///
+    /// The display frame rate is calculated from the clock divide ratio, pre-charge period and
+    /// multiplex ratio settings, assuming the typical oscillator frequency.
+    pub fn advance(&mut self, time: Duration) {
+        let Some(scroll) = self.scroll.filter(|_| self.scroll_active) else {
+            return;
+        };
+
+        self.scroll_frames += time.as_secs_f64() * self.frame_rate();
+        while self.scroll_frames >= f64::from(scroll.interval) {
+            self.scroll_frames -= f64::from(scroll.interval);
+            self.scroll_step(&scroll);
+        }
+    }
+
+    /// Returns the display frame rate in Hz.
+    fn frame_rate(&self) -> f64 {
+        let divide_ratio = f64::from((self.clock_divide_ratio & 0x0F) + 1);
+        let phase_1 = f64::from(self.precharge_period & 0x0F);
+        let phase_2 = f64::from(self.precharge_period >> 4);
+
+        OSCILLATOR_FREQUENCY
+            / (divide_ratio * (phase_1 + phase_2 + 50.0) * f64::from(self.multiplex_ratio))
+    }
+
+    fn scroll_step(&mut self, scroll: &Scroll) {
+        for page in scroll.start_page..=scroll.end_page.max(scroll.start_page) {
+            if scroll.left {
+                self.ram[page].rotate_left(1);
+            } else {
+                self.ram[page].rotate_right(1);
+            }
+        }
+
+        let (_, scroll_rows) = self.vertical_scroll_area;
+        if scroll_rows > 0 {
+            self.vertical_scroll = (self.vertical_scroll + scroll.vertical_offset) % scroll_rows;
+        }
+    }
+
+    /// Returns the number of arguments of a command.
+    fn argument_count(command: u8) -> usize {
+        match command {
+            0x20 | 0x81 | 0x8D | 0xA8 | 0xD3 | 0xD5 | 0xD9 | 0xDA | 0xDB => 1,
+            0x21 | 0x22 | 0xA3 => 2,
+            0x29 | 0x2A => 5,
+            0x26 | 0x27 => 6,
+            _ => 0,
+        }
+    }
+
+    fn execute(&mut self, command: u8, args: &[u8]) {
+        match command {
+            0x00..=0x0F => self.column = (self.column & 0xF0) | usize::from(command & 0x0F),
+            0x10..=0x1F => {
+                self.column = ((usize::from(command & 0x07) << 4) | (self.column & 0x0F)) % COLUMNS
+            }
+            0x20 => {
+                self.addressing_mode = match args[0] & 0x03 {
+                    0 => AddressingMode::Horizontal,
+                    1 => AddressingMode::Vertical,
+                    2 => AddressingMode::Page,
+                    _ => self.addressing_mode,
+                }
+            }
+            0x21 => {
+                self.column_range = (usize::from(args[0] & 0x7F), usize::from(args[1] & 0x7F));
+                self.column = self.column_range.0;
+            }
+            0x22 => {
+                self.page_range = (usize::from(args[0] & 0x07), usize::from(args[1] & 0x07));
+                self.page = self.page_range.0;
+            }
+            0x26 | 0x27 | 0x29 | 0x2A => {
+                self.scroll = Some(Scroll {
+                    left: command == 0x27 || command == 0x2A,
+                    start_page: usize::from(args[1] & 0x07),
+                    end_page: usize::from(args[3] & 0x07),
+                    interval: SCROLL_INTERVALS[usize::from(args[2] & 0x07)],
+                    vertical_offset: if command >= 0x29 { args[4] & 0x3F } else { 0 },
+                });
+                self.scroll_frames = 0.0;
+            }
+            0x2E => self.scroll_active = false,
+            0x2F => self.scroll_active = true,
+            0x40..=0x7F => self.start_line = command & 0x3F,
+            0x81 => self.contrast = args[0],
+            0xA0 | 0xA1 => self.config.segment_remap = command == 0xA1,
+            0xA3 => {
+                self.vertical_scroll_area = (args[0] & 0x3F, args[1] & 0x7F);
+                self.vertical_scroll = 0;
+            }
+            0xA4 | 0xA5 => self.entire_display_on = command == 0xA5,
+            0xA6 | 0xA7 => self.inverted = command == 0xA7,
+            // multiplex ratios below 16 are invalid
+            0xA8 if args[0] & 0x3F >= 15 => self.multiplex_ratio = (args[0] & 0x3F) + 1,
+            0xAE | 0xAF => self.display_on = command == 0xAF,
+            0xB0..=0xB7 => self.page = usize::from(command & 0x07),
+            0xC0..=0xCF => self.config.com_remap = command & 0x08 != 0,
+            0xD3 => self.display_offset = args[0] & 0x3F,
+            0xD5 => self.clock_divide_ratio = args[0],
+            0xD9 => self.precharge_period = args[0],
+            0xDA => self.config.com_pins = args[0] & 0x32,
+            // charge pump, VCOMH deselect level, NOP and unsupported commands
+            _ => {}
+        }
+    }
+
+    /// Advances the RAM address pointers after a data byte was written.
+    fn advance_address(&mut self) {
+        let (column_start, column_end) = self.column_range;
+        let (page_start, page_end) = self.page_range;
+
+        match self.addressing_mode {
+            AddressingMode::Page => self.column = (self.column + 1) % COLUMNS,
+            AddressingMode::Horizontal => {
+                if self.column >= column_end {
+                    self.column = column_start;
+                    self.page = if self.page >= page_end {
+                        page_start
+                    } else {
+                        self.page + 1
+                    };
+                } else {
+                    self.column += 1;
+                }
+            }
+            AddressingMode::Vertical => {
+                if self.page >= page_end {
+                    self.page = page_start;
+                    self.column = if self.column >= column_end {
+                        column_start
+                    } else {
+                        self.column + 1
+                    };
+                } else {
+                    self.page += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the display RAM row that is shown on each panel row.
+    fn row_mapping(&self) -> Vec<Option<u8>> {
+        let mut rows_by_pin = [None; ROWS as usize];
+        for row in 0..self.multiplex_ratio {
+            rows_by_pin[usize::from(self.config.com_pin(row, self.multiplex_ratio))] = Some(row);
+        }
+
+        let (fixed_rows, scroll_rows) = self.vertical_scroll_area;
+
+        (0..self.size.height as u8)
+            .map(|y| {
+                let pin = self.panel_config.com_pin(y, self.size.height as u8);
+                let row = rows_by_pin[usize::from(pin)]?;
+                let mut ram_row = (row + self.start_line + self.display_offset) % ROWS;
+
+                if self.vertical_scroll > 0
+                    && ram_row >= fixed_rows
+                    && ram_row < fixed_rows.saturating_add(scroll_rows)
+                {
+                    ram_row =
+                        (ram_row - fixed_rows + self.vertical_scroll) % scroll_rows + fixed_rows;
+                }
+
+                Some(ram_row % ROWS)
+            })
+            .collect()
+    }
+
+    fn render(&mut self) {
+        let rows = self.row_mapping();
+        let width = self.size.width as usize;
+        let mirrored = self.config.segment_remap != self.panel_config.segment_remap;
+
+        let pixels = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| (0..width).map(move |x| (x, y, *row)))
+            .map(|(x, y, row)| {
+                let column = if mirrored { COLUMNS - 1 - x } else { x };
+                let on = row.is_some_and(|row| {
+                    let bit = self.ram[usize::from(row / 8)][column] & (1 << (row % 8)) != 0;
+
+                    self.entire_display_on || bit != self.inverted
+                });
+
+                Pixel(
+                    Point::new(x as i32, y as i32),
+                    BinaryColor::from(on && self.display_on),
+                )
+            })
+            .filter(|Pixel(point, color)| self.display.get_pixel(*point) != *color)
+            .collect::<Vec<_>>();
+
+        self.display.draw_iter(pixels).unwrap();
+    }
+}
+
+impl Controller for Ssd1306 {
+    type Color = BinaryColor;
+
+    fn write_commands(&mut self, commands: &[u8]) {
+        for byte in commands {
+            self.command.push(*byte);
+
+            let command = self.command[0];
+            if self.command.len() > Self::argument_count(command) {
+                let args = std::mem::take(&mut self.command);
+                self.execute(command, &args[1..]);
+            }
+        }
+    }
+
+    fn write_data(&mut self, data: &[u8]) {
+        for byte in data {
+            self.ram[self.page][self.column] = *byte;
+            self.advance_address();
+        }
+    }
+
+    fn display(&mut self) -> &SimulatorDisplay<BinaryColor> {
+        self.render();
+
+        &self.display
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Initialization sequence used by the `ssd1306` crate for 128x64 displays.
+    const INIT: &[u8] = &[
+        0xAE, 0xD5, 0x80, 0xA8, 0x3F, 0xD3, 0x00, 0x40, 0x8D, 0x14, 0x20, 0x00, 0xA1, 0xC8, 0xDA,
+        0x12, 0x81, 0xCF, 0xD9, 0xF1, 0xDB, 0x40, 0xA4, 0xA6, 0x2E, 0xAF,
+    ];
+
+    fn lit_pixels(controller: &mut Ssd1306) -> Vec<Point> {
+        let display = controller.display();
+
+        display
+            .bounding_box()
+            .points()
+            .filter(|p| display.get_pixel(*p) == BinaryColor::On)
+            .collect()
+    }
+
+    /// Creates a controller that shows a single pixel at the top left corner of the RAM.
+    fn single_pixel(commands: &[u8]) -> Ssd1306 {
+        let mut controller = Ssd1306::new(Size::new(128, 64));
+        controller.write_commands(INIT);
+        controller.write_commands(commands);
+        controller.write_commands(&[0x21, 0, 127, 0x22, 0, 7]);
+        controller.write_data(&[0x01]);
+
+        controller
+    }
+
+    #[test]
+    fn display_off_after_reset() {
+        let mut controller = Ssd1306::new(Size::new(128, 64));
+        controller.write_data(&[0xFF; 128]);
+
+        assert!(!controller.is_display_on());
+        assert_eq!(lit_pixels(&mut controller), vec![]);
+    }
+
+    #[test]
+    fn horizontal_addressing() {
+        let mut controller = Ssd1306::new(Size::new(128, 64));
+        controller.write_commands(INIT);
+        controller.write_commands(&[0x21, 10, 11, 0x22, 2, 3]);
+        controller.write_data(&[0x01, 0x02, 0x04, 0x08, 0x10]);
+
+        assert_eq!(
+            lit_pixels(&mut controller),
+            vec![
+                Point::new(11, 17),
+                // the address wraps to the start and overwrites the first byte
+                Point::new(10, 20),
+                Point::new(10, 26),
+                Point::new(11, 27),
+            ]
+        );
+    }
+
+    #[test]
+    fn vertical_addressing() {
+        let mut controller = Ssd1306::new(Size::new(128, 64));
+        controller.write_commands(INIT);
+        controller.write_commands(&[0x20, 0x01, 0x21, 0, 127, 0x22, 0, 1]);
+        controller.write_data(&[0x01, 0x01, 0x01]);
+
+        assert_eq!(
+            lit_pixels(&mut controller),
+            vec![Point::new(0, 0), Point::new(1, 0), Point::new(0, 8)]
+        );
+    }
+
+    #[test]
+    fn page_addressing() {
+        let mut controller = Ssd1306::new(Size::new(128, 64));
+        controller.write_commands(INIT);
+        controller.write_commands(&[0x20, 0x02, 0xB3, 0x05, 0x11]);
+        controller.write_data(&[0x80]);
+
+        assert_eq!(lit_pixels(&mut controller), vec![Point::new(21, 31)]);
+    }
+
+    #[test]
+    fn upright_image() {
+        assert_eq!(lit_pixels(&mut single_pixel(&[])), vec![Point::new(0, 0)]);
+    }
+
+    #[test]
+    fn segment_remap() {
+        assert_eq!(
+            lit_pixels(&mut single_pixel(&[0xA0])),
+            vec![Point::new(127, 0)]
+        );
+    }
+
+    #[test]
+    fn com_scan_direction() {
+        assert_eq!(
+            lit_pixels(&mut single_pixel(&[0xC0])),
+            vec![Point::new(0, 63)]
+        );
+    }
+
+    #[test]
+    fn com_pins_config() {
+        // sequential COM pins on a panel that requires the alternative configuration
+        let mut controller = single_pixel(&[0xDA, 0x02]);
+        controller.write_commands(&[0x21, 0, 127, 0x22, 0, 7]);
+        controller.write_data(&[0x03]);
+
+        assert_eq!(
+            lit_pixels(&mut controller),
+            vec![Point::new(0, 0), Point::new(0, 2)]
+        );
+    }
+
+    #[test]
+    fn start_line_and_offset() {
+        assert_eq!(
+            lit_pixels(&mut single_pixel(&[0x48])),
+            vec![Point::new(0, 56)]
+        );
+        assert_eq!(
+            lit_pixels(&mut single_pixel(&[0xD3, 4])),
+            vec![Point::new(0, 60)]
+        );
+    }
+
+    #[test]
+    fn multiplex_ratio() {
+        let mut controller = single_pixel(&[0xA8, 31]);
+        controller.write_commands(&[0x22, 7, 7]);
+        controller.write_data(&[0x80]);
+
+        // rows outside the multiplex ratio aren't driven and the remaining rows are
+        // driven by different COM pins
+        assert_eq!(lit_pixels(&mut controller), vec![Point::new(0, 32)]);
+    }
+
+    #[test]
+    fn invert_and_entire_display_on() {
+        let mut controller = single_pixel(&[0xA7]);
+        assert_eq!(lit_pixels(&mut controller).len(), 128 * 64 - 1);
+
+        controller.write_commands(&[0xA6, 0xA5]);
+        assert_eq!(lit_pixels(&mut controller).len(), 128 * 64);
+
+        controller.write_commands(&[0xAE]);
+        assert_eq!(lit_pixels(&mut controller), vec![]);
+    }
+
+    #[test]
+    fn commands_split_across_writes() {
+        let mut controller = single_pixel(&[]);
+        controller.write_commands(&[0x81]);
+        controller.write_commands(&[0x10]);
+
+        assert_eq!(controller.contrast(), 0x10);
+    }
+
+    #[test]
+    fn contrast() {
+        let mut controller = Ssd1306::new(Size::new(128, 64));
+
+        controller.write_commands(&[0x81, 0xFF]);
+        assert_eq!(
+            controller.apply_contrast(BinaryColorTheme::Default),
+            BinaryColorTheme::Custom {
+                color_off: Rgb888::BLACK,
+                color_on: Rgb888::WHITE
+            }
+        );
+
+        controller.write_commands(&[0x81, 0x00]);
+        assert_eq!(
+            controller.apply_contrast(BinaryColorTheme::Default),
+            BinaryColorTheme::Custom {
+                color_off: Rgb888::BLACK,
+                color_on: Rgb888::new(51, 51, 51)
+            }
+        );
+    }
+
+    #[test]
+    fn horizontal_scroll() {
+        // scroll page 0 to the right, one step every 2 frames
+        let mut controller = single_pixel(&[0x26, 0x00, 0x00, 0x07, 0x00, 0x00, 0xFF, 0x2F]);
+
+        let frame_time = Duration::from_secs_f64(1.0 / controller.frame_rate());
+        controller.advance(frame_time * 5);
+
+        assert_eq!(lit_pixels(&mut controller), vec![Point::new(2, 0)]);
+
+        controller.write_commands(&[0x2E]);
+        controller.advance(frame_time * 5);
+
+        assert_eq!(lit_pixels(&mut controller), vec![Point::new(2, 0)]);
+    }
+
+    #[test]
+    fn vertical_scroll() {
+        // scroll up by 1 row every 2 frames
+        let mut controller = single_pixel(&[0xA3, 0, 64, 0x29, 0x00, 0x07, 0x07, 0x07, 0x01, 0x2F]);
+
+        let frame_time = Duration::from_secs_f64(1.0 / controller.frame_rate());
+        controller.advance(frame_time * 3);
+
+        assert_eq!(lit_pixels(&mut controller), vec![Point::new(0, 63)]);
+    }
+}
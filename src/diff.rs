@@ -0,0 +1,429 @@
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use embedded_graphics::{pixelcolor::Rgb888, prelude::*, primitives::Rectangle};
+
+use crate::{display::SimulatorDisplay, output_settings::OutputSettings, snapshot::SnapshotError};
+
+/// Tolerance for fuzzy display comparisons.
+///
+/// Two pixels are considered equal if the difference of each color channel is at most
+/// [`max_channel_delta`](Self::max_channel_delta). Two images are considered equal if the number
+/// of differing pixels doesn't exceed [`max_differing_pixels`](Self::max_differing_pixels).
+///
+/// The default tolerance only accepts an exact match.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DiffTolerance {
+    /// Maximum allowed difference per color channel.
+    pub max_channel_delta: u8,
+    /// Maximum allowed number of differing pixels.
+    pub max_differing_pixels: PixelBudget,
+}
+
+impl DiffTolerance {
+    /// Reads the tolerance from the `EG_SIMULATOR_CHECK_TOLERANCE` and
+    /// `EG_SIMULATOR_CHECK_MAX_PIXELS` environment variables.
+    ///
+    /// Returns an error if one of the variables contains an invalid value.
+    pub(crate) fn from_env() -> Result<Self, SnapshotError> {
+        Self::parse(
+            env::var("EG_SIMULATOR_CHECK_TOLERANCE").ok().as_deref(),
+            env::var("EG_SIMULATOR_CHECK_MAX_PIXELS").ok().as_deref(),
+        )
+    }
+
+    /// Parses the values of the tolerance environment variables.
+    fn parse(
+        max_channel_delta: Option<&str>,
+        max_differing_pixels: Option<&str>,
+    ) -> Result<Self, SnapshotError> {
+        let invalid = |variable, value: &str| SnapshotError::InvalidTolerance {
+            variable,
+            value: value.to_string(),
+        };
+
+        let max_channel_delta = max_channel_delta
+            .map(|value| {
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("EG_SIMULATOR_CHECK_TOLERANCE", value))
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let max_differing_pixels = max_differing_pixels
+            .map(|value| {
+                PixelBudget::parse(value)
+                    .ok_or_else(|| invalid("EG_SIMULATOR_CHECK_MAX_PIXELS", value))
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Self {
+            max_channel_delta,
+            max_differing_pixels,
+        })
+    }
+}
+
+/// Maximum number of differing pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelBudget {
+    /// Absolute number of pixels.
+    Count(usize),
+    /// Percentage of the total number of pixels.
+    Percentage(f32),
+}
+
+impl PixelBudget {
+    /// Parses a pixel count (`10`) or a percentage (`0.5%`).
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        if let Some(percentage) = value.strip_suffix('%') {
+            percentage
+                .trim()
+                .parse()
+                .ok()
+                .filter(|percentage: &f32| (0.0..=100.0).contains(percentage))
+                .map(PixelBudget::Percentage)
+        } else {
+            value.parse().ok().map(PixelBudget::Count)
+        }
+    }
+}
+
+impl Default for PixelBudget {
+    fn default() -> Self {
+        Self::Count(0)
+    }
+}
+
+/// Statistics about the difference of two displays.
+///
+/// See [`SimulatorDisplay::diff_with_tolerance`](crate::SimulatorDisplay::diff_with_tolerance)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffStatistics {
+    /// Total number of compared pixels.
+    pub total_pixels: usize,
+    /// Number of pixels that exceed the allowed channel delta.
+    pub differing_pixels: usize,
+    /// Largest channel difference of all compared pixels.
+    pub max_channel_delta: u8,
+    /// Bounding box of all pixels that exceed the allowed channel delta.
+    ///
+    /// `None` if no pixel exceeds the allowed channel delta.
+    pub bounding_box: Option<Rectangle>,
+    /// `true` if the difference is within the tolerance.
+    pub within_tolerance: bool,
+}
+
+impl DiffStatistics {
+    /// Returns the percentage of differing pixels.
+    pub fn differing_percentage(&self) -> f32 {
+        if self.total_pixels == 0 {
+            0.0
+        } else {
+            self.differing_pixels as f32 * 100.0 / self.total_pixels as f32
+        }
+    }
+}
+
+impl fmt::Display for DiffStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} pixels ({:.3}%) differ, max channel delta: {}",
+            self.differing_pixels,
+            self.total_pixels,
+            self.differing_percentage(),
+            self.max_channel_delta
+        )?;
+
+        if let Some(bounding_box) = self.bounding_box {
+            write!(
+                f,
+                ", bounding box: {}x{} at ({}, {})",
+                bounding_box.size.width,
+                bounding_box.size.height,
+                bounding_box.top_left.x,
+                bounding_box.top_left.y
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares two sequences of pixels in row-major order.
+pub(crate) fn compare<A, B>(width: u32, a: A, b: B, tolerance: &DiffTolerance) -> DiffStatistics
+where
+    A: IntoIterator<Item = Rgb888>,
+    B: IntoIterator<Item = Rgb888>,
+{
+    let mut total_pixels = 0;
+    let mut differing_pixels = 0;
+    let mut max_channel_delta = 0;
+    let mut top_left = Point::new(i32::MAX, i32::MAX);
+    let mut bottom_right = Point::new(i32::MIN, i32::MIN);
+
+    for (index, (a, b)) in a.into_iter().zip(b).enumerate() {
+        let delta = channel_delta(a, b);
+
+        total_pixels += 1;
+        if delta > tolerance.max_channel_delta {
+            let point = Point::new(
+                (index % width as usize) as i32,
+                (index / width as usize) as i32,
+            );
+
+            differing_pixels += 1;
+            top_left = top_left.component_min(point);
+            bottom_right = bottom_right.component_max(point);
+        }
+        max_channel_delta = max_channel_delta.max(delta);
+    }
+
+    let mut statistics = DiffStatistics {
+        total_pixels,
+        differing_pixels,
+        max_channel_delta,
+        bounding_box: (differing_pixels > 0)
+            .then(|| Rectangle::with_corners(top_left, bottom_right)),
+        within_tolerance: false,
+    };
+
+    statistics.within_tolerance = match tolerance.max_differing_pixels {
+        PixelBudget::Count(count) => differing_pixels <= count,
+        PixelBudget::Percentage(percentage) => statistics.differing_percentage() <= percentage,
+    };
+
+    statistics
+}
+
+/// Creates an image that highlights the differences between two displays.
+///
+/// Pixels that exceed the allowed channel delta are drawn in red, all other pixels are drawn as a
+/// dimmed grayscale version of the expected display.
+pub(crate) fn highlight_differences<C>(
+    actual: &SimulatorDisplay<C>,
+    expected: &SimulatorDisplay<C>,
+    tolerance: &DiffTolerance,
+) -> SimulatorDisplay<Rgb888>
+where
+    C: PixelColor + Into<Rgb888>,
+{
+    let mut output = SimulatorDisplay::with_default_color(expected.size(), Rgb888::BLACK);
+
+    expected
+        .bounding_box()
+        .points()
+        .map(|p| {
+            let actual_color = actual.get_pixel(p).into();
+            let expected_color = expected.get_pixel(p).into();
+
+            let color = if channel_delta(actual_color, expected_color) > tolerance.max_channel_delta
+            {
+                Rgb888::RED
+            } else {
+                let luma = (u16::from(expected_color.r())
+                    + u16::from(expected_color.g())
+                    + u16::from(expected_color.b()))
+                    / 3;
+                let dimmed = (luma / 4) as u8;
+
+                Rgb888::new(dimmed, dimmed, dimmed)
+            };
+
+            Pixel(p, color)
+        })
+        .draw(&mut output)
+        .unwrap();
+
+    output
+}
+
+/// Writes the actual, expected and difference images of a failed check.
+///
+/// The images are written to the directory set by the `EG_SIMULATOR_CHECK_OUTPUT_DIR` environment
+/// variable. The file names are derived from the name of the reference image. Returns the output
+/// directory or `None` if the variable isn't set.
+///
+/// # Panics
+///
+/// Panics if the images couldn't be written.
+pub(crate) fn write_check_images<C>(
+    reference: &Path,
+    actual: &SimulatorDisplay<C>,
+    expected: &SimulatorDisplay<C>,
+    tolerance: &DiffTolerance,
+) -> Option<PathBuf>
+where
+    C: PixelColor + Into<Rgb888>,
+{
+    let output_dir = PathBuf::from(env::var_os("EG_SIMULATOR_CHECK_OUTPUT_DIR")?);
+    fs::create_dir_all(&output_dir).expect("failed to create EG_SIMULATOR_CHECK_OUTPUT_DIR");
+
+    let name = reference
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let output_settings = OutputSettings::default();
+
+    for (suffix, image) in [
+        ("actual", actual.to_rgb_output_image(&output_settings)),
+        ("expected", expected.to_rgb_output_image(&output_settings)),
+        (
+            "diff",
+            highlight_differences(actual, expected, tolerance)
+                .to_rgb_output_image(&output_settings),
+        ),
+    ] {
+        let path = output_dir.join(format!("{name}-{suffix}.png"));
+        image
+            .save_png(&path)
+            .unwrap_or_else(|e| panic!("failed to write {}: {e}", path.display()));
+    }
+
+    Some(output_dir)
+}
+
+/// Returns the largest difference of the color channels.
+fn channel_delta(a: Rgb888, b: Rgb888) -> u8 {
+    a.r()
+        .abs_diff(b.r())
+        .max(a.g().abs_diff(b.g()))
+        .max(a.b().abs_diff(b.b()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tolerance() {
+        assert_eq!(
+            DiffTolerance::parse(None, None).unwrap(),
+            DiffTolerance::default()
+        );
+        assert_eq!(
+            DiffTolerance::parse(Some(" 3"), Some("1%")).unwrap(),
+            DiffTolerance {
+                max_channel_delta: 3,
+                max_differing_pixels: PixelBudget::Percentage(1.0),
+            }
+        );
+
+        let error = DiffTolerance::parse(Some("300"), None).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid EG_SIMULATOR_CHECK_TOLERANCE value \"300\" (expected 0-255)"
+        );
+        assert!(matches!(
+            DiffTolerance::parse(None, Some("x")),
+            Err(SnapshotError::InvalidTolerance {
+                variable: "EG_SIMULATOR_CHECK_MAX_PIXELS",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_pixel_budget() {
+        assert_eq!(PixelBudget::parse("10"), Some(PixelBudget::Count(10)));
+        assert_eq!(
+            PixelBudget::parse(" 0.5% "),
+            Some(PixelBudget::Percentage(0.5))
+        );
+        assert_eq!(
+            PixelBudget::parse("100%"),
+            Some(PixelBudget::Percentage(100.0))
+        );
+        assert_eq!(PixelBudget::parse("101%"), None);
+        assert_eq!(PixelBudget::parse("-1"), None);
+        assert_eq!(PixelBudget::parse("abc"), None);
+    }
+
+    #[test]
+    fn compare_exact() {
+        let a = [Rgb888::BLACK, Rgb888::WHITE, Rgb888::new(10, 20, 30)];
+        let b = [Rgb888::BLACK, Rgb888::WHITE, Rgb888::new(10, 21, 30)];
+
+        let statistics = compare(3, a, b, &DiffTolerance::default());
+        assert_eq!(
+            statistics,
+            DiffStatistics {
+                total_pixels: 3,
+                differing_pixels: 1,
+                max_channel_delta: 1,
+                bounding_box: Some(Rectangle::new(Point::new(2, 0), Size::new(1, 1))),
+                within_tolerance: false,
+            }
+        );
+    }
+
+    #[test]
+    fn compare_with_tolerance() {
+        let a = [Rgb888::BLACK, Rgb888::WHITE, Rgb888::new(10, 20, 30)];
+        let b = [Rgb888::new(1, 1, 1), Rgb888::BLACK, Rgb888::new(10, 22, 30)];
+
+        let tolerance = DiffTolerance {
+            max_channel_delta: 2,
+            max_differing_pixels: PixelBudget::Count(1),
+        };
+        let statistics = compare(2, a, b, &tolerance);
+        assert_eq!(statistics.differing_pixels, 1);
+        assert_eq!(
+            statistics.bounding_box,
+            Some(Rectangle::new(Point::new(1, 0), Size::new(1, 1)))
+        );
+        assert_eq!(statistics.max_channel_delta, 255);
+        assert!(statistics.within_tolerance);
+
+        let tolerance = DiffTolerance {
+            max_channel_delta: 1,
+            max_differing_pixels: PixelBudget::Percentage(50.0),
+        };
+        let statistics = compare(2, a, b, &tolerance);
+        assert_eq!(statistics.differing_pixels, 2);
+        assert_eq!(
+            statistics.bounding_box,
+            Some(Rectangle::new(Point::new(0, 0), Size::new(2, 2)))
+        );
+        assert!(!statistics.within_tolerance);
+    }
+
+    #[test]
+    fn highlight_differences() {
+        let mut actual = SimulatorDisplay::<Rgb888>::new(Size::new(3, 2));
+        Pixel(Point::new(1, 0), Rgb888::new(100, 100, 100))
+            .draw(&mut actual)
+            .unwrap();
+
+        let expected = actual.clone();
+
+        Pixel(Point::new(2, 1), Rgb888::WHITE)
+            .draw(&mut actual)
+            .unwrap();
+
+        let image = super::highlight_differences(&actual, &expected, &DiffTolerance::default());
+
+        let expected_pixels = [
+            Rgb888::BLACK,
+            Rgb888::new(25, 25, 25),
+            Rgb888::BLACK,
+            Rgb888::BLACK,
+            Rgb888::BLACK,
+            Rgb888::RED,
+        ];
+        assert!(image
+            .bounding_box()
+            .points()
+            .map(|p| image.get_pixel(p))
+            .eq(expected_pixels));
+    }
+}
@@ -1,33 +1,162 @@
 use std::{
+    cmp::Ordering,
+    collections::VecDeque,
     convert::TryFrom,
     fs::File,
+    hash::{Hash, Hasher},
     io::BufReader,
     path::Path,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{self, AtomicUsize},
 };
 
 use embedded_graphics::{
-    pixelcolor::{raw::ToBytes, BinaryColor, Gray8, Rgb888},
+    pixelcolor::{
+        raw::{RawData, RawU2, ToBytes},
+        BinaryColor, Gray8, Rgb888,
+    },
     prelude::*,
+    primitives::Rectangle,
 };
 
-use crate::{output_image::OutputImage, output_settings::OutputSettings};
+use crate::{
+    diff::{self, DiffStatistics, DiffTolerance},
+    framebuffer::{self, ByteOrder, FramebufferError, FramebufferLayout},
+    indexed_color::IndexedColor,
+    output_image::OutputImage,
+    output_settings::OutputSettings,
+    profile::DrawProfile,
+    snapshot::{self, SnapshotError},
+    tri_color::TriColor,
+};
 
 static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+static NEXT_HISTORY: AtomicUsize = AtomicUsize::new(0);
+
+/// Maximum number of entries in the change history of a display.
+const MAX_CHANGES: usize = 16;
 
 /// Simulator display.
-#[derive(Debug, Clone, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Eq, PartialOrd, Ord)]
 pub struct SimulatorDisplay<C> {
     size: Size,
     pub(crate) pixels: Box<[C]>,
     pub(crate) id: usize,
+    changes: Changes,
+    palette: Option<Palette<C>>,
+    profile: Option<DrawProfile>,
+}
+
+/// Change history of a display.
+///
+/// Each change increments the generation of the display. The history contains the changed areas
+/// together with the generation of the change. If the history grows too long, the oldest entries
+/// are merged, which results in larger, but still correct, dirty areas for old generations.
+///
+/// Clones of a display keep the ID of the original display, but get a new history ID. This makes
+/// it possible to detect that the generations of a clone and the original display can't be
+/// compared after the clone was changed.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Changes {
+    history_id: usize,
+    generation: u64,
+    areas: VecDeque<(u64, Rectangle)>,
+}
+
+impl Changes {
+    fn new(area: Rectangle) -> Self {
+        let mut changes = Self {
+            history_id: NEXT_HISTORY.fetch_add(1, atomic::Ordering::SeqCst),
+            generation: 0,
+            areas: VecDeque::new(),
+        };
+        changes.add(area);
+
+        changes
+    }
+
+    fn add(&mut self, area: Rectangle) {
+        if area.is_zero_sized() {
+            return;
+        }
+
+        self.generation += 1;
+        self.areas.push_back((self.generation, area));
+
+        if self.areas.len() > MAX_CHANGES {
+            let (_, oldest) = self.areas.pop_front().unwrap();
+            let (generation, next) = self.areas.pop_front().unwrap();
+            self.areas
+                .push_front((generation, envelope(&oldest, &next)));
+        }
+    }
+
+    fn since(&self, generation: u64) -> Option<Rectangle> {
+        self.areas
+            .iter()
+            .filter(|(g, _)| *g > generation)
+            .map(|(_, area)| *area)
+            .reduce(|a, b| envelope(&a, &b))
+    }
+}
+
+impl Clone for Changes {
+    fn clone(&self) -> Self {
+        Self {
+            history_id: NEXT_HISTORY.fetch_add(1, atomic::Ordering::SeqCst),
+            generation: self.generation,
+            areas: self.areas.clone(),
+        }
+    }
+}
+
+/// Palette of an indexed color display.
+///
+/// Palettes are compared and hashed by their colors, because `index` only depends on the color
+/// type.
+#[derive(Debug, Clone)]
+struct Palette<C> {
+    colors: Box<[Rgb888]>,
+    index: fn(C) -> usize,
+}
+
+impl<C> PartialEq for Palette<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.colors == other.colors
+    }
+}
+
+impl<C> Eq for Palette<C> {}
+
+impl<C> PartialOrd for Palette<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C> Ord for Palette<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.colors.cmp(&other.colors)
+    }
+}
+
+impl<C> Hash for Palette<C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.colors.hash(state);
+    }
 }
 
 impl<C: PixelColor> SimulatorDisplay<C> {
     fn new_common(size: Size, pixels: Box<[C]>) -> Self {
-        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let id = NEXT_ID.fetch_add(1, atomic::Ordering::SeqCst);
 
-        Self { size, pixels, id }
+        Self {
+            size,
+            pixels,
+            id,
+            changes: Changes::new(Rectangle::new(Point::zero(), size)),
+            palette: None,
+            profile: None,
+        }
     }
 
     /// Creates a new display filled with a color.
@@ -52,6 +181,122 @@ impl<C: PixelColor> SimulatorDisplay<C> {
             .expect("can't get point outside of display")
     }
 
+    /// Returns the generation of the display content.
+    ///
+    /// The generation is incremented each time the display is changed. It can be passed to
+    /// [`dirty_area_since`](Self::dirty_area_since) to get the area that was changed after this
+    /// call. A newly created display has generation `1`.
+    pub fn generation(&self) -> u64 {
+        self.changes.generation
+    }
+
+    /// Returns the ID of the change history.
+    ///
+    /// Generations can only be compared if they belong to the same history.
+    pub(crate) fn history_id(&self) -> usize {
+        self.changes.history_id
+    }
+
+    /// Returns the area that was changed since a generation.
+    ///
+    /// The returned rectangle is the bounding box of all pixels that were drawn after the display
+    /// had the given [`generation`](Self::generation). Passing `0` returns the area that was
+    /// changed since the display was created, which is the entire display. `None` is returned if
+    /// no pixels were drawn.
+    ///
+    /// Each [`Window`](crate::Window) keeps track of the last shown generation, which makes it
+    /// possible to show the same display in multiple windows.
+    pub fn dirty_area_since(&self, generation: u64) -> Option<Rectangle> {
+        self.changes.since(generation)
+    }
+
+    /// Marks an area of the display as changed.
+    ///
+    /// This can be used to force a redraw of an area, which wasn't changed by drawing to the
+    /// display, during the next window update.
+    pub fn mark_dirty(&mut self, area: &Rectangle) {
+        let area = area.intersection(&self.bounding_box());
+        self.add_dirty_area(area);
+    }
+
+    /// Enables or disables draw call profiling.
+    ///
+    /// If profiling is enabled, the number of draw calls and pixel writes is recorded in a
+    /// [`DrawProfile`], which can be accessed by [`profile`](Self::profile). Enabling profiling
+    /// starts a new profile.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use embedded_graphics::{
+    ///     pixelcolor::BinaryColor,
+    ///     prelude::*,
+    ///     primitives::{PrimitiveStyle, Rectangle},
+    /// };
+    /// use embedded_graphics_simulator::{OutputSettings, SimulatorDisplay};
+    ///
+    /// let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(64, 64));
+    /// display.set_profiling(true);
+    ///
+    /// Rectangle::new(Point::new(0, 0), Size::new(32, 32))
+    ///     .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+    ///     .draw(&mut display)
+    ///     .unwrap();
+    ///
+    /// let profile = display.profile().unwrap();
+    /// println!("{profile}");
+    ///
+    /// // example: profile.heatmap(&OutputSettings::default()).save_png("heatmap.png")?;
+    /// ```
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profile = enabled.then(|| DrawProfile::new(self.size));
+    }
+
+    /// Returns the draw call profile.
+    ///
+    /// Returns `None` if profiling isn't enabled.
+    pub fn profile(&self) -> Option<&DrawProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Resets the draw call profile.
+    ///
+    /// This can be used to profile individual frames. Does nothing if profiling isn't enabled.
+    pub fn reset_profile(&mut self) {
+        if self.profile.is_some() {
+            self.profile = Some(DrawProfile::new(self.size));
+        }
+    }
+
+    fn add_dirty_area(&mut self, area: Rectangle) {
+        self.changes.add(area);
+    }
+
+    fn draw_pixels<I>(&mut self, pixels: I)
+    where
+        I: IntoIterator<Item = Pixel<C>>,
+    {
+        let mut top_left = Point::new(i32::MAX, i32::MAX);
+        let mut bottom_right = Point::new(i32::MIN, i32::MIN);
+
+        for Pixel(point, color) in pixels.into_iter() {
+            if let Some(index) = self.point_to_index(point) {
+                if let Some(profile) = &mut self.profile {
+                    profile.record_write(index, self.pixels[index] == color);
+                }
+
+                self.pixels[index] = color;
+
+                top_left = top_left.component_min(point);
+                bottom_right = bottom_right.component_max(point);
+            }
+        }
+
+        if top_left.x <= bottom_right.x {
+            self.add_dirty_area(Rectangle::with_corners(top_left, bottom_right));
+        }
+    }
+
     fn point_to_index(&self, point: Point) -> Option<usize> {
         if let Ok((x, y)) = <(u32, u32)>::try_from(point) {
             if x < self.size.width && y < self.size.height {
@@ -89,7 +334,7 @@ impl<C: PixelColor> SimulatorDisplay<C> {
             .collect::<Vec<_>>()
             .into_boxed_slice();
 
-        if pixels.iter().any(|p| *p == BinaryColor::On) {
+        if pixels.contains(&BinaryColor::On) {
             Some(SimulatorDisplay::new_common(self.size, pixels))
         } else {
             None
@@ -102,8 +347,9 @@ impl<C: PixelColor> SimulatorDisplay<C> {
     /// [`pixel_spacing`](OutputSettings::pixel_spacing) settings to determine
     /// the size of this display in output pixels.
     pub fn output_size(&self, output_settings: &OutputSettings) -> Size {
-        self.size.component_mul(output_settings.scale)
-            + self.size.saturating_sub(Size::new_equal(1)) * output_settings.pixel_spacing
+        output_settings
+            .display_to_output_area(&self.bounding_box(), self.size)
+            .size
     }
 }
 
@@ -123,6 +369,153 @@ impl<C> SimulatorDisplay<C>
 where
     C: PixelColor + Into<Rgb888>,
 {
+    /// Compares the content of this display with another display using a tolerance.
+    ///
+    /// Both displays are compared after converting their colors to [`Rgb888`]. Pixels are
+    /// considered different if one of their color channels differs by more than the allowed
+    /// channel delta. The returned statistics contain the number of differing pixels and
+    /// whether the difference is within the tolerance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+    /// use embedded_graphics_simulator::{DiffTolerance, PixelBudget, SimulatorDisplay};
+    ///
+    /// let mut display = SimulatorDisplay::<Rgb888>::new(Size::new(16, 16));
+    /// let expected = display.clone();
+    ///
+    /// Pixel(Point::new(1, 2), Rgb888::new(1, 0, 0)).draw(&mut display).unwrap();
+    ///
+    /// let tolerance = DiffTolerance {
+    ///     max_channel_delta: 1,
+    ///     max_differing_pixels: PixelBudget::Count(0),
+    /// };
+    /// let statistics = display.diff_with_tolerance(&expected, &tolerance);
+    /// assert!(statistics.within_tolerance);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the both display don't have the same size.
+    pub fn diff_with_tolerance(
+        &self,
+        other: &SimulatorDisplay<C>,
+        tolerance: &DiffTolerance,
+    ) -> DiffStatistics {
+        assert!(
+            self.size == other.size,
+            "both displays must have the same size (self: {}x{}, other: {}x{})",
+            self.size.width,
+            self.size.height,
+            other.size.width,
+            other.size.height,
+        );
+
+        diff::compare(
+            self.size.width,
+            self.pixels.iter().map(|c| (*c).into()),
+            other.pixels.iter().map(|c| (*c).into()),
+            tolerance,
+        )
+    }
+
+    /// Returns the color of a pixel with the palette applied.
+    ///
+    /// If the display doesn't have a palette the pixel color is converted to `Rgb888`.
+    pub(crate) fn output_color(&self, point: Point) -> Rgb888 {
+        let color = self.get_pixel(point);
+
+        match &self.palette {
+            Some(palette) => palette
+                .colors
+                .get((palette.index)(color))
+                .copied()
+                .unwrap_or(Rgb888::BLACK),
+            None => color.into(),
+        }
+    }
+
+    /// Compares the display content with a reference PNG file.
+    ///
+    /// The output settings are applied to the display before it is compared with the reference
+    /// image. The comparison uses the tolerance set by the `EG_SIMULATOR_CHECK_TOLERANCE` and
+    /// `EG_SIMULATOR_CHECK_MAX_PIXELS` environment variables and writes the actual, expected and
+    /// difference images to `EG_SIMULATOR_CHECK_OUTPUT_DIR` if the content doesn't match.
+    ///
+    /// Unlike `EG_SIMULATOR_CHECK` this method doesn't exit the process, which makes it possible to
+    /// check multiple screens inside a test. If the `EG_SIMULATOR_UPDATE_SNAPSHOTS` environment
+    /// variable is set to `1` missing or mismatching reference images are replaced by the current
+    /// display content instead of returning an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+    /// use embedded_graphics_simulator::{OutputSettings, SimulatorDisplay};
+    ///
+    /// let display = SimulatorDisplay::<BinaryColor>::new(Size::new(128, 64));
+    ///
+    /// // draw something to the display
+    ///
+    /// display
+    ///     .assert_matches_png("tests/snapshots/main_screen.png", &OutputSettings::default())
+    ///     .unwrap();
+    /// ```
+    pub fn assert_matches_png<P: AsRef<Path>>(
+        &self,
+        path: P,
+        output_settings: &OutputSettings,
+    ) -> Result<(), SnapshotError> {
+        self.assert_matches_png_with_tolerance(path, output_settings, &DiffTolerance::from_env()?)
+    }
+
+    /// Compares the display content with a reference PNG file using a tolerance.
+    ///
+    /// This method works like [`assert_matches_png`](Self::assert_matches_png), but uses the
+    /// given tolerance instead of reading it from the environment variables.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+    /// use embedded_graphics_simulator::{
+    ///     DiffTolerance, OutputSettings, PixelBudget, SimulatorDisplay,
+    /// };
+    ///
+    /// let display = SimulatorDisplay::<Rgb888>::new(Size::new(128, 64));
+    ///
+    /// // draw something to the display
+    ///
+    /// let tolerance = DiffTolerance {
+    ///     max_channel_delta: 2,
+    ///     max_differing_pixels: PixelBudget::Count(10),
+    /// };
+    ///
+    /// display
+    ///     .assert_matches_png_with_tolerance(
+    ///         "tests/snapshots/main_screen.png",
+    ///         &OutputSettings::default(),
+    ///         &tolerance,
+    ///     )
+    ///     .unwrap();
+    /// ```
+    pub fn assert_matches_png_with_tolerance<P: AsRef<Path>>(
+        &self,
+        path: P,
+        output_settings: &OutputSettings,
+        tolerance: &DiffTolerance,
+    ) -> Result<(), SnapshotError> {
+        let output = self.to_rgb_output_image(output_settings).to_display();
+
+        snapshot::check_png(
+            path.as_ref(),
+            &output,
+            tolerance,
+            snapshot::update_snapshots(),
+        )
+    }
+
     /// Converts the display contents into a RGB output image.
     ///
     /// # Examples
@@ -189,49 +582,251 @@ where
 {
     /// Converts the display content to big endian raw data.
     pub fn to_be_bytes(&self) -> Vec<u8> {
-        self.to_bytes(ToBytes::to_be_bytes)
+        self.to_bytes_with_layout(&FramebufferLayout::row_major())
+            .unwrap()
     }
 
     /// Converts the display content to little endian raw data.
     pub fn to_le_bytes(&self) -> Vec<u8> {
-        self.to_bytes(ToBytes::to_le_bytes)
+        self.to_bytes_with_layout(&FramebufferLayout {
+            byte_order: ByteOrder::LittleEndian,
+            ..FramebufferLayout::row_major()
+        })
+        .unwrap()
     }
 
     /// Converts the display content to native endian raw data.
     pub fn to_ne_bytes(&self) -> Vec<u8> {
-        self.to_bytes(ToBytes::to_ne_bytes)
+        self.to_bytes_with_layout(&FramebufferLayout {
+            byte_order: ByteOrder::NativeEndian,
+            ..FramebufferLayout::row_major()
+        })
+        .unwrap()
     }
 
-    fn to_bytes<F>(&self, pixel_to_bytes: F) -> Vec<u8>
-    where
-        F: Fn(C) -> C::Bytes,
-    {
-        let mut bytes = Vec::new();
+    /// Converts the display content to raw data with the given memory layout.
+    ///
+    /// If the layout has a stride, all lines are padded with zeros, including the last line.
+    /// Returns an error if the stride is smaller than the number of bytes in a line.
+    pub fn to_bytes_with_layout(
+        &self,
+        layout: &FramebufferLayout,
+    ) -> Result<Vec<u8>, FramebufferError> {
+        let pixels = self.pixels.iter().map(|pixel| {
+            pixel
+                .to_be_bytes()
+                .as_ref()
+                .iter()
+                .fold(0, |value, byte| value << 8 | u32::from(*byte))
+        });
 
-        if C::Raw::BITS_PER_PIXEL >= 8 {
-            for pixel in self.pixels.iter() {
-                bytes.extend_from_slice(pixel_to_bytes(*pixel).as_ref())
-            }
-        } else {
-            let pixels_per_byte = 8 / C::Raw::BITS_PER_PIXEL;
+        framebuffer::pack(self.size, C::Raw::BITS_PER_PIXEL, layout, pixels)
+    }
+}
+
+impl<C> SimulatorDisplay<C>
+where
+    C: PixelColor + From<<C as PixelColor>::Raw>,
+{
+    /// Creates a display from big endian raw data.
+    ///
+    /// This is the inverse of [`to_be_bytes`](Self::to_be_bytes) and uses the same packing rules:
+    /// colors with less than 8 bits per pixel are packed into bytes, with the leftmost pixel in
+    /// the most significant bits, and each row starts at a new byte.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+    /// use embedded_graphics_simulator::SimulatorDisplay;
+    ///
+    /// let data = [0b1000_0001, 0b0100_0010];
+    /// let display = SimulatorDisplay::<BinaryColor>::from_be_bytes(Size::new(8, 2), &data).unwrap();
+    ///
+    /// assert_eq!(display.get_pixel(Point::new(7, 0)), BinaryColor::On);
+    /// assert_eq!(display.to_be_bytes(), data);
+    /// ```
+    pub fn from_be_bytes(size: Size, bytes: &[u8]) -> Result<Self, FramebufferError> {
+        Self::from_bytes_with_layout(size, &FramebufferLayout::row_major(), bytes)
+    }
+
+    /// Creates a display from little endian raw data.
+    ///
+    /// This is the inverse of [`to_le_bytes`](Self::to_le_bytes), see
+    /// [`from_be_bytes`](Self::from_be_bytes) for more details.
+    pub fn from_le_bytes(size: Size, bytes: &[u8]) -> Result<Self, FramebufferError> {
+        let layout = FramebufferLayout {
+            byte_order: ByteOrder::LittleEndian,
+            ..FramebufferLayout::row_major()
+        };
 
-            for row in self.pixels.chunks(self.size.width as usize) {
-                for byte_pixels in row.chunks(pixels_per_byte) {
-                    let mut value = 0;
+        Self::from_bytes_with_layout(size, &layout, bytes)
+    }
 
-                    for pixel in byte_pixels {
-                        value <<= C::Raw::BITS_PER_PIXEL;
-                        value |= pixel.to_be_bytes().as_ref()[0];
-                    }
+    /// Creates a display from native endian raw data.
+    ///
+    /// This is the inverse of [`to_ne_bytes`](Self::to_ne_bytes), see
+    /// [`from_be_bytes`](Self::from_be_bytes) for more details.
+    pub fn from_ne_bytes(size: Size, bytes: &[u8]) -> Result<Self, FramebufferError> {
+        let layout = FramebufferLayout {
+            byte_order: ByteOrder::NativeEndian,
+            ..FramebufferLayout::row_major()
+        };
 
-                    value <<= C::Raw::BITS_PER_PIXEL * (pixels_per_byte - byte_pixels.len());
+        Self::from_bytes_with_layout(size, &layout, bytes)
+    }
 
-                    bytes.push(value);
-                }
-            }
+    /// Creates a display from big endian raw data with a row stride.
+    ///
+    /// Each row starts `row_stride` bytes after the start of the previous row, which makes it
+    /// possible to load framebuffers with padded rows. The padding isn't required after the last
+    /// row.
+    pub fn from_be_bytes_with_row_stride(
+        size: Size,
+        row_stride: usize,
+        bytes: &[u8],
+    ) -> Result<Self, FramebufferError> {
+        Self::from_bytes_with_row_stride(size, row_stride, ByteOrder::BigEndian, bytes)
+    }
+
+    /// Creates a display from little endian raw data with a row stride.
+    ///
+    /// See [`from_be_bytes_with_row_stride`](Self::from_be_bytes_with_row_stride) for more
+    /// details.
+    pub fn from_le_bytes_with_row_stride(
+        size: Size,
+        row_stride: usize,
+        bytes: &[u8],
+    ) -> Result<Self, FramebufferError> {
+        Self::from_bytes_with_row_stride(size, row_stride, ByteOrder::LittleEndian, bytes)
+    }
+
+    /// Creates a display from native endian raw data with a row stride.
+    ///
+    /// See [`from_be_bytes_with_row_stride`](Self::from_be_bytes_with_row_stride) for more
+    /// details.
+    pub fn from_ne_bytes_with_row_stride(
+        size: Size,
+        row_stride: usize,
+        bytes: &[u8],
+    ) -> Result<Self, FramebufferError> {
+        Self::from_bytes_with_row_stride(size, row_stride, ByteOrder::NativeEndian, bytes)
+    }
+
+    /// Creates a display from raw data with the given memory layout.
+    ///
+    /// This is the inverse of [`to_bytes_with_layout`](Self::to_bytes_with_layout). The padding
+    /// after the last line isn't required.
+    pub fn from_bytes_with_layout(
+        size: Size,
+        layout: &FramebufferLayout,
+        bytes: &[u8],
+    ) -> Result<Self, FramebufferError> {
+        let pixels = framebuffer::unpack(size, C::Raw::BITS_PER_PIXEL, layout, bytes)?
+            .into_iter()
+            .map(|value| C::from(C::Raw::from_u32(value)))
+            .collect();
+
+        Ok(Self::new_common(size, pixels))
+    }
+
+    fn from_bytes_with_row_stride(
+        size: Size,
+        row_stride: usize,
+        byte_order: ByteOrder,
+        bytes: &[u8],
+    ) -> Result<Self, FramebufferError> {
+        let layout = FramebufferLayout {
+            byte_order,
+            stride: Some(row_stride),
+            ..FramebufferLayout::row_major()
+        };
+
+        Self::from_bytes_with_layout(size, &layout, bytes)
+    }
+}
+
+impl<R> SimulatorDisplay<IndexedColor<R>>
+where
+    R: RawData<Storage = u8> + Copy + PartialEq,
+{
+    /// Sets the palette of an indexed color display.
+    ///
+    /// The palette is applied when the display is drawn to a window or an output image. Indices
+    /// without a palette entry are shown as black. Setting the palette marks the entire display
+    /// as changed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use embedded_graphics::{
+    ///     pixelcolor::{raw::RawU4, Rgb888},
+    ///     prelude::*,
+    /// };
+    /// use embedded_graphics_simulator::{IndexedColor, OutputSettings, SimulatorDisplay};
+    ///
+    /// let mut display = SimulatorDisplay::<IndexedColor<RawU4>>::new(Size::new(16, 16));
+    /// display.set_palette(&[Rgb888::BLACK, Rgb888::RED, Rgb888::GREEN]);
+    ///
+    /// Pixel(Point::new(1, 2), IndexedColor::new(1))
+    ///     .draw(&mut display)
+    ///     .unwrap();
+    ///
+    /// // Rotate the colors without changing the display content.
+    /// display.palette_mut().unwrap()[1..].rotate_left(1);
+    /// ```
+    pub fn set_palette(&mut self, palette: &[Rgb888]) {
+        self.palette = Some(Palette {
+            colors: palette.into(),
+            index: |color: IndexedColor<R>| usize::from(color.index()),
+        });
+        self.mark_dirty(&self.bounding_box());
+    }
+
+    /// Returns the palette.
+    ///
+    /// Returns `None` if no palette was set.
+    pub fn palette(&self) -> Option<&[Rgb888]> {
+        self.palette.as_ref().map(|palette| palette.colors.as_ref())
+    }
+
+    /// Returns a mutable reference to the palette.
+    ///
+    /// Returns `None` if no palette was set. If a palette was set, calling this method marks the
+    /// entire display as changed.
+    pub fn palette_mut(&mut self) -> Option<&mut [Rgb888]> {
+        if self.palette.is_some() {
+            self.mark_dirty(&self.bounding_box());
         }
 
-        bytes
+        self.palette.as_mut().map(|palette| palette.colors.as_mut())
+    }
+}
+
+impl SimulatorDisplay<TriColor> {
+    /// Converts the display content to the two bit planes used by three color e-paper controllers.
+    ///
+    /// Returns the black/white plane followed by the chromatic plane. In the black/white plane
+    /// white and chromatic pixels are represented by `1` bits and black pixels by `0` bits. In the
+    /// chromatic plane chromatic pixels are represented by `1` bits and all other pixels by `0`
+    /// bits. Each row starts at a new byte, with the leftmost pixel in the most significant bit.
+    ///
+    /// Controllers that use the opposite polarity for one of the planes can be supported by
+    /// inverting all bytes of that plane.
+    pub fn to_bit_planes(&self) -> [Vec<u8>; 2] {
+        [0, 1].map(|bit| {
+            self.pixels
+                .chunks(self.size.width as usize)
+                .flat_map(|row| row.chunks(8))
+                .map(|byte_pixels| {
+                    byte_pixels
+                        .iter()
+                        .enumerate()
+                        .map(|(i, pixel)| (RawU2::from(*pixel).into_inner() >> bit & 1) << (7 - i))
+                        .fold(0, |byte, value| byte | value)
+                })
+                .collect()
+        })
     }
 }
 
@@ -264,12 +859,113 @@ impl<C: PixelColor> DrawTarget for SimulatorDisplay<C> {
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        for Pixel(point, color) in pixels.into_iter() {
-            if let Some(index) = self.point_to_index(point) {
-                self.pixels[index] = color;
+        if let Some(profile) = &mut self.profile {
+            profile.draw_iter_calls += 1;
+        }
+
+        self.draw_pixels(pixels);
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if let Some(profile) = &mut self.profile {
+            profile.fill_contiguous_calls += 1;
+        }
+
+        let visible = area.intersection(&self.bounding_box());
+        if visible.is_zero_sized() {
+            return Ok(());
+        }
+
+        // Number of colors that need to be skipped for clipped parts of the area.
+        let area_width = area.size.width as usize;
+        let skip_top = (visible.top_left.y - area.top_left.y) as usize * area_width;
+        let skip_left = (visible.top_left.x - area.top_left.x) as usize;
+        let skip_right = area_width - skip_left - visible.size.width as usize;
+
+        let mut colors = colors.into_iter();
+        if skip_top > 0 {
+            colors.nth(skip_top - 1);
+        }
+
+        let mut rows = 0;
+        for (row_start, row) in rows_mut(&mut self.pixels, self.size.width, &visible) {
+            if skip_left > 0 {
+                colors.nth(skip_left - 1);
+            }
+
+            let mut written = 0;
+            if let Some(profile) = &mut self.profile {
+                for ((index, pixel), color) in (row_start..).zip(row).zip(colors.by_ref()) {
+                    profile.record_write(index, *pixel == color);
+                    *pixel = color;
+                    written += 1;
+                }
+            } else {
+                for (pixel, color) in row.iter_mut().zip(colors.by_ref()) {
+                    *pixel = color;
+                    written += 1;
+                }
+            }
+
+            if written > 0 {
+                rows += 1;
+            }
+            if written < visible.size.width {
+                break;
+            }
+
+            if skip_right > 0 {
+                colors.nth(skip_right - 1);
             }
         }
 
+        self.add_dirty_area(Rectangle::new(
+            visible.top_left,
+            Size::new(visible.size.width, rows),
+        ));
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        if let Some(profile) = &mut self.profile {
+            profile.fill_solid_calls += 1;
+        }
+
+        let area = area.intersection(&self.bounding_box());
+
+        for (row_start, row) in rows_mut(&mut self.pixels, self.size.width, &area) {
+            if let Some(profile) = &mut self.profile {
+                for (index, pixel) in (row_start..).zip(row.iter()) {
+                    profile.record_write(index, *pixel == color);
+                }
+            }
+
+            row.fill(color);
+        }
+
+        self.add_dirty_area(area);
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        if let Some(profile) = &mut self.profile {
+            profile.clear_calls += 1;
+
+            for (index, pixel) in self.pixels.iter().enumerate() {
+                profile.record_write(index, *pixel == color);
+            }
+        }
+
+        self.pixels.fill(color);
+        self.add_dirty_area(self.bounding_box());
+
         Ok(())
     }
 }
@@ -282,14 +978,59 @@ impl<C> OriginDimensions for SimulatorDisplay<C> {
 
 impl<C: PartialEq> PartialEq for SimulatorDisplay<C> {
     fn eq(&self, other: &Self) -> bool {
-        self.size == other.size && self.pixels == other.pixels
+        self.size == other.size && self.pixels == other.pixels && self.palette == other.palette
     }
 }
 
+impl<C: Hash> Hash for SimulatorDisplay<C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.size.hash(state);
+        self.pixels.hash(state);
+        self.palette.hash(state);
+    }
+}
+
+/// Returns the rows of an area as mutable pixel slices and the index of the first pixel in each row.
+///
+/// The area must be inside the display.
+fn rows_mut<'a, C>(
+    pixels: &'a mut [C],
+    display_width: u32,
+    area: &Rectangle,
+) -> impl Iterator<Item = (usize, &'a mut [C])> {
+    let display_width = display_width as usize;
+    let x_start = area.top_left.x as usize;
+    let x_end = x_start + area.size.width as usize;
+    let rows = area.rows();
+
+    pixels
+        .chunks_exact_mut(display_width.max(1))
+        .enumerate()
+        .skip(rows.start as usize)
+        .take(rows.len())
+        .map(move |(y, row)| (y * display_width + x_start, &mut row[x_start..x_end]))
+}
+
+/// Returns the smallest rectangle that contains both rectangles.
+pub(crate) fn envelope(a: &Rectangle, b: &Rectangle) -> Rectangle {
+    let (a_bottom_right, b_bottom_right) = match (a.bottom_right(), b.bottom_right()) {
+        (Some(a_bottom_right), Some(b_bottom_right)) => (a_bottom_right, b_bottom_right),
+        (Some(_), None) => return *a,
+        (None, _) => return *b,
+    };
+
+    Rectangle::with_corners(
+        a.top_left.component_min(b.top_left),
+        a_bottom_right.component_max(b_bottom_right),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::{OutputSettingsBuilder, Rotation};
+
     use embedded_graphics::{
         pixelcolor::{Gray2, Gray4, Rgb565},
         primitives::{Circle, Line, PrimitiveStyle},
@@ -337,6 +1078,48 @@ mod tests {
         assert_eq!(image.data.as_ref(), expected);
     }
 
+    #[test]
+    fn rotated_output_image() {
+        let mut display = SimulatorDisplay::<Gray8>::new(Size::new(3, 2));
+        for (i, point) in display.bounding_box().points().enumerate() {
+            Pixel(point, Gray8::new((i as u8 + 1) * 10))
+                .draw(&mut display)
+                .unwrap();
+        }
+
+        let output_settings = OutputSettingsBuilder::new()
+            .rotation(Rotation::Deg90)
+            .build();
+        let image = display.to_grayscale_output_image(&output_settings);
+        assert_eq!(image.size(), Size::new(2, 3));
+
+        let expected: &[u8] = &[
+            40, 10, //
+            50, 20, //
+            60, 30, //
+        ];
+        assert_eq!(image.data.as_ref(), expected);
+
+        let output_settings = OutputSettingsBuilder::new()
+            .rotation(Rotation::Deg180)
+            .flip_horizontal(true)
+            .build();
+        let image = display.to_grayscale_output_image(&output_settings);
+
+        let expected: &[u8] = &[
+            40, 50, 60, //
+            10, 20, 30, //
+        ];
+        assert_eq!(image.data.as_ref(), expected);
+
+        let output_settings = OutputSettingsBuilder::new()
+            .scale_non_square(Size::new(2, 1))
+            .pixel_spacing(1)
+            .rotation(Rotation::Deg270)
+            .build();
+        assert_eq!(display.output_size(&output_settings), Size::new(3, 8));
+    }
+
     #[test]
     fn to_bytes_u1() {
         let display = SimulatorDisplay {
@@ -351,6 +1134,9 @@ mod tests {
             .collect::<Vec<_>>()
             .into_boxed_slice(),
             id: 0,
+            changes: Changes::new(Rectangle::zero()),
+            palette: None,
+            profile: None,
         };
 
         let expected = [
@@ -363,6 +1149,163 @@ mod tests {
         assert_eq!(&display.to_ne_bytes(), &expected);
     }
 
+    #[test]
+    fn from_bytes() {
+        let mut display = SimulatorDisplay::<Rgb565>::new(Size::new(3, 2));
+        Pixel(Point::new(1, 0), Rgb565::new(1, 2, 3))
+            .draw(&mut display)
+            .unwrap();
+        Pixel(Point::new(2, 1), Rgb565::RED)
+            .draw(&mut display)
+            .unwrap();
+
+        let from_be =
+            SimulatorDisplay::<Rgb565>::from_be_bytes(display.size, &display.to_be_bytes());
+        let from_le =
+            SimulatorDisplay::<Rgb565>::from_le_bytes(display.size, &display.to_le_bytes());
+        let from_ne =
+            SimulatorDisplay::<Rgb565>::from_ne_bytes(display.size, &display.to_ne_bytes());
+        assert_eq!(from_be, Ok(display.clone()));
+        assert_eq!(from_le, Ok(display.clone()));
+        assert_eq!(from_ne, Ok(display));
+    }
+
+    #[test]
+    fn from_bytes_with_row_stride() {
+        let data = [
+            0b00011011, 0b11000000, 0xFF, //
+            0b11100100, 0b00000000, 0xFF, //
+            0b01010101, 0b01000000,
+        ];
+        let display =
+            SimulatorDisplay::<Gray2>::from_be_bytes_with_row_stride(Size::new(5, 3), 3, &data)
+                .unwrap();
+
+        let expected = [
+            0, 1, 2, 3, 3, //
+            3, 2, 1, 0, 0, //
+            1, 1, 1, 1, 1, //
+        ];
+        assert_eq!(
+            display.pixels.iter().map(|c| c.luma()).collect::<Vec<_>>(),
+            expected
+        );
+
+        assert_eq!(
+            SimulatorDisplay::<Gray2>::from_be_bytes_with_row_stride(Size::new(5, 3), 1, &data),
+            Err(FramebufferError::StrideTooSmall {
+                stride: 1,
+                line_length: 2,
+            })
+        );
+        assert_eq!(
+            SimulatorDisplay::<Gray2>::from_be_bytes_with_row_stride(
+                Size::new(5, 3),
+                3,
+                &data[0..7]
+            ),
+            Err(FramebufferError::NotEnoughData {
+                expected: 8,
+                actual: 7,
+            })
+        );
+    }
+
+    #[test]
+    fn palette_mut_without_palette() {
+        let mut display = SimulatorDisplay::<IndexedColor<RawU2>>::new(Size::new(3, 1));
+        let generation = display.generation();
+
+        assert_eq!(display.palette_mut(), None);
+        assert_eq!(display.generation(), generation);
+        assert_eq!(display.dirty_area_since(generation), None);
+    }
+
+    #[test]
+    fn palette() {
+        let mut display = SimulatorDisplay::<IndexedColor<RawU2>>::new(Size::new(3, 1));
+        for (x, index) in [1, 2, 3].into_iter().enumerate() {
+            Pixel(Point::new(x as i32, 0), IndexedColor::new(index))
+                .draw(&mut display)
+                .unwrap();
+        }
+        let generation = display.generation();
+
+        let output_settings = OutputSettings::default();
+        let output_colors = |display: &SimulatorDisplay<IndexedColor<RawU2>>| {
+            let output = display.to_rgb_output_image(&output_settings).to_display();
+            (0..3)
+                .map(|x| output.get_pixel(Point::new(x, 0)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            output_colors(&display),
+            [
+                Rgb888::new(85, 85, 85),
+                Rgb888::new(170, 170, 170),
+                Rgb888::WHITE
+            ]
+        );
+
+        display.set_palette(&[Rgb888::BLACK, Rgb888::RED, Rgb888::GREEN]);
+        assert_eq!(
+            display.dirty_area_since(generation),
+            Some(display.bounding_box())
+        );
+        assert_eq!(
+            output_colors(&display),
+            [Rgb888::RED, Rgb888::GREEN, Rgb888::BLACK]
+        );
+
+        display.palette_mut().unwrap()[1..].rotate_left(1);
+        assert_eq!(
+            display.dirty_area_since(generation),
+            Some(display.bounding_box())
+        );
+        assert_eq!(
+            output_colors(&display),
+            [Rgb888::GREEN, Rgb888::RED, Rgb888::BLACK]
+        );
+
+        assert_eq!(display.to_be_bytes(), [0b01101100]);
+    }
+
+    #[test]
+    fn to_bit_planes() {
+        let display = SimulatorDisplay {
+            size: Size::new(9, 2),
+            pixels: [
+                0, 1, 2, 0, 0, 0, 0, 0, 1, //
+                2, 2, 1, 1, 0, 0, 0, 0, 2, //
+            ]
+            .iter()
+            .map(|c| [TriColor::White, TriColor::Black, TriColor::Chromatic][*c])
+            .collect::<Vec<_>>()
+            .into_boxed_slice(),
+            id: 0,
+            changes: Changes::new(Rectangle::zero()),
+            palette: None,
+            profile: None,
+        };
+
+        let [black_white, chromatic] = display.to_bit_planes();
+        assert_eq!(
+            black_white,
+            [
+                0b10111111, 0b00000000, //
+                0b11001111, 0b10000000, //
+            ]
+        );
+        assert_eq!(
+            chromatic,
+            [
+                0b00100000, 0b00000000, //
+                0b11000000, 0b10000000, //
+            ]
+        );
+    }
+
     #[test]
     fn to_bytes_u2() {
         let display = SimulatorDisplay {
@@ -376,6 +1319,9 @@ mod tests {
             .collect::<Vec<_>>()
             .into_boxed_slice(),
             id: 0,
+            changes: Changes::new(Rectangle::zero()),
+            palette: None,
+            profile: None,
         };
 
         let expected = [
@@ -402,6 +1348,9 @@ mod tests {
             .collect::<Vec<_>>()
             .into_boxed_slice(),
             id: 0,
+            changes: Changes::new(Rectangle::zero()),
+            palette: None,
+            profile: None,
         };
 
         let expected = [
@@ -431,6 +1380,9 @@ mod tests {
                 .collect::<Vec<_>>()
                 .into_boxed_slice(),
             id: 0,
+            changes: Changes::new(Rectangle::zero()),
+            palette: None,
+            profile: None,
         };
 
         assert_eq!(&display.to_be_bytes(), &expected);
@@ -446,6 +1398,9 @@ mod tests {
             size: Size::new(2, 1),
             pixels: expected.clone().into_boxed_slice(),
             id: 0,
+            changes: Changes::new(Rectangle::zero()),
+            palette: None,
+            profile: None,
         };
 
         assert_eq!(&display.to_be_bytes(), &[0x80, 0x00, 0x00, 0x01]);
@@ -460,6 +1415,9 @@ mod tests {
             size: Size::new(2, 1),
             pixels: expected.clone().into_boxed_slice(),
             id: 0,
+            changes: Changes::new(Rectangle::zero()),
+            palette: None,
+            profile: None,
         };
 
         assert_eq!(
@@ -503,4 +1461,213 @@ mod tests {
 
         assert_eq!(display.diff(&expected), None);
     }
+
+    #[test]
+    fn dirty_area_new() {
+        let display = SimulatorDisplay::<BinaryColor>::new(Size::new(4, 6));
+
+        assert_eq!(display.generation(), 1);
+        assert_eq!(display.dirty_area_since(0), Some(display.bounding_box()));
+        assert_eq!(display.dirty_area_since(1), None);
+    }
+
+    #[test]
+    fn dirty_area_draw() {
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(10, 8));
+        let generation = display.generation();
+
+        Line::new(Point::new(1, 2), Point::new(3, 2))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(&mut display)
+            .unwrap();
+        assert_eq!(
+            display.dirty_area_since(generation),
+            Some(Rectangle::new(Point::new(1, 2), Size::new(3, 1)))
+        );
+
+        Pixel(Point::new(5, 6), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+        assert_eq!(
+            display.dirty_area_since(generation),
+            Some(Rectangle::new(Point::new(1, 2), Size::new(5, 5)))
+        );
+    }
+
+    #[test]
+    fn dirty_area_outside_display() {
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(10, 8));
+        let generation = display.generation();
+
+        Pixel(Point::new(-1, 2), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+        assert_eq!(display.dirty_area_since(generation), None);
+
+        display.mark_dirty(&Rectangle::new(Point::new(8, 6), Size::new(10, 10)));
+        assert_eq!(
+            display.dirty_area_since(generation),
+            Some(Rectangle::new(Point::new(8, 6), Size::new(2, 2)))
+        );
+    }
+
+    #[test]
+    fn dirty_area_history() {
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(64, 4));
+        let mut generations = vec![display.generation()];
+
+        for x in 0..40 {
+            Pixel(Point::new(x, 1), BinaryColor::On)
+                .draw(&mut display)
+                .unwrap();
+            generations.push(display.generation());
+        }
+
+        assert_eq!(display.generation(), 41);
+        assert_eq!(
+            display.dirty_area_since(generations[38]),
+            Some(Rectangle::new(Point::new(38, 1), Size::new(2, 1)))
+        );
+
+        // Old generations return the merged areas, which must include all changes.
+        let area = display.dirty_area_since(generations[3]).unwrap();
+        assert_eq!(
+            area.intersection(&Rectangle::new(Point::new(3, 1), Size::new(37, 1))),
+            Rectangle::new(Point::new(3, 1), Size::new(37, 1))
+        );
+        assert_eq!(display.dirty_area_since(0), Some(display.bounding_box()));
+    }
+
+    #[test]
+    fn clone_has_new_history() {
+        let display = SimulatorDisplay::<BinaryColor>::new(Size::new(4, 4));
+        let clone = display.clone();
+
+        assert_eq!(display.id, clone.id);
+        assert_ne!(display.history_id(), clone.history_id());
+        assert_eq!(display.generation(), clone.generation());
+        assert_eq!(display, clone);
+    }
+
+    #[test]
+    fn display_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<SimulatorDisplay<BinaryColor>>();
+    }
+
+    #[test]
+    fn fill_contiguous_clipped() {
+        let areas = [
+            Rectangle::new(Point::new(1, 2), Size::new(3, 4)),
+            Rectangle::new(Point::new(-2, -3), Size::new(5, 6)),
+            Rectangle::new(Point::new(4, 5), Size::new(5, 6)),
+            Rectangle::new(Point::new(-1, 1), Size::new(9, 2)),
+            Rectangle::new(Point::new(6, 0), Size::new(2, 2)),
+        ];
+
+        for area in areas {
+            let colors = (0..).map(|i| Gray4::new(i as u8 % 15 + 1));
+
+            let mut expected = SimulatorDisplay::<Gray4>::new(Size::new(6, 7));
+            expected
+                .draw_iter(area.points().zip(colors.clone()).map(|(p, c)| Pixel(p, c)))
+                .unwrap();
+
+            let mut display = SimulatorDisplay::<Gray4>::new(Size::new(6, 7));
+            let generation = display.generation();
+            display.fill_contiguous(&area, colors).unwrap();
+
+            assert_eq!(display, expected, "{area:?}");
+            assert_eq!(
+                display.dirty_area_since(generation),
+                Some(area.intersection(&display.bounding_box())).filter(|a| !a.is_zero_sized()),
+                "{area:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn fill_contiguous_not_enough_colors() {
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(6, 4));
+        let generation = display.generation();
+
+        display
+            .fill_contiguous(
+                &Rectangle::new(Point::new(-1, 1), Size::new(4, 3)),
+                [BinaryColor::On; 6],
+            )
+            .unwrap();
+
+        let on_pixels = display
+            .bounding_box()
+            .points()
+            .filter(|p| display.get_pixel(*p).is_on())
+            .collect::<Vec<_>>();
+        assert_eq!(on_pixels, [(0, 1), (1, 1), (2, 1), (0, 2)].map(Point::from));
+        assert_eq!(
+            display.dirty_area_since(generation),
+            Some(Rectangle::new(Point::new(0, 1), Size::new(3, 2)))
+        );
+    }
+
+    #[test]
+    fn fill_solid_clipped() {
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(6, 4));
+        let generation = display.generation();
+
+        display
+            .fill_solid(
+                &Rectangle::new(Point::new(3, -1), Size::new(5, 3)),
+                BinaryColor::On,
+            )
+            .unwrap();
+
+        let on_pixels = display
+            .bounding_box()
+            .points()
+            .filter(|p| display.get_pixel(*p).is_on())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            on_pixels,
+            [(3, 0), (4, 0), (5, 0), (3, 1), (4, 1), (5, 1)].map(Point::from)
+        );
+        assert_eq!(
+            display.dirty_area_since(generation),
+            Some(Rectangle::new(Point::new(3, 0), Size::new(3, 2)))
+        );
+    }
+
+    #[test]
+    fn diff_with_tolerance() {
+        let mut display = SimulatorDisplay::<Rgb888>::new(Size::new(4, 6));
+        let expected = display.clone();
+
+        Pixel(Point::new(1, 1), Rgb888::new(2, 0, 0))
+            .draw(&mut display)
+            .unwrap();
+        Pixel(Point::new(2, 3), Rgb888::new(0, 0, 3))
+            .draw(&mut display)
+            .unwrap();
+
+        let tolerance = DiffTolerance {
+            max_channel_delta: 2,
+            max_differing_pixels: Default::default(),
+        };
+        let statistics = display.diff_with_tolerance(&expected, &tolerance);
+        assert_eq!(statistics.total_pixels, 24);
+        assert_eq!(statistics.differing_pixels, 1);
+        assert_eq!(statistics.max_channel_delta, 3);
+        assert!(!statistics.within_tolerance);
+
+        let tolerance = DiffTolerance {
+            max_channel_delta: 3,
+            max_differing_pixels: Default::default(),
+        };
+        assert!(
+            display
+                .diff_with_tolerance(&expected, &tolerance)
+                .within_tolerance
+        );
+    }
 }
@@ -0,0 +1,438 @@
+use std::{error, fmt};
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+/// Framebuffer memory layout.
+///
+/// The layout describes how the pixels of a display are stored in the memory of a display
+/// controller or in an image asset. It is used to export the display content by
+/// [`SimulatorDisplay::to_bytes_with_layout`] and to import raw data by
+/// [`SimulatorDisplay::from_bytes_with_layout`].
+///
+/// The framebuffer is divided into lines, which are rows, columns or pages depending on the pixel
+/// order. Each line starts at a new byte and lines can be padded by setting a stride.
+///
+/// # Examples
+///
+/// ```rust
+/// use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+/// use embedded_graphics_simulator::{BitOrder, FramebufferLayout, SimulatorDisplay};
+///
+/// let display = SimulatorDisplay::<BinaryColor>::new(Size::new(128, 64));
+///
+/// // Layout of the SSD1306 display RAM.
+/// let layout = FramebufferLayout::vertical_pages();
+/// let ram = display.to_bytes_with_layout(&layout).unwrap();
+/// assert_eq!(ram.len(), 128 * 64 / 8);
+///
+/// // Row major layout with the leftmost pixel in the least significant bit.
+/// let layout = FramebufferLayout {
+///     bit_order: BitOrder::LsbFirst,
+///     ..FramebufferLayout::row_major()
+/// };
+/// let display = SimulatorDisplay::<BinaryColor>::from_bytes_with_layout(
+///     Size::new(128, 64),
+///     &layout,
+///     &ram,
+/// )
+/// .unwrap();
+/// ```
+///
+/// [`SimulatorDisplay::to_bytes_with_layout`]: crate::SimulatorDisplay::to_bytes_with_layout
+/// [`SimulatorDisplay::from_bytes_with_layout`]: crate::SimulatorDisplay::from_bytes_with_layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FramebufferLayout {
+    /// Order of the pixels.
+    pub pixel_order: PixelOrder,
+    /// Order of the pixels inside a byte for colors with less than 8 bits per pixel.
+    pub bit_order: BitOrder,
+    /// Byte order of colors with more than 8 bits per pixel.
+    pub byte_order: ByteOrder,
+    /// Number of bytes between the start of two lines.
+    ///
+    /// `None` if the lines aren't padded. A stride that is smaller than the number of bytes in a
+    /// line results in a [`FramebufferError::StrideTooSmall`] error.
+    pub stride: Option<usize>,
+}
+
+impl FramebufferLayout {
+    /// Creates a row major layout.
+    ///
+    /// This is the layout that is used by [`to_be_bytes`](crate::SimulatorDisplay::to_be_bytes).
+    pub const fn row_major() -> Self {
+        Self {
+            pixel_order: PixelOrder::RowMajor,
+            bit_order: BitOrder::MsbFirst,
+            byte_order: ByteOrder::BigEndian,
+            stride: None,
+        }
+    }
+
+    /// Creates a column major layout.
+    pub const fn column_major() -> Self {
+        Self {
+            pixel_order: PixelOrder::ColumnMajor,
+            ..Self::row_major()
+        }
+    }
+
+    /// Creates a vertical page layout.
+    ///
+    /// This is the layout of monochrome controllers like the SSD1306 or SH1106, with the top
+    /// pixel of each byte in the least significant bit.
+    pub const fn vertical_pages() -> Self {
+        Self {
+            pixel_order: PixelOrder::VerticalPages,
+            bit_order: BitOrder::LsbFirst,
+            ..Self::row_major()
+        }
+    }
+
+    /// Returns the position of a pixel in the framebuffer.
+    ///
+    /// The returned tuple contains the line, the byte offset inside the line and the bit shift
+    /// for packed pixels.
+    fn locate(&self, point: Point, bits_per_pixel: usize) -> (usize, usize, u32) {
+        let (x, y) = (point.x as usize, point.y as usize);
+        let bytes_per_pixel = bits_per_pixel.div_ceil(8);
+        let pixels_per_byte = (8 / bits_per_pixel).max(1);
+
+        let (line, position) = match self.pixel_order {
+            PixelOrder::RowMajor => (y, x),
+            PixelOrder::ColumnMajor => (x, y),
+            PixelOrder::VerticalPages => {
+                let page = y / pixels_per_byte;
+                let index = y % pixels_per_byte;
+
+                return (page, x * bytes_per_pixel, self.shift(index, bits_per_pixel));
+            }
+        };
+
+        if bits_per_pixel >= 8 {
+            (line, position * bytes_per_pixel, 0)
+        } else {
+            let index = position % pixels_per_byte;
+
+            (
+                line,
+                position / pixels_per_byte,
+                self.shift(index, bits_per_pixel),
+            )
+        }
+    }
+
+    /// Returns the bit shift of the pixel with the given index inside a byte.
+    fn shift(&self, index: usize, bits_per_pixel: usize) -> u32 {
+        if bits_per_pixel >= 8 {
+            return 0;
+        }
+
+        match self.bit_order {
+            BitOrder::MsbFirst => (8 - bits_per_pixel * (index + 1)) as u32,
+            BitOrder::LsbFirst => (bits_per_pixel * index) as u32,
+        }
+    }
+
+    /// Returns the stride for lines with the given length.
+    fn stride(&self, line_length: usize) -> Result<usize, FramebufferError> {
+        match self.stride {
+            Some(stride) if stride < line_length => Err(FramebufferError::StrideTooSmall {
+                stride,
+                line_length,
+            }),
+            stride => Ok(stride.unwrap_or(line_length)),
+        }
+    }
+
+    /// Returns the number of lines and the number of bytes in a line without padding.
+    fn lines(&self, size: Size, bits_per_pixel: usize) -> (usize, usize) {
+        let (width, height) = (size.width as usize, size.height as usize);
+
+        match self.pixel_order {
+            PixelOrder::RowMajor => (height, (width * bits_per_pixel).div_ceil(8)),
+            PixelOrder::ColumnMajor => (width, (height * bits_per_pixel).div_ceil(8)),
+            PixelOrder::VerticalPages => {
+                let pixels_per_byte = (8 / bits_per_pixel).max(1);
+
+                (
+                    height.div_ceil(pixels_per_byte),
+                    width * bits_per_pixel.div_ceil(8),
+                )
+            }
+        }
+    }
+}
+
+impl Default for FramebufferLayout {
+    fn default() -> Self {
+        Self::row_major()
+    }
+}
+
+/// Pixel order of a framebuffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelOrder {
+    /// Pixels are stored row by row, from left to right.
+    RowMajor,
+    /// Pixels are stored column by column, from top to bottom.
+    ColumnMajor,
+    /// Pixels are stored in pages of vertical bytes.
+    ///
+    /// Each byte contains vertically adjacent pixels, e.g. 8 pixels for a monochrome display. The
+    /// bytes of a page are stored from left to right, followed by the next page. Colors with 8 or
+    /// more bits per pixel use pages that are one row high.
+    VerticalPages,
+}
+
+/// Order of packed pixels in a byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BitOrder {
+    /// The first pixel is stored in the most significant bits.
+    MsbFirst,
+    /// The first pixel is stored in the least significant bits.
+    LsbFirst,
+}
+
+/// Byte order of colors with more than 8 bits per pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteOrder {
+    /// Big endian.
+    BigEndian,
+    /// Little endian.
+    LittleEndian,
+    /// Native endian.
+    NativeEndian,
+}
+
+impl ByteOrder {
+    fn is_big_endian(self) -> bool {
+        match self {
+            ByteOrder::BigEndian => true,
+            ByteOrder::LittleEndian => false,
+            ByteOrder::NativeEndian => cfg!(target_endian = "big"),
+        }
+    }
+}
+
+/// Framebuffer error.
+///
+/// Returned if a display can't be converted into raw framebuffer data or raw framebuffer data
+/// can't be converted into a display, see
+/// [`SimulatorDisplay::to_bytes_with_layout`](crate::SimulatorDisplay::to_bytes_with_layout) and
+/// [`SimulatorDisplay::from_bytes_with_layout`](crate::SimulatorDisplay::from_bytes_with_layout).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FramebufferError {
+    /// The stride is smaller than the number of bytes required to store a line.
+    StrideTooSmall {
+        /// Stride in bytes.
+        stride: usize,
+        /// Number of bytes required to store a line.
+        line_length: usize,
+    },
+    /// The data is shorter than the framebuffer.
+    NotEnoughData {
+        /// Expected minimum length in bytes.
+        expected: usize,
+        /// Actual length in bytes.
+        actual: usize,
+    },
+}
+
+impl fmt::Display for FramebufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramebufferError::StrideTooSmall {
+                stride,
+                line_length,
+            } => write!(
+                f,
+                "stride is too small (stride: {stride}, line length: {line_length})"
+            ),
+            FramebufferError::NotEnoughData { expected, actual } => write!(
+                f,
+                "not enough framebuffer data (expected: {expected} bytes, actual: {actual} bytes)"
+            ),
+        }
+    }
+}
+
+impl error::Error for FramebufferError {}
+
+/// Packs pixel values into a framebuffer.
+///
+/// The pixels must be in row major order. Every line, including the last line, is padded to the
+/// stride.
+pub(crate) fn pack<I>(
+    size: Size,
+    bits_per_pixel: usize,
+    layout: &FramebufferLayout,
+    pixels: I,
+) -> Result<Vec<u8>, FramebufferError>
+where
+    I: IntoIterator<Item = u32>,
+{
+    let (lines, line_length) = layout.lines(size, bits_per_pixel);
+    let stride = layout.stride(line_length)?;
+    let bytes_per_pixel = bits_per_pixel.div_ceil(8);
+
+    let mut bytes = vec![0; lines * stride];
+
+    for (point, value) in Rectangle::new(Point::zero(), size).points().zip(pixels) {
+        let (line, offset, shift) = layout.locate(point, bits_per_pixel);
+        let index = line * stride + offset;
+
+        if bits_per_pixel >= 8 {
+            let pixel_bytes = &mut bytes[index..index + bytes_per_pixel];
+            for (i, byte) in pixel_bytes.iter_mut().enumerate() {
+                let byte_index = if layout.byte_order.is_big_endian() {
+                    bytes_per_pixel - 1 - i
+                } else {
+                    i
+                };
+
+                *byte = (value >> (8 * byte_index)) as u8;
+            }
+        } else {
+            bytes[index] |= (value as u8) << shift;
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Unpacks the pixel values in a framebuffer.
+///
+/// The pixels are returned in row major order. The padding after the last line is optional.
+pub(crate) fn unpack(
+    size: Size,
+    bits_per_pixel: usize,
+    layout: &FramebufferLayout,
+    bytes: &[u8],
+) -> Result<Vec<u32>, FramebufferError> {
+    let (lines, line_length) = layout.lines(size, bits_per_pixel);
+    let stride = layout.stride(line_length)?;
+
+    let expected = match lines {
+        0 => 0,
+        lines => stride * (lines - 1) + line_length,
+    };
+    if bytes.len() < expected {
+        return Err(FramebufferError::NotEnoughData {
+            expected,
+            actual: bytes.len(),
+        });
+    }
+
+    let bytes_per_pixel = bits_per_pixel.div_ceil(8);
+    let mask = u32::MAX >> (32 - bits_per_pixel);
+
+    let pixels = Rectangle::new(Point::zero(), size)
+        .points()
+        .map(|point| {
+            let (line, offset, shift) = layout.locate(point, bits_per_pixel);
+            let index = line * stride + offset;
+
+            if bits_per_pixel >= 8 {
+                let pixel_bytes = &bytes[index..index + bytes_per_pixel];
+                let fold = |value, byte: &u8| value << 8 | u32::from(*byte);
+
+                if layout.byte_order.is_big_endian() {
+                    pixel_bytes.iter().fold(0, fold)
+                } else {
+                    pixel_bytes.iter().rev().fold(0, fold)
+                }
+            } else {
+                u32::from(bytes[index] >> shift) & mask
+            }
+        })
+        .collect();
+
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertical_pages() {
+        let layout = FramebufferLayout::vertical_pages();
+        let size = Size::new(3, 10);
+
+        // Diagonal line and a pixel in the second page.
+        let pixels = Rectangle::new(Point::zero(), size)
+            .points()
+            .map(|p| u32::from(p.x == p.y || p == Point::new(2, 9)));
+
+        let bytes = pack(size, 1, &layout, pixels.clone()).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0b0000_0001,
+                0b0000_0010,
+                0b0000_0100, //
+                0b0000_0000,
+                0b0000_0000,
+                0b0000_0010, //
+            ]
+        );
+
+        assert_eq!(
+            unpack(size, 1, &layout, &bytes),
+            Ok(pixels.collect::<Vec<_>>())
+        );
+    }
+
+    #[test]
+    fn column_major() {
+        let layout = FramebufferLayout {
+            byte_order: ByteOrder::LittleEndian,
+            stride: Some(6),
+            ..FramebufferLayout::column_major()
+        };
+        let size = Size::new(2, 2);
+        let pixels = [0x0102, 0x0304, 0x0506, 0x0708];
+
+        let bytes = pack(size, 16, &layout, pixels).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0x02, 0x01, 0x06, 0x05, 0, 0, //
+                0x04, 0x03, 0x08, 0x07, 0, 0, //
+            ]
+        );
+
+        assert_eq!(unpack(size, 16, &layout, &bytes[0..10]), Ok(pixels.into()));
+    }
+
+    #[test]
+    fn lsb_first() {
+        let layout = FramebufferLayout {
+            bit_order: BitOrder::LsbFirst,
+            ..FramebufferLayout::row_major()
+        };
+        let size = Size::new(5, 1);
+        let pixels = [1, 2, 3, 0, 1];
+
+        let bytes = pack(size, 2, &layout, pixels).unwrap();
+        assert_eq!(bytes, [0b00_11_10_01, 0b00_00_00_01]);
+
+        assert_eq!(unpack(size, 2, &layout, &bytes), Ok(pixels.into()));
+    }
+
+    #[test]
+    fn stride_too_small() {
+        let layout = FramebufferLayout {
+            stride: Some(1),
+            ..FramebufferLayout::row_major()
+        };
+        let size = Size::new(3, 2);
+        let error = FramebufferError::StrideTooSmall {
+            stride: 1,
+            line_length: 3,
+        };
+
+        assert_eq!(pack(size, 8, &layout, [0; 6]), Err(error.clone()));
+        assert_eq!(unpack(size, 8, &layout, &[0; 6]), Err(error));
+    }
+}
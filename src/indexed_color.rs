@@ -0,0 +1,117 @@
+use embedded_graphics::{
+    pixelcolor::{
+        raw::{RawData, RawU1, RawU2, RawU4, RawU8},
+        BinaryColor, Rgb888,
+    },
+    prelude::*,
+};
+
+/// Palette index color.
+///
+/// Indexed colors store an index into a palette instead of a color. The raw data type `R`
+/// determines the number of bits per pixel, e.g. `IndexedColor<RawU4>` for a 16 color palette and
+/// `IndexedColor<RawU8>` for a 256 color palette.
+///
+/// The palette of a display is set by using
+/// [`SimulatorDisplay::set_palette`](crate::SimulatorDisplay::set_palette) and is applied when
+/// the display is drawn to a window or an output image. Changes to the palette are shown without
+/// redrawing the display content, which makes it possible to simulate palette animations. The
+/// raw data exported by [`to_be_bytes`](crate::SimulatorDisplay::to_be_bytes) and related methods
+/// contains the indices.
+///
+/// Without a palette the indices are shown as gray levels, with index `0` being black and the
+/// largest index being white.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IndexedColor<R = RawU8>(R);
+
+impl<R> IndexedColor<R>
+where
+    R: RawData<Storage = u8>,
+{
+    /// Creates a new indexed color.
+    ///
+    /// Only the least significant bits of `index` are used if `R` has less than 8 bits.
+    pub fn new(index: u8) -> Self {
+        Self(R::from(index))
+    }
+
+    /// Returns the palette index.
+    pub fn index(self) -> u8 {
+        self.0.into_inner()
+    }
+}
+
+impl<R> PixelColor for IndexedColor<R>
+where
+    R: RawData<Storage = u8> + Copy + PartialEq,
+{
+    type Raw = R;
+}
+
+macro_rules! impl_raw_conversion {
+    ($($raw:ident),*) => {
+        $(
+            impl From<$raw> for IndexedColor<$raw> {
+                fn from(raw: $raw) -> Self {
+                    Self(raw)
+                }
+            }
+
+            impl From<IndexedColor<$raw>> for $raw {
+                fn from(color: IndexedColor<$raw>) -> Self {
+                    color.0
+                }
+            }
+        )*
+    };
+}
+
+impl_raw_conversion!(RawU1, RawU2, RawU4, RawU8);
+
+/// Converts a binary color into an indexed color.
+///
+/// `Off` is converted to index `0` and `On` to index `1`.
+impl<R> From<BinaryColor> for IndexedColor<R>
+where
+    R: RawData<Storage = u8>,
+{
+    fn from(color: BinaryColor) -> Self {
+        Self::new(color.is_on().into())
+    }
+}
+
+/// Converts an indexed color into the gray level that is used if no palette is set.
+impl<R> From<IndexedColor<R>> for Rgb888
+where
+    R: RawData<Storage = u8>,
+{
+    fn from(color: IndexedColor<R>) -> Self {
+        let max_index = (1u16 << R::BITS_PER_PIXEL) - 1;
+        let level = u16::from(color.index()) * 255 / max_index;
+
+        Rgb888::new(level as u8, level as u8, level as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index() {
+        assert_eq!(IndexedColor::<RawU8>::new(200).index(), 200);
+        assert_eq!(IndexedColor::<RawU4>::new(0x1F).index(), 0xF);
+        assert_eq!(RawU4::from(IndexedColor::<RawU4>::new(5)), RawU4::new(5));
+    }
+
+    #[test]
+    fn gray_levels() {
+        assert_eq!(Rgb888::from(IndexedColor::<RawU2>::new(0)), Rgb888::BLACK);
+        assert_eq!(
+            Rgb888::from(IndexedColor::<RawU2>::new(1)),
+            Rgb888::new(85, 85, 85)
+        );
+        assert_eq!(Rgb888::from(IndexedColor::<RawU2>::new(3)), Rgb888::WHITE);
+        assert_eq!(Rgb888::from(IndexedColor::<RawU8>::new(255)), Rgb888::WHITE);
+    }
+}
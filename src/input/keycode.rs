@@ -0,0 +1,489 @@
+/// Key code.
+///
+/// The key codes use the same values as SDL key codes, which makes it possible to convert between
+/// both representations without a lookup table.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Keycode(i32);
+
+macro_rules! keycodes {
+    ($($name:ident = $value:expr,)*) => {
+        #[allow(missing_docs)]
+        impl Keycode {
+            $(pub const $name: Keycode = Keycode($value);)*
+        }
+
+        /// Names of all key codes.
+        const NAMES: &[(&str, Keycode)] = &[$((stringify!($name), Keycode::$name),)*];
+    };
+}
+
+keycodes! {
+    BACKSPACE = 8,
+    TAB = 9,
+    RETURN = 13,
+    ESCAPE = 27,
+    SPACE = 32,
+    EXCLAIM = 33,
+    QUOTEDBL = 34,
+    HASH = 35,
+    DOLLAR = 36,
+    PERCENT = 37,
+    AMPERSAND = 38,
+    QUOTE = 39,
+    LEFTPAREN = 40,
+    RIGHTPAREN = 41,
+    ASTERISK = 42,
+    PLUS = 43,
+    COMMA = 44,
+    MINUS = 45,
+    PERIOD = 46,
+    SLASH = 47,
+    NUM_0 = 48,
+    NUM_1 = 49,
+    NUM_2 = 50,
+    NUM_3 = 51,
+    NUM_4 = 52,
+    NUM_5 = 53,
+    NUM_6 = 54,
+    NUM_7 = 55,
+    NUM_8 = 56,
+    NUM_9 = 57,
+    COLON = 58,
+    SEMICOLON = 59,
+    LESS = 60,
+    EQUALS = 61,
+    GREATER = 62,
+    QUESTION = 63,
+    AT = 64,
+    LEFTBRACKET = 91,
+    BACKSLASH = 92,
+    RIGHTBRACKET = 93,
+    CARET = 94,
+    UNDERSCORE = 95,
+    BACKQUOTE = 96,
+    A = 97,
+    B = 98,
+    C = 99,
+    D = 100,
+    E = 101,
+    F = 102,
+    G = 103,
+    H = 104,
+    I = 105,
+    J = 106,
+    K = 107,
+    L = 108,
+    M = 109,
+    N = 110,
+    O = 111,
+    P = 112,
+    Q = 113,
+    R = 114,
+    S = 115,
+    T = 116,
+    U = 117,
+    V = 118,
+    W = 119,
+    X = 120,
+    Y = 121,
+    Z = 122,
+    DELETE = 127,
+    CAPSLOCK = 1073741881,
+    F1 = 1073741882,
+    F2 = 1073741883,
+    F3 = 1073741884,
+    F4 = 1073741885,
+    F5 = 1073741886,
+    F6 = 1073741887,
+    F7 = 1073741888,
+    F8 = 1073741889,
+    F9 = 1073741890,
+    F10 = 1073741891,
+    F11 = 1073741892,
+    F12 = 1073741893,
+    PRINTSCREEN = 1073741894,
+    SCROLLLOCK = 1073741895,
+    PAUSE = 1073741896,
+    INSERT = 1073741897,
+    HOME = 1073741898,
+    PAGEUP = 1073741899,
+    END = 1073741901,
+    PAGEDOWN = 1073741902,
+    RIGHT = 1073741903,
+    LEFT = 1073741904,
+    DOWN = 1073741905,
+    UP = 1073741906,
+    NUMLOCKCLEAR = 1073741907,
+    KP_DIVIDE = 1073741908,
+    KP_MULTIPLY = 1073741909,
+    KP_MINUS = 1073741910,
+    KP_PLUS = 1073741911,
+    KP_ENTER = 1073741912,
+    KP_1 = 1073741913,
+    KP_2 = 1073741914,
+    KP_3 = 1073741915,
+    KP_4 = 1073741916,
+    KP_5 = 1073741917,
+    KP_6 = 1073741918,
+    KP_7 = 1073741919,
+    KP_8 = 1073741920,
+    KP_9 = 1073741921,
+    KP_0 = 1073741922,
+    KP_PERIOD = 1073741923,
+    APPLICATION = 1073741925,
+    POWER = 1073741926,
+    KP_EQUALS = 1073741927,
+    F13 = 1073741928,
+    F14 = 1073741929,
+    F15 = 1073741930,
+    F16 = 1073741931,
+    F17 = 1073741932,
+    F18 = 1073741933,
+    F19 = 1073741934,
+    F20 = 1073741935,
+    F21 = 1073741936,
+    F22 = 1073741937,
+    F23 = 1073741938,
+    F24 = 1073741939,
+    EXECUTE = 1073741940,
+    HELP = 1073741941,
+    MENU = 1073741942,
+    SELECT = 1073741943,
+    STOP = 1073741944,
+    AGAIN = 1073741945,
+    UNDO = 1073741946,
+    CUT = 1073741947,
+    COPY = 1073741948,
+    PASTE = 1073741949,
+    FIND = 1073741950,
+    MUTE = 1073741951,
+    VOLUMEUP = 1073741952,
+    VOLUMEDOWN = 1073741953,
+    KP_COMMA = 1073741957,
+    KP_EQUALSAS400 = 1073741958,
+    ALTERASE = 1073741977,
+    SYSREQ = 1073741978,
+    CANCEL = 1073741979,
+    CLEAR = 1073741980,
+    PRIOR = 1073741981,
+    RETURN2 = 1073741982,
+    SEPARATOR = 1073741983,
+    OUT = 1073741984,
+    OPER = 1073741985,
+    CLEARAGAIN = 1073741986,
+    CRSEL = 1073741987,
+    EXSEL = 1073741988,
+    KP_00 = 1073742000,
+    KP_000 = 1073742001,
+    THOUSANDSSEPARATOR = 1073742002,
+    DECIMALSEPARATOR = 1073742003,
+    CURRENCYUNIT = 1073742004,
+    CURRENCYSUBUNIT = 1073742005,
+    KP_LEFTPAREN = 1073742006,
+    KP_RIGHTPAREN = 1073742007,
+    KP_LEFTBRACE = 1073742008,
+    KP_RIGHTBRACE = 1073742009,
+    KP_TAB = 1073742010,
+    KP_BACKSPACE = 1073742011,
+    KP_A = 1073742012,
+    KP_B = 1073742013,
+    KP_C = 1073742014,
+    KP_D = 1073742015,
+    KP_E = 1073742016,
+    KP_F = 1073742017,
+    KP_XOR = 1073742018,
+    KP_POWER = 1073742019,
+    KP_PERCENT = 1073742020,
+    KP_LESS = 1073742021,
+    KP_GREATER = 1073742022,
+    KP_AMPERSAND = 1073742023,
+    KP_DBLAMPERSAND = 1073742024,
+    KP_VERTICALBAR = 1073742025,
+    KP_DBLVERTICALBAR = 1073742026,
+    KP_COLON = 1073742027,
+    KP_HASH = 1073742028,
+    KP_SPACE = 1073742029,
+    KP_AT = 1073742030,
+    KP_EXCLAM = 1073742031,
+    KP_MEMSTORE = 1073742032,
+    KP_MEMRECALL = 1073742033,
+    KP_MEMCLEAR = 1073742034,
+    KP_MEMADD = 1073742035,
+    KP_MEMSUBTRACT = 1073742036,
+    KP_MEMMULTIPLY = 1073742037,
+    KP_MEMDIVIDE = 1073742038,
+    KP_PLUSMINUS = 1073742039,
+    KP_CLEAR = 1073742040,
+    KP_CLEARENTRY = 1073742041,
+    KP_BINARY = 1073742042,
+    KP_OCTAL = 1073742043,
+    KP_DECIMAL = 1073742044,
+    KP_HEXADECIMAL = 1073742045,
+    LCTRL = 1073742048,
+    LSHIFT = 1073742049,
+    LALT = 1073742050,
+    LGUI = 1073742051,
+    RCTRL = 1073742052,
+    RSHIFT = 1073742053,
+    RALT = 1073742054,
+    RGUI = 1073742055,
+    MODE = 1073742081,
+    AUDIONEXT = 1073742082,
+    AUDIOPREV = 1073742083,
+    AUDIOSTOP = 1073742084,
+    AUDIOPLAY = 1073742085,
+    AUDIOMUTE = 1073742086,
+    MEDIASELECT = 1073742087,
+    WWW = 1073742088,
+    MAIL = 1073742089,
+    CALCULATOR = 1073742090,
+    COMPUTER = 1073742091,
+    AC_SEARCH = 1073742092,
+    AC_HOME = 1073742093,
+    AC_BACK = 1073742094,
+    AC_FORWARD = 1073742095,
+    AC_STOP = 1073742096,
+    AC_REFRESH = 1073742097,
+    AC_BOOKMARKS = 1073742098,
+    BRIGHTNESSDOWN = 1073742099,
+    BRIGHTNESSUP = 1073742100,
+    DISPLAYSWITCH = 1073742101,
+    KBDILLUMTOGGLE = 1073742102,
+    KBDILLUMDOWN = 1073742103,
+    KBDILLUMUP = 1073742104,
+    EJECT = 1073742105,
+    SLEEP = 1073742106,
+}
+
+#[allow(non_upper_case_globals, missing_docs)]
+impl Keycode {
+    // CamelCase aliases that match the keycode names used by older versions of the `sdl2`
+    // crate.
+    pub const Backspace: Keycode = Keycode::BACKSPACE;
+    pub const Tab: Keycode = Keycode::TAB;
+    pub const Return: Keycode = Keycode::RETURN;
+    pub const Escape: Keycode = Keycode::ESCAPE;
+    pub const Space: Keycode = Keycode::SPACE;
+    pub const Exclaim: Keycode = Keycode::EXCLAIM;
+    pub const Quotedbl: Keycode = Keycode::QUOTEDBL;
+    pub const Hash: Keycode = Keycode::HASH;
+    pub const Dollar: Keycode = Keycode::DOLLAR;
+    pub const Percent: Keycode = Keycode::PERCENT;
+    pub const Ampersand: Keycode = Keycode::AMPERSAND;
+    pub const Quote: Keycode = Keycode::QUOTE;
+    pub const LeftParen: Keycode = Keycode::LEFTPAREN;
+    pub const RightParen: Keycode = Keycode::RIGHTPAREN;
+    pub const Asterisk: Keycode = Keycode::ASTERISK;
+    pub const Plus: Keycode = Keycode::PLUS;
+    pub const Comma: Keycode = Keycode::COMMA;
+    pub const Minus: Keycode = Keycode::MINUS;
+    pub const Period: Keycode = Keycode::PERIOD;
+    pub const Slash: Keycode = Keycode::SLASH;
+    pub const Num0: Keycode = Keycode::NUM_0;
+    pub const Num1: Keycode = Keycode::NUM_1;
+    pub const Num2: Keycode = Keycode::NUM_2;
+    pub const Num3: Keycode = Keycode::NUM_3;
+    pub const Num4: Keycode = Keycode::NUM_4;
+    pub const Num5: Keycode = Keycode::NUM_5;
+    pub const Num6: Keycode = Keycode::NUM_6;
+    pub const Num7: Keycode = Keycode::NUM_7;
+    pub const Num8: Keycode = Keycode::NUM_8;
+    pub const Num9: Keycode = Keycode::NUM_9;
+    pub const Colon: Keycode = Keycode::COLON;
+    pub const Semicolon: Keycode = Keycode::SEMICOLON;
+    pub const Less: Keycode = Keycode::LESS;
+    pub const Equals: Keycode = Keycode::EQUALS;
+    pub const Greater: Keycode = Keycode::GREATER;
+    pub const Question: Keycode = Keycode::QUESTION;
+    pub const At: Keycode = Keycode::AT;
+    pub const LeftBracket: Keycode = Keycode::LEFTBRACKET;
+    pub const Backslash: Keycode = Keycode::BACKSLASH;
+    pub const RightBracket: Keycode = Keycode::RIGHTBRACKET;
+    pub const Caret: Keycode = Keycode::CARET;
+    pub const Underscore: Keycode = Keycode::UNDERSCORE;
+    pub const Backquote: Keycode = Keycode::BACKQUOTE;
+    pub const Delete: Keycode = Keycode::DELETE;
+    pub const CapsLock: Keycode = Keycode::CAPSLOCK;
+    pub const PrintScreen: Keycode = Keycode::PRINTSCREEN;
+    pub const ScrollLock: Keycode = Keycode::SCROLLLOCK;
+    pub const Pause: Keycode = Keycode::PAUSE;
+    pub const Insert: Keycode = Keycode::INSERT;
+    pub const Home: Keycode = Keycode::HOME;
+    pub const PageUp: Keycode = Keycode::PAGEUP;
+    pub const End: Keycode = Keycode::END;
+    pub const PageDown: Keycode = Keycode::PAGEDOWN;
+    pub const Right: Keycode = Keycode::RIGHT;
+    pub const Left: Keycode = Keycode::LEFT;
+    pub const Down: Keycode = Keycode::DOWN;
+    pub const Up: Keycode = Keycode::UP;
+    pub const NumLockClear: Keycode = Keycode::NUMLOCKCLEAR;
+    pub const KpDivide: Keycode = Keycode::KP_DIVIDE;
+    pub const KpMultiply: Keycode = Keycode::KP_MULTIPLY;
+    pub const KpMinus: Keycode = Keycode::KP_MINUS;
+    pub const KpPlus: Keycode = Keycode::KP_PLUS;
+    pub const KpEnter: Keycode = Keycode::KP_ENTER;
+    pub const Kp1: Keycode = Keycode::KP_1;
+    pub const Kp2: Keycode = Keycode::KP_2;
+    pub const Kp3: Keycode = Keycode::KP_3;
+    pub const Kp4: Keycode = Keycode::KP_4;
+    pub const Kp5: Keycode = Keycode::KP_5;
+    pub const Kp6: Keycode = Keycode::KP_6;
+    pub const Kp7: Keycode = Keycode::KP_7;
+    pub const Kp8: Keycode = Keycode::KP_8;
+    pub const Kp9: Keycode = Keycode::KP_9;
+    pub const Kp0: Keycode = Keycode::KP_0;
+    pub const KpPeriod: Keycode = Keycode::KP_PERIOD;
+    pub const Application: Keycode = Keycode::APPLICATION;
+    pub const Power: Keycode = Keycode::POWER;
+    pub const KpEquals: Keycode = Keycode::KP_EQUALS;
+    pub const Execute: Keycode = Keycode::EXECUTE;
+    pub const Help: Keycode = Keycode::HELP;
+    pub const Menu: Keycode = Keycode::MENU;
+    pub const Select: Keycode = Keycode::SELECT;
+    pub const Stop: Keycode = Keycode::STOP;
+    pub const Again: Keycode = Keycode::AGAIN;
+    pub const Undo: Keycode = Keycode::UNDO;
+    pub const Cut: Keycode = Keycode::CUT;
+    pub const Copy: Keycode = Keycode::COPY;
+    pub const Paste: Keycode = Keycode::PASTE;
+    pub const Find: Keycode = Keycode::FIND;
+    pub const Mute: Keycode = Keycode::MUTE;
+    pub const VolumeUp: Keycode = Keycode::VOLUMEUP;
+    pub const VolumeDown: Keycode = Keycode::VOLUMEDOWN;
+    pub const KpComma: Keycode = Keycode::KP_COMMA;
+    pub const KpEqualsAS400: Keycode = Keycode::KP_EQUALSAS400;
+    pub const AltErase: Keycode = Keycode::ALTERASE;
+    pub const Sysreq: Keycode = Keycode::SYSREQ;
+    pub const Cancel: Keycode = Keycode::CANCEL;
+    pub const Clear: Keycode = Keycode::CLEAR;
+    pub const Prior: Keycode = Keycode::PRIOR;
+    pub const Return2: Keycode = Keycode::RETURN2;
+    pub const Separator: Keycode = Keycode::SEPARATOR;
+    pub const Out: Keycode = Keycode::OUT;
+    pub const Oper: Keycode = Keycode::OPER;
+    pub const ClearAgain: Keycode = Keycode::CLEARAGAIN;
+    pub const CrSel: Keycode = Keycode::CRSEL;
+    pub const ExSel: Keycode = Keycode::EXSEL;
+    pub const Kp00: Keycode = Keycode::KP_00;
+    pub const Kp000: Keycode = Keycode::KP_000;
+    pub const ThousandsSeparator: Keycode = Keycode::THOUSANDSSEPARATOR;
+    pub const DecimalSeparator: Keycode = Keycode::DECIMALSEPARATOR;
+    pub const CurrencyUnit: Keycode = Keycode::CURRENCYUNIT;
+    pub const CurrencySubUnit: Keycode = Keycode::CURRENCYSUBUNIT;
+    pub const KpLeftParen: Keycode = Keycode::KP_LEFTPAREN;
+    pub const KpRightParen: Keycode = Keycode::KP_RIGHTPAREN;
+    pub const KpLeftBrace: Keycode = Keycode::KP_LEFTBRACE;
+    pub const KpRightBrace: Keycode = Keycode::KP_RIGHTBRACE;
+    pub const KpTab: Keycode = Keycode::KP_TAB;
+    pub const KpBackspace: Keycode = Keycode::KP_BACKSPACE;
+    pub const KpA: Keycode = Keycode::KP_A;
+    pub const KpB: Keycode = Keycode::KP_B;
+    pub const KpC: Keycode = Keycode::KP_C;
+    pub const KpD: Keycode = Keycode::KP_D;
+    pub const KpE: Keycode = Keycode::KP_E;
+    pub const KpF: Keycode = Keycode::KP_F;
+    pub const KpXor: Keycode = Keycode::KP_XOR;
+    pub const KpPower: Keycode = Keycode::KP_POWER;
+    pub const KpPercent: Keycode = Keycode::KP_PERCENT;
+    pub const KpLess: Keycode = Keycode::KP_LESS;
+    pub const KpGreater: Keycode = Keycode::KP_GREATER;
+    pub const KpAmpersand: Keycode = Keycode::KP_AMPERSAND;
+    pub const KpDblAmpersand: Keycode = Keycode::KP_DBLAMPERSAND;
+    pub const KpVerticalBar: Keycode = Keycode::KP_VERTICALBAR;
+    pub const KpDblVerticalBar: Keycode = Keycode::KP_DBLVERTICALBAR;
+    pub const KpColon: Keycode = Keycode::KP_COLON;
+    pub const KpHash: Keycode = Keycode::KP_HASH;
+    pub const KpSpace: Keycode = Keycode::KP_SPACE;
+    pub const KpAt: Keycode = Keycode::KP_AT;
+    pub const KpExclam: Keycode = Keycode::KP_EXCLAM;
+    pub const KpMemStore: Keycode = Keycode::KP_MEMSTORE;
+    pub const KpMemRecall: Keycode = Keycode::KP_MEMRECALL;
+    pub const KpMemClear: Keycode = Keycode::KP_MEMCLEAR;
+    pub const KpMemAdd: Keycode = Keycode::KP_MEMADD;
+    pub const KpMemSubtract: Keycode = Keycode::KP_MEMSUBTRACT;
+    pub const KpMemMultiply: Keycode = Keycode::KP_MEMMULTIPLY;
+    pub const KpMemDivide: Keycode = Keycode::KP_MEMDIVIDE;
+    pub const KpPlusMinus: Keycode = Keycode::KP_PLUSMINUS;
+    pub const KpClear: Keycode = Keycode::KP_CLEAR;
+    pub const KpClearEntry: Keycode = Keycode::KP_CLEARENTRY;
+    pub const KpBinary: Keycode = Keycode::KP_BINARY;
+    pub const KpOctal: Keycode = Keycode::KP_OCTAL;
+    pub const KpDecimal: Keycode = Keycode::KP_DECIMAL;
+    pub const KpHexadecimal: Keycode = Keycode::KP_HEXADECIMAL;
+    pub const LCtrl: Keycode = Keycode::LCTRL;
+    pub const LShift: Keycode = Keycode::LSHIFT;
+    pub const LAlt: Keycode = Keycode::LALT;
+    pub const LGui: Keycode = Keycode::LGUI;
+    pub const RCtrl: Keycode = Keycode::RCTRL;
+    pub const RShift: Keycode = Keycode::RSHIFT;
+    pub const RAlt: Keycode = Keycode::RALT;
+    pub const RGui: Keycode = Keycode::RGUI;
+    pub const Mode: Keycode = Keycode::MODE;
+    pub const AudioNext: Keycode = Keycode::AUDIONEXT;
+    pub const AudioPrev: Keycode = Keycode::AUDIOPREV;
+    pub const AudioStop: Keycode = Keycode::AUDIOSTOP;
+    pub const AudioPlay: Keycode = Keycode::AUDIOPLAY;
+    pub const AudioMute: Keycode = Keycode::AUDIOMUTE;
+    pub const MediaSelect: Keycode = Keycode::MEDIASELECT;
+    pub const Www: Keycode = Keycode::WWW;
+    pub const Mail: Keycode = Keycode::MAIL;
+    pub const Calculator: Keycode = Keycode::CALCULATOR;
+    pub const Computer: Keycode = Keycode::COMPUTER;
+    pub const AcSearch: Keycode = Keycode::AC_SEARCH;
+    pub const AcHome: Keycode = Keycode::AC_HOME;
+    pub const AcBack: Keycode = Keycode::AC_BACK;
+    pub const AcForward: Keycode = Keycode::AC_FORWARD;
+    pub const AcStop: Keycode = Keycode::AC_STOP;
+    pub const AcRefresh: Keycode = Keycode::AC_REFRESH;
+    pub const AcBookmarks: Keycode = Keycode::AC_BOOKMARKS;
+    pub const BrightnessDown: Keycode = Keycode::BRIGHTNESSDOWN;
+    pub const BrightnessUp: Keycode = Keycode::BRIGHTNESSUP;
+    pub const DisplaySwitch: Keycode = Keycode::DISPLAYSWITCH;
+    pub const KbdIllumToggle: Keycode = Keycode::KBDILLUMTOGGLE;
+    pub const KbdIllumDown: Keycode = Keycode::KBDILLUMDOWN;
+    pub const KbdIllumUp: Keycode = Keycode::KBDILLUMUP;
+    pub const Eject: Keycode = Keycode::EJECT;
+    pub const Sleep: Keycode = Keycode::SLEEP;
+}
+
+impl Keycode {
+    /// Returns the SDL key code value.
+    pub const fn into_i32(self) -> i32 {
+        self.0
+    }
+
+    /// Returns the key code with the given name.
+    ///
+    /// The name is compared case insensitive and underscores are ignored, which means that
+    /// `PAGE_UP`, `PageUp` and `pageup` all return [`Keycode::PAGEUP`]. Returns `None` if the name
+    /// is unknown.
+    pub fn from_name(name: &str) -> Option<Keycode> {
+        let name = name.replace('_', "");
+
+        NAMES
+            .iter()
+            .find(|(n, _)| n.replace('_', "").eq_ignore_ascii_case(&name))
+            .map(|(_, keycode)| *keycode)
+    }
+
+    /// Returns the name of the key code.
+    ///
+    /// Returns `None` if the key code has no name.
+    pub(crate) fn name(self) -> Option<&'static str> {
+        NAMES
+            .iter()
+            .find(|(_, keycode)| *keycode == self)
+            .map(|(name, _)| *name)
+    }
+
+    /// Creates a key code from a SDL key code value.
+    ///
+    /// Returns `None` if `value` is `0`.
+    pub const fn from_i32(value: i32) -> Option<Keycode> {
+        if value != 0 {
+            Some(Keycode(value))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Input events and the types used in them.
+//!
+//! The types in this module don't depend on a specific window backend, which makes it possible to
+//! test input handling code without SDL2. See [`Window::push_event`](crate::Window::push_event)
+//! for more details.
+
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+use embedded_graphics::prelude::{Point, Size};
+
+use crate::output_settings::OutputSettings;
+
+mod keycode;
+
+pub use keycode::Keycode;
+
+/// Keyboard modifier state.
+///
+/// The bit values are identical to the SDL modifier flags.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Mod(u16);
+
+#[allow(missing_docs)]
+impl Mod {
+    pub const NOMOD: Mod = Mod(0x0000);
+    pub const LSHIFTMOD: Mod = Mod(0x0001);
+    pub const RSHIFTMOD: Mod = Mod(0x0002);
+    pub const LCTRLMOD: Mod = Mod(0x0040);
+    pub const RCTRLMOD: Mod = Mod(0x0080);
+    pub const LALTMOD: Mod = Mod(0x0100);
+    pub const RALTMOD: Mod = Mod(0x0200);
+    pub const LGUIMOD: Mod = Mod(0x0400);
+    pub const RGUIMOD: Mod = Mod(0x0800);
+    pub const NUMMOD: Mod = Mod(0x1000);
+    pub const CAPSMOD: Mod = Mod(0x2000);
+    pub const MODEMOD: Mod = Mod(0x4000);
+    pub const RESERVEDMOD: Mod = Mod(0x8000);
+}
+
+impl Mod {
+    /// Returns an empty modifier state.
+    pub const fn empty() -> Self {
+        Self::NOMOD
+    }
+
+    /// Creates a modifier state from SDL modifier flags.
+    pub const fn from_bits_truncate(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the SDL modifier flags.
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Returns `true` if no modifier is set.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all modifiers in `other` are set.
+    pub const fn contains(self, other: Mod) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if any modifier in `other` is set.
+    pub const fn intersects(self, other: Mod) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for Mod {
+    type Output = Mod;
+
+    fn bitor(self, rhs: Mod) -> Mod {
+        Mod(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Mod {
+    fn bitor_assign(&mut self, rhs: Mod) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Mod {
+    type Output = Mod;
+
+    fn bitand(self, rhs: Mod) -> Mod {
+        Mod(self.0 & rhs.0)
+    }
+}
+
+/// Mouse button.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[allow(missing_docs)]
+pub enum MouseButton {
+    Unknown,
+    Left,
+    Middle,
+    Right,
+    X1,
+    X2,
+}
+
+/// Mouse wheel direction.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum MouseWheelDirection {
+    /// The scroll direction is normal.
+    Normal,
+    /// The scroll direction is flipped.
+    Flipped,
+    /// Unknown scroll direction.
+    Unknown(u32),
+}
+
+/// Simulator event with mouse positions in embedded-graphics coordinates.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SimulatorEvent {
+    /// A keypress event, fired on keyUp
+    KeyUp {
+        /// The key being released
+        keycode: Keycode,
+        /// Any modifier being held at the time of keyup
+        keymod: Mod,
+        /// Whether the key is repeating
+        repeat: bool,
+    },
+    /// A keypress event, fired on keyDown
+    KeyDown {
+        /// The key being pressed
+        keycode: Keycode,
+        /// Any modifier being held at the time of keydown
+        keymod: Mod,
+        /// Whether the key is repeating
+        repeat: bool,
+    },
+    /// A mouse click event, fired on mouseUp
+    MouseButtonUp {
+        /// The mouse button being released
+        mouse_btn: MouseButton,
+        /// The location of the mouse in Simulator coordinates
+        point: Point,
+    },
+    /// A mouse click event, fired on mouseDown
+    MouseButtonDown {
+        /// The mouse button being pressed
+        mouse_btn: MouseButton,
+        /// The location of the mouse in Simulator coordinates
+        point: Point,
+    },
+    /// A mouse wheel event
+    MouseWheel {
+        /// The scroll wheel delta in the x and y direction
+        scroll_delta: Point,
+        /// The directionality of the scroll (normal or flipped)
+        direction: MouseWheelDirection,
+    },
+    /// Mouse move event
+    MouseMove {
+        /// The current mouse position
+        point: Point,
+    },
+    /// An exit event
+    Quit,
+}
+
+impl SimulatorEvent {
+    /// Translates the mouse position from output to display coordinates.
+    pub(crate) fn output_to_display(
+        self,
+        output_settings: &OutputSettings,
+        display_size: Size,
+    ) -> Self {
+        self.map_point(|point| output_settings.output_to_display(point, display_size))
+    }
+
+    /// Translates the mouse position from display to output coordinates.
+    pub(crate) fn display_to_output(
+        self,
+        output_settings: &OutputSettings,
+        display_size: Size,
+    ) -> Self {
+        self.map_point(|point| output_settings.display_to_output(point, display_size))
+    }
+
+    fn map_point<F>(self, f: F) -> Self
+    where
+        F: Fn(Point) -> Point,
+    {
+        match self {
+            SimulatorEvent::MouseButtonUp { mouse_btn, point } => SimulatorEvent::MouseButtonUp {
+                mouse_btn,
+                point: f(point),
+            },
+            SimulatorEvent::MouseButtonDown { mouse_btn, point } => {
+                SimulatorEvent::MouseButtonDown {
+                    mouse_btn,
+                    point: f(point),
+                }
+            }
+            SimulatorEvent::MouseMove { point } => SimulatorEvent::MouseMove { point: f(point) },
+            event => event,
+        }
+    }
+}
+
+#[cfg(feature = "with-sdl")]
+impl From<sdl2::keyboard::Keycode> for Keycode {
+    fn from(keycode: sdl2::keyboard::Keycode) -> Self {
+        Keycode::from_i32(keycode.into_i32()).unwrap()
+    }
+}
+
+#[cfg(feature = "with-sdl")]
+impl From<Keycode> for sdl2::keyboard::Keycode {
+    fn from(keycode: Keycode) -> Self {
+        sdl2::keyboard::Keycode::from_i32(keycode.into_i32()).unwrap()
+    }
+}
+
+#[cfg(feature = "with-sdl")]
+impl From<sdl2::keyboard::Mod> for Mod {
+    fn from(keymod: sdl2::keyboard::Mod) -> Self {
+        Mod::from_bits_truncate(keymod.bits())
+    }
+}
+
+#[cfg(feature = "with-sdl")]
+impl From<Mod> for sdl2::keyboard::Mod {
+    fn from(keymod: Mod) -> Self {
+        sdl2::keyboard::Mod::from_bits_truncate(keymod.bits())
+    }
+}
+
+#[cfg(feature = "with-sdl")]
+impl From<sdl2::mouse::MouseButton> for MouseButton {
+    fn from(button: sdl2::mouse::MouseButton) -> Self {
+        match button {
+            sdl2::mouse::MouseButton::Unknown => MouseButton::Unknown,
+            sdl2::mouse::MouseButton::Left => MouseButton::Left,
+            sdl2::mouse::MouseButton::Middle => MouseButton::Middle,
+            sdl2::mouse::MouseButton::Right => MouseButton::Right,
+            sdl2::mouse::MouseButton::X1 => MouseButton::X1,
+            sdl2::mouse::MouseButton::X2 => MouseButton::X2,
+        }
+    }
+}
+
+#[cfg(feature = "with-sdl")]
+impl From<sdl2::mouse::MouseWheelDirection> for MouseWheelDirection {
+    fn from(direction: sdl2::mouse::MouseWheelDirection) -> Self {
+        match direction {
+            sdl2::mouse::MouseWheelDirection::Normal => MouseWheelDirection::Normal,
+            sdl2::mouse::MouseWheelDirection::Flipped => MouseWheelDirection::Flipped,
+            sdl2::mouse::MouseWheelDirection::Unknown(direction) => {
+                MouseWheelDirection::Unknown(direction)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{OutputSettingsBuilder, Rotation};
+
+    const DISPLAY_SIZE: Size = Size::new(16, 8);
+
+    #[test]
+    fn keycode_values() {
+        assert_eq!(Keycode::A.into_i32(), 'a' as i32);
+        assert_eq!(Keycode::Left, Keycode::LEFT);
+        assert_eq!(Keycode::LEFT.into_i32(), 0x4000_0050);
+        assert_eq!(Keycode::from_i32(0), None);
+    }
+
+    #[test]
+    fn keycode_from_name() {
+        assert_eq!(Keycode::from_name("Down"), Some(Keycode::DOWN));
+        assert_eq!(Keycode::from_name("PAGE_UP"), Some(Keycode::PAGEUP));
+        assert_eq!(Keycode::from_name("kpenter"), Some(Keycode::KP_ENTER));
+        assert_eq!(Keycode::from_name("a"), Some(Keycode::A));
+        assert_eq!(Keycode::from_name("Foo"), None);
+    }
+
+    #[test]
+    fn modifiers() {
+        let keymod = Mod::LSHIFTMOD | Mod::LCTRLMOD;
+
+        assert!(keymod.contains(Mod::LSHIFTMOD));
+        assert!(!keymod.contains(Mod::LSHIFTMOD | Mod::LALTMOD));
+        assert!(keymod.intersects(Mod::LSHIFTMOD | Mod::LALTMOD));
+        assert!(Mod::default().is_empty());
+    }
+
+    #[test]
+    fn translate_mouse_position() {
+        let output_settings = OutputSettingsBuilder::new()
+            .scale(3)
+            .pixel_spacing(1)
+            .build();
+
+        let event = SimulatorEvent::MouseButtonDown {
+            mouse_btn: MouseButton::Left,
+            point: Point::new(9, 17),
+        };
+        assert_eq!(
+            event.output_to_display(&output_settings, DISPLAY_SIZE),
+            SimulatorEvent::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                point: Point::new(2, 4),
+            }
+        );
+
+        let event = SimulatorEvent::MouseWheel {
+            scroll_delta: Point::new(0, 1),
+            direction: MouseWheelDirection::Normal,
+        };
+        assert_eq!(
+            event.output_to_display(&output_settings, DISPLAY_SIZE),
+            event
+        );
+    }
+
+    #[test]
+    fn display_to_output() {
+        let output_settings = OutputSettingsBuilder::new()
+            .scale(3)
+            .pixel_spacing(1)
+            .build();
+
+        let event = SimulatorEvent::MouseMove {
+            point: Point::new(2, 4),
+        };
+        assert_eq!(
+            event.display_to_output(&output_settings, DISPLAY_SIZE),
+            SimulatorEvent::MouseMove {
+                point: Point::new(8, 16),
+            }
+        );
+        assert_eq!(
+            event
+                .display_to_output(&output_settings, DISPLAY_SIZE)
+                .output_to_display(&output_settings, DISPLAY_SIZE),
+            event
+        );
+    }
+
+    #[test]
+    fn translate_rotated_mouse_position() {
+        for rotation in [
+            Rotation::Deg0,
+            Rotation::Deg90,
+            Rotation::Deg180,
+            Rotation::Deg270,
+        ] {
+            for (flip_horizontal, flip_vertical) in
+                [(false, false), (true, false), (false, true), (true, true)]
+            {
+                let output_settings = OutputSettingsBuilder::new()
+                    .scale_non_square(Size::new(2, 3))
+                    .pixel_spacing(1)
+                    .rotation(rotation)
+                    .flip_horizontal(flip_horizontal)
+                    .flip_vertical(flip_vertical)
+                    .build();
+
+                let event = SimulatorEvent::MouseMove {
+                    point: Point::new(3, 5),
+                };
+                assert_eq!(
+                    event
+                        .display_to_output(&output_settings, DISPLAY_SIZE)
+                        .output_to_display(&output_settings, DISPLAY_SIZE),
+                    event
+                );
+            }
+        }
+
+        let output_settings = OutputSettingsBuilder::new()
+            .scale(2)
+            .rotation(Rotation::Deg90)
+            .build();
+        let event = SimulatorEvent::MouseMove {
+            point: Point::new(0, 0),
+        };
+        assert_eq!(
+            event.display_to_output(&output_settings, DISPLAY_SIZE),
+            SimulatorEvent::MouseMove {
+                point: Point::new(14, 0),
+            }
+        );
+    }
+}
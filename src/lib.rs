@@ -102,6 +102,58 @@
 //! applies the output settings before exporting the PNG file and the later dumps the unaltered
 //! display content.
 //!
+//! ## Selecting frames
+//!
+//! Programs that show a splash screen or an animation before the interesting content is
+//! displayed can select the exported frame by using one or more of the following variables:
+//!
+//! - `EG_SIMULATOR_DUMP_FRAME` selects frames by their index, starting at `0` for the first
+//!   [`Window::update`] call.
+//! - `EG_SIMULATOR_DUMP_TIME` selects the first frame after the given time since the first
+//!   [`Window::update`] call has elapsed. The time must include a unit, e.g. `500ms` or `2.5s`.
+//! - `EG_SIMULATOR_DUMP_LABEL` selects frames that were labeled by calling
+//!   [`Window::mark_frame`] before the frame was passed to [`Window::update`].
+//!
+//! ```bash
+//! EG_SIMULATOR_DUMP=screenshot.png EG_SIMULATOR_DUMP_FRAME=30 cargo run
+//! EG_SIMULATOR_DUMP=screenshot.png EG_SIMULATOR_DUMP_LABEL=settings-menu cargo run
+//! ```
+//!
+//! No window is opened while the simulator waits for the selected frames, which makes it possible
+//! to create screenshots in CI environments without a display. If the selected frames aren't found
+//! within 60 seconds after the latest `EG_SIMULATOR_DUMP_TIME` value, the program panics and lists
+//! the selections that didn't match. The timeout can be changed by setting
+//! `EG_SIMULATOR_DUMP_TIMEOUT` to a duration, e.g. `300s`.
+//!
+//! Multiple frames can be exported by including a `{frame}` placeholder in the path, which is
+//! replaced by the frame index. In this mode all values of the comma separated selection variables
+//! are exported before the process is terminated. If no selection variable is set every frame is
+//! exported and the program continues to run normally.
+//!
+//! ```bash
+//! EG_SIMULATOR_DUMP="frame-{frame}.png" EG_SIMULATOR_DUMP_FRAME=10,20,30 cargo run
+//! ```
+//!
+//! # Recording animations
+//!
+//! The content of a [`Window`] or `MultiWindow` can be recorded as an animated GIF or PNG file
+//! by setting the `EG_SIMULATOR_RECORD` environment variable. The format is determined by the
+//! file extension: `.gif` files are saved as animated GIFs and `.png` or `.apng` files as
+//! animated PNGs.
+//!
+//! ```bash
+//! EG_SIMULATOR_RECORD=demo.gif cargo run
+//! ```
+//!
+//! Each frame passed to [`Window::update`] or `MultiWindow::flush` is recorded, with frame
+//! delays based on the time between the updates. The recording is saved when the window is
+//! dropped. Recordings can also be started and stopped programmatically by using
+//! [`Window::start_recording`] and [`Window::stop_recording`].
+//!
+//! Note that GIF files only support frame delays in multiples of 10 ms and are limited to 256
+//! colors per frame. Animated PNGs don't have these limitations, but aren't supported by all
+//! image viewers.
+//!
 //! # Exporting images
 //!
 //! If a program doesn't require to display a window and only needs to export one or more images, a
@@ -122,7 +174,274 @@
 //! ```
 //!
 //! `EG_SIMULATOR_CHECK` assumes that the reference image was created using the same
-//! `OutputSetting`s, while `EG_SIMULATOR_CHECK_RAW` assumes an unstyled reference image.
+//! `OutputSetting`s, while `EG_SIMULATOR_CHECK_RAW` assumes an unstyled reference image. The raw
+//! check converts the display colors to `Rgb888` before they are compared, which means that the
+//! reference image needs to match the output of `EG_SIMULATOR_DUMP_RAW`.
+//!
+//! By default the display content needs to match the reference image exactly. Small differences,
+//! for example caused by anti-aliasing or the `fixed_point` feature, can be accepted by setting a
+//! tolerance. `EG_SIMULATOR_CHECK_TOLERANCE` sets the maximum difference per color channel and
+//! `EG_SIMULATOR_CHECK_MAX_PIXELS` sets the maximum number of pixels that are allowed to exceed
+//! the channel tolerance, either as an absolute count or as a percentage:
+//!
+//! ```bash
+//! EG_SIMULATOR_CHECK=screenshot.png EG_SIMULATOR_CHECK_TOLERANCE=1 EG_SIMULATOR_CHECK_MAX_PIXELS=0.5% cargo run
+//! ```
+//!
+//! The same comparison is available as [`SimulatorDisplay::diff_with_tolerance`].
+//!
+//! If the `EG_SIMULATOR_CHECK_OUTPUT_DIR` variable is set, a failed check writes the actual
+//! display content, the expected image and an image that highlights the differing pixels in red
+//! to the given directory. The file names are derived from the reference image name, e.g.
+//! `screenshot-actual.png`, `screenshot-expected.png` and `screenshot-diff.png`.
+//!
+//! ## Snapshot tests
+//!
+//! The environment variables above terminate the process after the first check, which makes them
+//! unsuitable for tests that check multiple screens. Inside tests
+//! [`SimulatorDisplay::assert_matches_png`] can be used instead, which returns a
+//! [`SnapshotError`] if the display doesn't match the reference image:
+//!
+//! ```rust,no_run
+//! use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+//! use embedded_graphics_simulator::{OutputSettings, SimulatorDisplay};
+//!
+//! let display = SimulatorDisplay::<BinaryColor>::new(Size::new(128, 64));
+//!
+//! // draw the main screen
+//!
+//! display
+//!     .assert_matches_png("tests/snapshots/main.png", &OutputSettings::default())
+//!     .unwrap();
+//! ```
+//!
+//! The tolerance is read from the environment variables described above. A fixed tolerance can be
+//! passed to [`SimulatorDisplay::assert_matches_png_with_tolerance`] instead.
+//!
+//! Reference images can be created or updated by running the tests with the
+//! `EG_SIMULATOR_UPDATE_SNAPSHOTS` environment variable set to `1`. In this mode missing or
+//! mismatching reference images are overwritten with the current display content instead of
+//! reporting an error. The variable also applies to `EG_SIMULATOR_CHECK` and
+//! `EG_SIMULATOR_CHECK_RAW`.
+//!
+//! ```bash
+//! EG_SIMULATOR_UPDATE_SNAPSHOTS=1 cargo test
+//! ```
+//!
+//! ## Testing input handling
+//!
+//! [`SimulatorEvent`] and the types in the [`input`] module don't depend on SDL2, which makes it
+//! possible to test code that handles input events without opening a window. A window created by
+//! [`Window::new_headless`] doesn't open an SDL window and [`Window::events`] only returns the
+//! events that were added by [`Window::push_event`]. Mouse positions in pushed events are
+//! translated from window to display coordinates, the same way as events from the SDL window:
+//!
+//! ```rust
+//! use embedded_graphics::prelude::*;
+//! use embedded_graphics_simulator::{
+//!     input::{Keycode, Mod},
+//!     OutputSettings, SimulatorEvent, Window,
+//! };
+//!
+//! let mut window = Window::new_headless(&OutputSettings::default());
+//!
+//! window.push_event(SimulatorEvent::KeyDown {
+//!     keycode: Keycode::Down,
+//!     keymod: Mod::NOMOD,
+//!     repeat: false,
+//! });
+//!
+//! for event in window.events() {
+//!     // handle the event like in the main loop of the application
+//! }
+//! ```
+//!
+//! ## Input scripts
+//!
+//! Complete application runs can be driven by a script file, which is set by the
+//! `EG_SIMULATOR_SCRIPT` environment variable. Each line of the script contains one step, empty
+//! lines and text after a `#` are ignored:
+//!
+//! ```text
+//! # wait for the splash screen to disappear
+//! wait 1.5s
+//! expect main.png
+//! key Down
+//! key Return
+//! click 10,20
+//! dump menu.png
+//! ```
+//!
+//! - `wait <duration>` waits until the given time has elapsed, e.g. `wait 500ms`.
+//! - `key <name>` presses and releases a key. The key names are the names of the [`Keycode`]
+//!   constants, e.g. `Down`, `Return` or `A`.
+//! - `click <x>,<y>` clicks the left mouse button at the given display coordinates.
+//! - `expect <path>` compares the display with a reference PNG file, in the same way as
+//!   `EG_SIMULATOR_CHECK`, and panics if it doesn't match.
+//! - `dump <path>` saves the display content as a PNG file.
+//!
+//! The steps are executed in [`Window::update`] and the events created by `key` and `click` steps
+//! are returned by [`Window::events`]. After a `key` or `click` step the remaining steps are
+//! executed in the next frame, to give the application a chance to react to the input. Relative
+//! paths are resolved relative to the directory that contains the script. The process is
+//! terminated after all steps have been executed.
+//!
+//! ```bash
+//! EG_SIMULATOR_SCRIPT=tests/menu-flow.txt cargo run
+//! ```
+//!
+//! Scripts can also contain steps that add single events, which are mainly used by recorded
+//! input logs:
+//!
+//! - `frame <index>` waits until the frame with the given index is passed to [`Window::update`].
+//! - `keydown <name> [modifiers] [repeat]` and `keyup <name> [modifiers] [repeat]` add key events.
+//!   Modifiers are separated by `+`, e.g. `LSHIFT+LCTRL`.
+//! - `mousedown <button> <x>,<y>` and `mouseup <button> <x>,<y>` add mouse button events for the
+//!   `left`, `middle`, `right`, `x1` or `x2` button.
+//! - `mousemove <x>,<y>` adds a mouse move event.
+//! - `wheel <x>,<y> [flipped]` adds a mouse wheel event.
+//! - `quit` adds a [`SimulatorEvent::Quit`] event.
+//!
+//! Unlike `key` and `click` these steps don't end the current frame.
+//!
+//! ## Recording input
+//!
+//! Interactive sessions can be recorded by setting the `EG_SIMULATOR_RECORD_INPUT` environment
+//! variable to the path of a log file. All events returned by [`Window::events`] are written to
+//! the log, grouped by the index of the last frame that was passed to [`Window::update`] and
+//! annotated with the elapsed time:
+//!
+//! ```text
+//! frame 42 # 0.700s
+//! keydown DOWN
+//! keyup DOWN
+//! frame 57 # 0.950s
+//! mousedown left 10,20
+//! mouseup left 10,20
+//! ```
+//!
+//! The log is a valid input script, which makes it possible to replay the session by setting
+//! `EG_SIMULATOR_SCRIPT` to the log file. `expect` or `dump` steps can be added to the log to turn
+//! it into a regression test.
+//!
+//! ```bash
+//! EG_SIMULATOR_RECORD_INPUT=session.log cargo run
+//! EG_SIMULATOR_SCRIPT=session.log cargo run
+//! ```
+//!
+//! `MultiWindow` also writes the events returned by `MultiWindow::events` to the log, grouped by
+//! the frames passed to `MultiWindow::flush`. Mouse positions are logged in window coordinates
+//! in this case. Input scripts are only supported by [`Window`], because script steps refer to a
+//! single display.
+//!
+//! ## Deterministic timing
+//!
+//! By default [`Window::update`] sleeps to limit the frame rate and all times, like the times used
+//! by `EG_SIMULATOR_DUMP_TIME`, `wait` steps and recordings, are measured in real time. This makes
+//! tests slow and their results dependent on the execution speed. The simulator can instead use a
+//! virtual clock that advances by a fixed time step of `1 / max_fps` for each frame without
+//! sleeping. The virtual clock is enabled by default for headless windows, can be enabled by
+//! calling [`Window::set_virtual_clock`] or by setting the `EG_SIMULATOR_VIRTUAL_CLOCK`
+//! environment variable to `1`:
+//!
+//! ```bash
+//! EG_SIMULATOR_VIRTUAL_CLOCK=1 EG_SIMULATOR_DUMP=screenshot.png EG_SIMULATOR_DUMP_TIME=10s cargo run
+//! ```
+//!
+//! Applications should use [`Window::now`] and [`Window::frame_time`] instead of the system time
+//! to drive animations, which ensures that frame N is identical in every run.
+//!
+//! ## Bus bandwidth
+//!
+//! The time that is required to transfer a frame to a real display over a SPI or I2C bus can be
+//! estimated by setting a [`TransferModel`] with [`Window::set_transfer_model`]. The model
+//! calculates the number of transferred bytes from the bits per pixel of the display color type
+//! and the regions that are flushed each frame, which is either the full frame or the area that was
+//! changed since the last frame. The estimated transfer time and the resulting maximum frame rate
+//! are shown in the window title and returned by [`Window::transfer_estimate`], which makes it
+//! possible to check that a UI fits into its frame budget in a test:
+//!
+//! ```rust
+//! use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+//! use embedded_graphics_simulator::{OutputSettings, SimulatorDisplay, TransferModel, Window};
+//!
+//! let mut window = Window::new_headless(&OutputSettings::default());
+//! window.set_transfer_model(Some(TransferModel::spi(40_000_000)));
+//!
+//! let display = SimulatorDisplay::<Rgb565>::new(Size::new(240, 240));
+//! window.update(&display);
+//!
+//! let estimate = window.transfer_estimate().unwrap();
+//! assert!(estimate.max_fps() >= 30.0, "frame budget exceeded: {estimate}");
+//! ```
+//!
+//! ## Palette displays
+//!
+//! Displays with a palettized framebuffer can be simulated by using [`IndexedColor`] as the color
+//! type. The palette is set by [`SimulatorDisplay::set_palette`] and applied when the display is
+//! shown, which makes it possible to animate the display by changing the palette without redrawing
+//! the content. The raw data returned by [`SimulatorDisplay::to_be_bytes`] contains the palette
+//! indices.
+//!
+//! ## E-paper displays
+//!
+//! The refresh behavior of e-paper displays can be simulated by enabling the e-paper mode with
+//! [`Window::set_epaper`]. In this mode new content is only shown after a refresh was requested by
+//! calling [`Window::refresh_full`] or [`Window::refresh_partial`]. Full refreshes flash the
+//! display with realistic timing and partial refreshes leave ghosting artifacts, which accumulate
+//! until the next full refresh. The timing and the strength of the ghosting can be configured by
+//! using [`EPaperSettings`].
+//!
+//! Three color e-paper displays can be simulated by using [`TriColor`] as the color type of the
+//! display. The pigment colors of real panels are reproduced by the
+//! [`EPaper`](BinaryColorTheme::EPaper), [`EPaperRed`](BinaryColorTheme::EPaperRed) and
+//! [`EPaperYellow`](BinaryColorTheme::EPaperYellow) themes, and
+//! [`SimulatorDisplay::to_bit_planes`] converts the display content into the black/white and
+//! chromatic bit planes used by e-paper controllers.
+//!
+//! ## Emulated display controllers
+//!
+//! Display drivers that send raw command and data bytes to the display can be tested with the
+//! emulated display controllers in the [`controller`] module. The [`Ssd1306`] controller
+//! maintains the display RAM of an SSD1306 OLED controller and renders the content that would be
+//! shown on the panel, including the effects of the invert, remap, start line and scroll
+//! commands. Color TFT displays with an ILI9341, ST7789 or similar controller can be emulated by
+//! [`MipiDcs`], which supports the address window, `MADCTL` rotation and color order, 16 and 18
+//! bit pixel formats, inversion, sleep and display on/off commands.
+//!
+//! Display driver crates that are based on the `display-interface` crate, like `ssd1306`, can be
+//! used unchanged by passing a `SimulatorInterface` to the driver. This requires the
+//! `display-interface` feature, see the `ssd1306-driver` example for more details. Without the
+//! feature, the bytes can be passed to the controller directly. The window doesn't know about the
+//! SSD1306 contrast setting, which is why the example applies it to the theme before each update:
+//!
+//! ```rust,no_run
+//! use embedded_graphics::prelude::*;
+//! use embedded_graphics_simulator::{
+//!     controller::{Controller, Ssd1306},
+//!     BinaryColorTheme, OutputSettingsBuilder, Window,
+//! };
+//!
+//! let mut controller = Ssd1306::new(Size::new(128, 64));
+//! let mut window = Window::new("SSD1306", &OutputSettingsBuilder::new().build());
+//!
+//! loop {
+//!     // Send commands and data to the controller, e.g. by using a display driver.
+//!     controller.write_commands(&[0xAF, 0xA7]);
+//!
+//!     controller.advance(window.frame_time());
+//!     window.set_output_settings(
+//!         &OutputSettingsBuilder::new()
+//!             .theme(controller.apply_contrast(BinaryColorTheme::OledBlue))
+//!             .build(),
+//!     );
+//!     window.update(controller.display());
+//! }
+//! ```
+//!
+//! [`Keycode`]: input::Keycode
+//! [`Ssd1306`]: controller::Ssd1306
+//! [`MipiDcs`]: controller::MipiDcs
 //!
 //! # Usage without SDL2
 //!
@@ -152,31 +471,49 @@
     rustdoc::private_intra_doc_links
 )]
 
+pub mod controller;
+mod diff;
 mod display;
+mod framebuffer;
+mod indexed_color;
+pub mod input;
 mod output_image;
 mod output_settings;
+mod profile;
+mod snapshot;
+#[cfg(test)]
+mod test_util;
 mod theme;
+mod transfer;
+mod tri_color;
 mod window;
 
-/// Re-exported types from sdl2 crate.
+/// Input types used in [`SimulatorEvent`]s.
 ///
-/// The types in this module are used in the [`SimulatorEvent`] enum and are re-exported from the
-/// `sdl2` crate to make it possible to use them without adding a dependency to `sdl2`.
+/// This module is kept for compatibility with older versions of this crate, which re-exported the
+/// types from the `sdl2` crate. New code should use the [`input`] module instead.
 #[cfg(feature = "with-sdl")]
 pub mod sdl2 {
-    pub use sdl2::{
-        keyboard::{Keycode, Mod},
-        mouse::{MouseButton, MouseWheelDirection},
-    };
+    pub use crate::input::{Keycode, Mod, MouseButton, MouseWheelDirection};
 }
 
 pub use crate::{
+    diff::{DiffStatistics, DiffTolerance, PixelBudget},
     display::SimulatorDisplay,
+    framebuffer::{BitOrder, ByteOrder, FramebufferError, FramebufferLayout, PixelOrder},
+    indexed_color::IndexedColor,
+    input::SimulatorEvent,
     output_image::OutputImage,
-    output_settings::{OutputSettings, OutputSettingsBuilder},
+    output_settings::{
+        OutputSettings, OutputSettingsBuilder, PixelShape, Rotation, SubpixelLayout,
+    },
+    profile::DrawProfile,
+    snapshot::SnapshotError,
     theme::BinaryColorTheme,
-    window::Window,
+    transfer::{FlushMode, TransferEstimate, TransferModel},
+    tri_color::TriColor,
+    window::{EPaperSettings, SimulatorEventsIter, Window},
 };
 
 #[cfg(feature = "with-sdl")]
-pub use window::{MultiWindow, SimulatorEvent, SimulatorEventsIter};
+pub use window::MultiWindow;
@@ -11,7 +11,11 @@ use image::{
     ImageBuffer, ImageEncoder, Luma, Rgb,
 };
 
-use crate::{display::SimulatorDisplay, output_settings::OutputSettings};
+use crate::{
+    display::SimulatorDisplay,
+    output_settings::{OutputSettings, PixelShape},
+    theme::interpolate,
+};
 
 /// Output image.
 ///
@@ -57,45 +61,229 @@ where
     ) where
         DisplayC: PixelColor + Into<Rgb888>,
     {
-        let display_area = Rectangle::new(position, display.output_size(output_settings));
-        self.fill_solid(
-            &display_area,
-            output_settings.theme.convert(Rgb888::BLACK).into(),
+        self.draw_display_area(display, &display.bounding_box(), position, output_settings);
+    }
+
+    /// Draws an area of a display using the given position and output setting.
+    ///
+    /// Only the output pixels that correspond to display pixels inside `area` are changed. If the
+    /// pixels glow, the pixels next to `area` are also redrawn, because the glow extends into the
+    /// neighboring pixels. The changed area in output image coordinates is returned.
+    pub(crate) fn draw_display_area<DisplayC>(
+        &mut self,
+        display: &SimulatorDisplay<DisplayC>,
+        area: &Rectangle,
+        position: Point,
+        output_settings: &OutputSettings,
+    ) -> Rectangle
+    where
+        DisplayC: PixelColor + Into<Rgb888>,
+    {
+        self.draw_display_area_with_background(
+            display,
+            area,
+            position,
+            output_settings,
+            output_settings.theme.background(),
         )
-        .unwrap();
+    }
+
+    /// Draws an area of a display with the given background color.
+    ///
+    /// This method works like [`draw_display_area`](Self::draw_display_area), but uses
+    /// `background` for the pixel spacing instead of the background of the theme. This is used
+    /// to draw displays that already contain themed colors with the default theme.
+    pub(crate) fn draw_display_area_with_background<DisplayC>(
+        &mut self,
+        display: &SimulatorDisplay<DisplayC>,
+        area: &Rectangle,
+        position: Point,
+        output_settings: &OutputSettings,
+        background: Rgb888,
+    ) -> Rectangle
+    where
+        DisplayC: PixelColor + Into<Rgb888>,
+    {
+        let area = area.intersection(&display.bounding_box());
+
+        if output_settings.pixel_shape != PixelShape::Square || output_settings.pixel_glow > 0 {
+            return self.draw_shaped_display_area(
+                display,
+                &area,
+                position,
+                output_settings,
+                background,
+            );
+        }
+
+        let output_area = output_settings.display_to_output_area(&area, display.size());
+        let output_area = Rectangle::new(output_area.top_left + position, output_area.size);
+        self.fill_solid(&output_area, background.into()).unwrap();
 
         if output_settings.scale == Size::new(1, 1) && output_settings.pixel_spacing == 0 {
-            display
-                .bounding_box()
-                .points()
+            area.points()
                 .map(|p| {
-                    let raw_color = display.get_pixel(p).into();
+                    let raw_color = display.output_color(p);
                     let themed_color = output_settings.theme.convert(raw_color);
                     let output_color = C::from(themed_color);
 
-                    Pixel(p + position, output_color)
+                    Pixel(
+                        output_settings.display_to_output(p, display.size()) + position,
+                        output_color,
+                    )
                 })
                 .draw(self)
                 .unwrap();
         } else {
-            for p in display.bounding_box().points() {
-                let raw_color = display.get_pixel(p).into();
+            let subpixels = self.subpixels(output_settings);
+
+            for p in area.points() {
+                let raw_color = display.output_color(p);
                 let themed_color = output_settings.theme.convert(raw_color);
-                let output_color = C::from(themed_color);
-
-                self.fill_solid(
-                    &Rectangle::new(
-                        p.component_mul(output_settings.pixel_pitch()) + position,
-                        output_settings.scale,
-                    ),
-                    output_color,
-                )
-                .unwrap();
+                let top_left = output_settings.display_to_output(p, display.size()) + position;
+
+                if let Some(subpixels) = &subpixels {
+                    for (subpixel, color) in subpixels.iter().zip(subpixel_colors(themed_color)) {
+                        self.fill_solid(&subpixel.translate(top_left), C::from(color))
+                            .unwrap();
+                    }
+                } else {
+                    let output_color = C::from(themed_color);
+
+                    self.fill_solid(
+                        &Rectangle::new(top_left, output_settings.output_scale()),
+                        output_color,
+                    )
+                    .unwrap();
+                }
             }
         }
+
+        output_area
+    }
+
+    /// Returns the subpixel areas that are used to draw a pixel.
+    ///
+    /// `None` is returned if the pixels shouldn't be divided into subpixels.
+    fn subpixels(&self, output_settings: &OutputSettings) -> Option<[Rectangle; 3]> {
+        if !C::SUBPIXELS {
+            return None;
+        }
+
+        output_settings
+            .output_subpixel_layout()
+            .subpixels(output_settings.output_scale())
+    }
+
+    /// Draws an area of a display with shaped or glowing pixels.
+    ///
+    /// Unlike square pixels, which are drawn by filling rectangles, the color of each output pixel
+    /// is calculated individually.
+    fn draw_shaped_display_area<DisplayC>(
+        &mut self,
+        display: &SimulatorDisplay<DisplayC>,
+        area: &Rectangle,
+        position: Point,
+        output_settings: &OutputSettings,
+        background: Rgb888,
+    ) -> Rectangle
+    where
+        DisplayC: PixelColor + Into<Rgb888>,
+    {
+        let glow = output_settings.pixel_glow > 0;
+        let area = if glow && !area.is_zero_sized() {
+            area.offset(1).intersection(&display.bounding_box())
+        } else {
+            *area
+        };
+
+        let pitch = output_settings.pixel_pitch();
+        let scale = output_settings.output_scale();
+        let shape = output_settings.pixel_shape;
+        let theme = output_settings.theme;
+
+        let glow_strength = f32::from(output_settings.pixel_glow) / 255.0;
+        let glow_length =
+            output_settings.pixel_spacing as f32 + scale.width.min(scale.height) as f32 / 2.0;
+        let neighbors = if glow { -1..=1 } else { 0..=0 };
+        let subpixels = self.subpixels(output_settings);
+
+        // Returns the display pixel that is drawn at a pixel position in the output.
+        let display_pixel = |cell: Point| {
+            let point =
+                output_settings.output_to_display(cell.component_mul(pitch), display.size());
+
+            display
+                .bounding_box()
+                .contains(point)
+                .then(|| theme.convert(display.output_color(point)))
+        };
+
+        let output_area = output_settings.display_to_output_area(&area, display.size());
+        output_area
+            .points()
+            .map(|point| {
+                let cell = point.component_div(pitch);
+
+                let own_color = display_pixel(cell).unwrap();
+                let offset = point - cell.component_mul(pitch);
+                if shape.distance(offset, scale) <= 0.0 {
+                    let color = match &subpixels {
+                        Some(subpixels) => subpixels
+                            .iter()
+                            .zip(subpixel_colors(own_color))
+                            .find(|(subpixel, _)| subpixel.contains(offset))
+                            .map_or(Rgb888::BLACK, |(_, color)| color),
+                        None => own_color,
+                    };
+
+                    return Pixel(point + position, C::from(color));
+                }
+
+                // Pixels outside of the pixel shape show the most visible glow.
+                let mut output_color = background;
+                let mut max_contrast = 0.0;
+                for dy in neighbors.clone() {
+                    for dx in neighbors.clone() {
+                        let cell = cell + Point::new(dx, dy);
+                        let Some(color) = display_pixel(cell) else {
+                            continue;
+                        };
+
+                        let distance = shape.distance(point - cell.component_mul(pitch), scale);
+                        let intensity = glow_strength * (1.0 - distance / glow_length);
+                        let contrast = intensity * color_difference(color, background);
+                        if contrast > max_contrast {
+                            max_contrast = contrast;
+                            output_color =
+                                interpolate(background, color, (intensity * 255.0) as u8);
+                        }
+                    }
+                }
+
+                Pixel(point + position, C::from(output_color))
+            })
+            .draw(self)
+            .unwrap();
+
+        Rectangle::new(output_area.top_left + position, output_area.size)
     }
 }
 
+/// Returns the colors of the red, green and blue subpixel.
+fn subpixel_colors(color: Rgb888) -> [Rgb888; 3] {
+    [
+        Rgb888::new(color.r(), 0, 0),
+        Rgb888::new(0, color.g(), 0),
+        Rgb888::new(0, 0, color.b()),
+    ]
+}
+
+/// Returns the sum of the absolute differences of the color channels.
+fn color_difference(a: Rgb888, b: Rgb888) -> f32 {
+    (a.r().abs_diff(b.r()) as f32) + (a.g().abs_diff(b.g()) as f32) + (a.b().abs_diff(b.b()) as f32)
+}
+
 impl<C: OutputImageColor> OutputImage<C> {
     /// Saves the image content to a PNG file.
     pub fn save_png<PATH: AsRef<Path>>(&self, path: PATH) -> image::ImageResult<()> {
@@ -133,6 +321,22 @@ impl<C: OutputImageColor> OutputImage<C> {
     }
 }
 
+impl OutputImage<Rgb888> {
+    /// Converts the output image into a display.
+    pub(crate) fn to_display(&self) -> SimulatorDisplay<Rgb888> {
+        let mut display = SimulatorDisplay::with_default_color(self.size, Rgb888::BLACK);
+
+        self.bounding_box()
+            .points()
+            .zip(self.data.chunks_exact(3))
+            .map(|(p, bytes)| Pixel(p, Rgb888::new(bytes[0], bytes[1], bytes[2])))
+            .draw(&mut display)
+            .unwrap();
+
+        display
+    }
+}
+
 impl DrawTarget for OutputImage<Rgb888> {
     type Color = Rgb888;
     type Error = ();
@@ -249,24 +453,101 @@ pub trait OutputImageColor {
     type ImageColor: image::Pixel<Subpixel = u8> + 'static;
     const IMAGE_COLOR_TYPE: image::ColorType;
     const BYTES_PER_PIXEL: usize;
+    const SUBPIXELS: bool;
 }
 
 impl OutputImageColor for Gray8 {
     type ImageColor = Luma<u8>;
     const IMAGE_COLOR_TYPE: image::ColorType = image::ColorType::L8;
     const BYTES_PER_PIXEL: usize = 1;
+    const SUBPIXELS: bool = false;
 }
 
 impl OutputImageColor for Rgb888 {
     type ImageColor = Rgb<u8>;
     const IMAGE_COLOR_TYPE: image::ColorType = image::ColorType::Rgb8;
     const BYTES_PER_PIXEL: usize = 3;
+    const SUBPIXELS: bool = true;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    use crate::{BinaryColorTheme, OutputSettingsBuilder, Rotation, SubpixelLayout};
+
+    fn shaped_pixels(output_settings: &OutputSettings) -> OutputImage<Gray8> {
+        let mut display = SimulatorDisplay::<Gray8>::new(Size::new(2, 1));
+        Pixel(Point::zero(), Gray8::WHITE)
+            .draw(&mut display)
+            .unwrap();
+
+        display.to_grayscale_output_image(output_settings)
+    }
+
+    #[test]
+    fn circle_pixels() {
+        let output_settings = OutputSettingsBuilder::new()
+            .scale(4)
+            .pixel_spacing(2)
+            .pixel_shape(PixelShape::Circle)
+            .build();
+        let image = shaped_pixels(&output_settings);
+
+        let expected: &[u8] = &[
+            0, 255, 255, 0, 0, 0, 0, 0, 0, 0, //
+            255, 255, 255, 255, 0, 0, 0, 0, 0, 0, //
+            255, 255, 255, 255, 0, 0, 0, 0, 0, 0, //
+            0, 255, 255, 0, 0, 0, 0, 0, 0, 0, //
+        ];
+        assert_eq!(image.data.as_ref(), expected);
+    }
+
+    #[test]
+    fn pixel_glow() {
+        let output_settings = OutputSettingsBuilder::new()
+            .scale(4)
+            .pixel_spacing(2)
+            .pixel_glow(255)
+            .build();
+        let image = shaped_pixels(&output_settings);
+
+        let expected: &[u8] = &[255, 255, 255, 255, 223, 159, 0, 0, 0, 0];
+        for row in image.data.chunks(10) {
+            assert_eq!(row, expected);
+        }
+    }
+
+    #[test]
+    fn subpixels() {
+        let display = SimulatorDisplay::with_default_color(Size::new(1, 1), Rgb888::WHITE);
+
+        let output_settings = OutputSettingsBuilder::new()
+            .scale(3)
+            .subpixel_layout(SubpixelLayout::Bgr)
+            .build();
+        let image = display.to_rgb_output_image(&output_settings);
+        for row in image.data.chunks(9) {
+            assert_eq!(row, [0, 0, 255, 0, 255, 0, 255, 0, 0]);
+        }
+
+        let output_settings = OutputSettingsBuilder::new()
+            .scale(3)
+            .subpixel_layout(SubpixelLayout::Rgb)
+            .rotation(Rotation::Deg90)
+            .build();
+        let image = display.to_rgb_output_image(&output_settings);
+        let rows = image.data.chunks(9).collect::<Vec<_>>();
+        assert_eq!(rows[0], [255, 0, 0, 255, 0, 0, 255, 0, 0]);
+        assert_eq!(rows[1], [0, 255, 0, 0, 255, 0, 0, 255, 0]);
+        assert_eq!(rows[2], [0, 0, 255, 0, 0, 255, 0, 0, 255]);
+
+        let image = display.to_grayscale_output_image(&output_settings);
+        assert_eq!(image.data.as_ref(), [255; 9]);
+    }
+
     #[test]
     fn rgb888_default_data() {
         let image = OutputImage::<Rgb888>::new(Size::new(6, 5));
@@ -388,4 +669,36 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn draw_display_area() {
+        let output_settings = OutputSettingsBuilder::new()
+            .theme(BinaryColorTheme::LcdGreen)
+            .scale(2)
+            .pixel_spacing(1)
+            .build();
+
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(5, 4));
+        let mut image = display.to_rgb_output_image(&output_settings);
+
+        Pixel(Point::new(1, 2), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+        Pixel(Point::new(3, 1), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+
+        let output_area = image.draw_display_area(
+            &display,
+            &Rectangle::new(Point::new(1, 1), Size::new(3, 2)),
+            Point::zero(),
+            &output_settings,
+        );
+
+        assert_eq!(
+            output_area,
+            Rectangle::new(Point::new(3, 3), Size::new(8, 5))
+        );
+        assert_eq!(image, display.to_rgb_output_image(&output_settings));
+    }
 }
@@ -1,5 +1,5 @@
 use crate::theme::BinaryColorTheme;
-use embedded_graphics::prelude::*;
+use embedded_graphics::{prelude::*, primitives::Rectangle};
 
 /// Output settings.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -10,19 +10,143 @@ pub struct OutputSettings {
     pub pixel_spacing: u32,
     /// Binary color theme.
     pub theme: BinaryColorTheme,
+    /// Rotation of the display.
+    pub rotation: Rotation,
+    /// Mirrors the display horizontally.
+    pub flip_horizontal: bool,
+    /// Mirrors the display vertically.
+    pub flip_vertical: bool,
+    /// Shape of the pixels.
+    pub pixel_shape: PixelShape,
+    /// Strength of the glow around pixels.
+    ///
+    /// A value of `0` disables the glow and `255` is the strongest glow.
+    pub pixel_glow: u8,
+    /// Subpixel layout of the display.
+    pub subpixel_layout: SubpixelLayout,
 }
 
 impl OutputSettings {
     /// Translates a output coordinate to the corresponding display coordinate.
-    #[cfg(feature = "with-sdl")]
-    pub(crate) const fn output_to_display(&self, output_point: Point) -> Point {
-        output_point.component_div(self.pixel_pitch())
+    pub(crate) fn output_to_display(&self, output_point: Point, display_size: Size) -> Point {
+        let point = output_point.component_div(self.pixel_pitch());
+
+        let (width, height) = (display_size.width as i32, display_size.height as i32);
+        let point = match self.rotation {
+            Rotation::Deg0 => point,
+            Rotation::Deg90 => Point::new(point.y, height - 1 - point.x),
+            Rotation::Deg180 => Point::new(width - 1 - point.x, height - 1 - point.y),
+            Rotation::Deg270 => Point::new(width - 1 - point.y, point.x),
+        };
+
+        self.flip(point, display_size)
+    }
+
+    /// Translates a display coordinate to the top left corner of the corresponding output pixel.
+    pub(crate) fn display_to_output(&self, point: Point, display_size: Size) -> Point {
+        let point = self.flip(point, display_size);
+
+        let (width, height) = (display_size.width as i32, display_size.height as i32);
+        let point = match self.rotation {
+            Rotation::Deg0 => point,
+            Rotation::Deg90 => Point::new(height - 1 - point.y, point.x),
+            Rotation::Deg180 => Point::new(width - 1 - point.x, height - 1 - point.y),
+            Rotation::Deg270 => Point::new(point.y, width - 1 - point.x),
+        };
+
+        point.component_mul(self.pixel_pitch())
+    }
+
+    /// Translates a display area to the corresponding output area.
+    pub(crate) fn display_to_output_area(&self, area: &Rectangle, display_size: Size) -> Rectangle {
+        let Some(bottom_right) = area.bottom_right() else {
+            return Rectangle::new(
+                self.display_to_output(area.top_left, display_size),
+                Size::zero(),
+            );
+        };
+
+        let pitch = self.pixel_pitch();
+        let corners = Rectangle::with_corners(
+            self.display_to_output(area.top_left, display_size),
+            self.display_to_output(bottom_right, display_size),
+        );
+        let size = Size::new(
+            (corners.size.width - 1) / pitch.x as u32 + 1,
+            (corners.size.height - 1) / pitch.y as u32 + 1,
+        );
+
+        Rectangle::new(
+            corners.top_left,
+            size.component_mul(self.output_scale())
+                + size.saturating_sub(Size::new_equal(1)) * self.pixel_spacing,
+        )
+    }
+
+    /// Returns the size of a display pixel in the output.
+    ///
+    /// The scale is swapped if the display is rotated by 90 or 270 degrees.
+    pub(crate) const fn output_scale(&self) -> Size {
+        match self.rotation {
+            Rotation::Deg0 | Rotation::Deg180 => self.scale,
+            Rotation::Deg90 | Rotation::Deg270 => Size::new(self.scale.height, self.scale.width),
+        }
+    }
+
+    /// Returns the subpixel layout in the output.
+    ///
+    /// The subpixels are rotated with the display.
+    pub(crate) fn output_subpixel_layout(&self) -> SubpixelLayout {
+        // Each layout is followed by the layout that is visible after a clockwise rotation by 90
+        // degrees.
+        const LAYOUTS: [SubpixelLayout; 4] = [
+            SubpixelLayout::Rgb,
+            SubpixelLayout::VerticalRgb,
+            SubpixelLayout::Bgr,
+            SubpixelLayout::VerticalBgr,
+        ];
+
+        let Some(index) = LAYOUTS.iter().position(|l| *l == self.subpixel_layout) else {
+            return self.subpixel_layout;
+        };
+
+        let quarter_turns = match self.rotation {
+            Rotation::Deg0 => 0,
+            Rotation::Deg90 => 1,
+            Rotation::Deg180 => 2,
+            Rotation::Deg270 => 3,
+        };
+
+        LAYOUTS[(index + quarter_turns) % LAYOUTS.len()]
     }
 
     pub(crate) const fn pixel_pitch(&self) -> Point {
+        let scale = self.output_scale();
+
         Point::new(
-            (self.scale.width + self.pixel_spacing) as i32,
-            (self.scale.height + self.pixel_spacing) as i32,
+            (scale.width + self.pixel_spacing) as i32,
+            (scale.height + self.pixel_spacing) as i32,
+        )
+    }
+
+    /// Returns `true` if the translation between output and display coordinates depends on the
+    /// display size.
+    pub(crate) fn depends_on_display_size(&self) -> bool {
+        !matches!(self.rotation, Rotation::Deg0) || self.flip_horizontal || self.flip_vertical
+    }
+
+    fn flip(&self, point: Point, display_size: Size) -> Point {
+        Point::new(
+            if self.flip_horizontal {
+                display_size.width as i32 - 1 - point.x
+            } else {
+                point.x
+            },
+            if self.flip_vertical {
+                display_size.height as i32 - 1 - point.y
+            } else {
+                point.y
+            },
         )
     }
 }
@@ -33,12 +157,149 @@ impl Default for OutputSettings {
     }
 }
 
+/// Display rotation.
+///
+/// The rotation is applied clockwise, after the display was mirrored by the flip settings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    /// No rotation.
+    #[default]
+    Deg0,
+    /// Rotation by 90 degrees.
+    Deg90,
+    /// Rotation by 180 degrees.
+    Deg180,
+    /// Rotation by 270 degrees.
+    Deg270,
+}
+
+/// Pixel shape.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelShape {
+    /// Square pixels, which fill the entire area of a pixel.
+    #[default]
+    Square,
+    /// Round pixels, like the LEDs of a LED matrix.
+    ///
+    /// Pixels with a non-square scale are drawn as ellipses.
+    Circle,
+    /// Squares with rounded corners.
+    RoundedSquare,
+}
+
+impl PixelShape {
+    /// Returns the distance of an output pixel to the edge of the shape.
+    ///
+    /// `point` is the position of the output pixel relative to the top left corner of the display
+    /// pixel and `size` is the size of the display pixel in the output. Negative distances are
+    /// returned for output pixels inside the shape.
+    pub(crate) fn distance(self, point: Point, size: Size) -> f32 {
+        let (width, height) = (size.width as f32, size.height as f32);
+
+        // Distance of the output pixel center from the center of the shape.
+        let x = (point.x as f32 + 0.5 - width / 2.0).abs();
+        let y = (point.y as f32 + 0.5 - height / 2.0).abs();
+
+        match self {
+            PixelShape::Square => rounded_rectangle_distance(x, y, width, height, 0.0),
+            PixelShape::Circle => {
+                let (radius_x, radius_y) = (width / 2.0, height / 2.0);
+
+                ((x / radius_x).hypot(y / radius_y) - 1.0) * radius_x.min(radius_y)
+            }
+            PixelShape::RoundedSquare => {
+                rounded_rectangle_distance(x, y, width, height, width.min(height) / 4.0)
+            }
+        }
+    }
+}
+
+/// Subpixel layout.
+///
+/// The subpixel layout describes the order and orientation of the red, green and blue subpixels
+/// of a LCD. The subpixels are only drawn if a pixel is at least 3 output pixels wide, or high for
+/// vertical layouts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubpixelLayout {
+    /// No subpixels.
+    #[default]
+    None,
+    /// Vertical stripes in red, green, blue order from left to right.
+    Rgb,
+    /// Vertical stripes in blue, green, red order from left to right.
+    Bgr,
+    /// Horizontal stripes in red, green, blue order from top to bottom.
+    VerticalRgb,
+    /// Horizontal stripes in blue, green, red order from top to bottom.
+    VerticalBgr,
+}
+
+impl SubpixelLayout {
+    /// Returns the areas of the red, green and blue subpixel.
+    ///
+    /// The areas are relative to the top left corner of a pixel with the given size. `None` is
+    /// returned if the layout is [`None`](Self::None) or the pixel is too small to be divided.
+    pub(crate) fn subpixels(self, size: Size) -> Option<[Rectangle; 3]> {
+        let (horizontal, reversed) = match self {
+            SubpixelLayout::None => return None,
+            SubpixelLayout::Rgb => (true, false),
+            SubpixelLayout::Bgr => (true, true),
+            SubpixelLayout::VerticalRgb => (false, false),
+            SubpixelLayout::VerticalBgr => (false, true),
+        };
+
+        let length = if horizontal { size.width } else { size.height };
+        if length < 3 {
+            return None;
+        }
+
+        let mut subpixels = [0, 1, 2].map(|i| {
+            let start = i * length / 3;
+            let end = (i + 1) * length / 3;
+
+            if horizontal {
+                Rectangle::new(
+                    Point::new(start as i32, 0),
+                    Size::new(end - start, size.height),
+                )
+            } else {
+                Rectangle::new(
+                    Point::new(0, start as i32),
+                    Size::new(size.width, end - start),
+                )
+            }
+        });
+
+        if reversed {
+            subpixels.reverse();
+        }
+
+        Some(subpixels)
+    }
+}
+
+/// Returns the distance of a point to the edge of a rounded rectangle.
+///
+/// The point is given relative to the center of the rectangle and must be in the first quadrant.
+fn rounded_rectangle_distance(x: f32, y: f32, width: f32, height: f32, radius: f32) -> f32 {
+    let dx = x - (width / 2.0 - radius);
+    let dy = y - (height / 2.0 - radius);
+
+    dx.max(0.0).hypot(dy.max(0.0)) + dx.max(dy).min(0.0) - radius
+}
+
 /// Output settings builder.
 #[derive(Default)]
 pub struct OutputSettingsBuilder {
     scale: Option<Size>,
     pixel_spacing: Option<u32>,
     theme: BinaryColorTheme,
+    rotation: Rotation,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+    pixel_shape: PixelShape,
+    pixel_glow: u8,
+    subpixel_layout: SubpixelLayout,
 }
 
 impl OutputSettingsBuilder {
@@ -112,12 +373,79 @@ impl OutputSettingsBuilder {
         self
     }
 
+    /// Sets the rotation.
+    ///
+    /// This can be used to simulate a display that is mounted rotated. The display is rotated
+    /// clockwise and the pixel scale is rotated with the display. Mouse positions in events are
+    /// translated back into the coordinate system of the display.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+
+        self
+    }
+
+    /// Mirrors the display horizontally.
+    ///
+    /// Mirroring is applied before the display is rotated.
+    pub fn flip_horizontal(mut self, flip_horizontal: bool) -> Self {
+        self.flip_horizontal = flip_horizontal;
+
+        self
+    }
+
+    /// Mirrors the display vertically.
+    ///
+    /// Mirroring is applied before the display is rotated.
+    pub fn flip_vertical(mut self, flip_vertical: bool) -> Self {
+        self.flip_vertical = flip_vertical;
+
+        self
+    }
+
+    /// Sets the pixel shape.
+    ///
+    /// Round pixels can be used to simulate LED matrices. The shape is only visible if the pixel
+    /// scale is large enough, e.g. `4` or higher for circles.
+    pub fn pixel_shape(mut self, pixel_shape: PixelShape) -> Self {
+        self.pixel_shape = pixel_shape;
+
+        self
+    }
+
+    /// Sets the strength of the glow around pixels.
+    ///
+    /// The glow makes pixels bleed into the gap between pixels, which is set by
+    /// [`pixel_spacing`](Self::pixel_spacing), and fades out with the distance from the pixel. A
+    /// value of `0` disables the glow.
+    pub fn pixel_glow(mut self, pixel_glow: u8) -> Self {
+        self.pixel_glow = pixel_glow;
+
+        self
+    }
+
+    /// Sets the subpixel layout.
+    ///
+    /// If a subpixel layout is set, each pixel is drawn as separate red, green and blue
+    /// subpixels, which can be used to review the color fringing of subpixel rendered text. The
+    /// subpixels are only drawn in RGB output images and if the pixel scale is at least `3`.
+    pub fn subpixel_layout(mut self, subpixel_layout: SubpixelLayout) -> Self {
+        self.subpixel_layout = subpixel_layout;
+
+        self
+    }
+
     /// Builds the output settings.
     pub fn build(self) -> OutputSettings {
         OutputSettings {
             scale: self.scale.unwrap_or(Size::new_equal(1)),
             pixel_spacing: self.pixel_spacing.unwrap_or(0),
             theme: self.theme,
+            rotation: self.rotation,
+            flip_horizontal: self.flip_horizontal,
+            flip_vertical: self.flip_vertical,
+            pixel_shape: self.pixel_shape,
+            pixel_glow: self.pixel_glow,
+            subpixel_layout: self.subpixel_layout,
         }
     }
 }
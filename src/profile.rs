@@ -0,0 +1,185 @@
+use std::fmt;
+
+use embedded_graphics::{pixelcolor::Rgb888, prelude::*, primitives::Rectangle};
+
+use crate::{
+    display::SimulatorDisplay, output_image::OutputImage, output_settings::OutputSettings,
+    theme::BinaryColorTheme,
+};
+
+/// Colors used to draw the heatmap, indexed by the number of writes.
+const HEATMAP_COLORS: [Rgb888; 6] = [
+    Rgb888::BLACK,
+    Rgb888::new(0, 0, 160),
+    Rgb888::new(0, 160, 220),
+    Rgb888::new(0, 200, 0),
+    Rgb888::new(240, 220, 0),
+    Rgb888::new(240, 0, 0),
+];
+
+/// Draw call profile of a display.
+///
+/// The profile contains the number of draw calls and pixel writes since profiling was enabled by
+/// [`SimulatorDisplay::set_profiling`] or the profile was reset by
+/// [`SimulatorDisplay::reset_profile`]. Only writes to pixels inside the display are counted.
+///
+/// [`SimulatorDisplay::set_profiling`]: crate::SimulatorDisplay::set_profiling
+/// [`SimulatorDisplay::reset_profile`]: crate::SimulatorDisplay::reset_profile
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DrawProfile {
+    /// Number of pixels written.
+    pub pixels_written: u64,
+    /// Number of pixel writes that didn't change the color of the pixel.
+    pub redundant_writes: u64,
+    /// Number of `draw_iter` calls.
+    pub draw_iter_calls: u64,
+    /// Number of `fill_solid` calls.
+    pub fill_solid_calls: u64,
+    /// Number of `fill_contiguous` calls.
+    pub fill_contiguous_calls: u64,
+    /// Number of `clear` calls.
+    pub clear_calls: u64,
+    size: Size,
+    writes: Box<[u32]>,
+}
+
+impl DrawProfile {
+    pub(crate) fn new(size: Size) -> Self {
+        Self {
+            pixels_written: 0,
+            redundant_writes: 0,
+            draw_iter_calls: 0,
+            fill_solid_calls: 0,
+            fill_contiguous_calls: 0,
+            clear_calls: 0,
+            size,
+            writes: vec![0; size.width as usize * size.height as usize].into_boxed_slice(),
+        }
+    }
+
+    /// Records a pixel write.
+    pub(crate) fn record_write(&mut self, index: usize, redundant: bool) {
+        self.pixels_written += 1;
+        self.redundant_writes += u64::from(redundant);
+        self.writes[index] = self.writes[index].saturating_add(1);
+    }
+
+    /// Returns the number of writes to a pixel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `point` is outside the display.
+    pub fn writes(&self, point: Point) -> u32 {
+        let area = Rectangle::new(Point::zero(), self.size);
+        assert!(area.contains(point), "can't get point outside of display");
+
+        self.writes[point.x as usize + point.y as usize * self.size.width as usize]
+    }
+
+    /// Returns the largest number of writes to a single pixel.
+    pub fn max_writes(&self) -> u32 {
+        self.writes.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Returns the number of pixels that were written more than once.
+    pub fn overdrawn_pixels(&self) -> usize {
+        self.writes.iter().filter(|writes| **writes > 1).count()
+    }
+
+    /// Converts the number of writes per pixel into a heatmap.
+    ///
+    /// Pixels that weren't written are black and pixels that were written once are blue. Pixels
+    /// with more writes are drawn in cyan, green and yellow, and pixels with 5 or more writes are
+    /// red. The theme of the output settings isn't used.
+    pub fn heatmap(&self, output_settings: &OutputSettings) -> OutputImage<Rgb888> {
+        let mut display = SimulatorDisplay::with_default_color(self.size, Rgb888::BLACK);
+
+        Rectangle::new(Point::zero(), self.size)
+            .points()
+            .zip(self.writes.iter())
+            .map(|(point, writes)| {
+                let index = (*writes as usize).min(HEATMAP_COLORS.len() - 1);
+
+                Pixel(point, HEATMAP_COLORS[index])
+            })
+            .draw(&mut display)
+            .unwrap();
+
+        display.to_rgb_output_image(&OutputSettings {
+            theme: BinaryColorTheme::Default,
+            ..*output_settings
+        })
+    }
+}
+
+impl fmt::Display for DrawProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} pixels written ({} redundant), {} overdrawn pixels, draw calls: {} draw_iter, {} fill_solid, {} fill_contiguous, {} clear",
+            self.pixels_written,
+            self.redundant_writes,
+            self.overdrawn_pixels(),
+            self.draw_iter_calls,
+            self.fill_solid_calls,
+            self.fill_contiguous_calls,
+            self.clear_calls,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_graphics::{
+        pixelcolor::BinaryColor,
+        primitives::{Line, PrimitiveStyle},
+    };
+
+    #[test]
+    fn profile() {
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(4, 3));
+        display.set_profiling(true);
+
+        display.clear(BinaryColor::Off).unwrap();
+        display
+            .fill_solid(
+                &Rectangle::new(Point::new(2, 1), Size::new(10, 10)),
+                BinaryColor::On,
+            )
+            .unwrap();
+        Line::new(Point::new(0, 0), Point::new(3, 0))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(&mut display)
+            .unwrap();
+        display
+            .fill_contiguous(
+                &Rectangle::new(Point::new(3, 2), Size::new(2, 1)),
+                [BinaryColor::On, BinaryColor::Off],
+            )
+            .unwrap();
+
+        let profile = display.profile().unwrap();
+        assert_eq!(profile.pixels_written, 12 + 4 + 4 + 1);
+        assert_eq!(profile.redundant_writes, 12 + 1);
+        assert_eq!(profile.draw_iter_calls, 1);
+        assert_eq!(profile.fill_solid_calls, 1);
+        assert_eq!(profile.fill_contiguous_calls, 1);
+        assert_eq!(profile.clear_calls, 1);
+        assert_eq!(profile.writes(Point::new(3, 2)), 3);
+        assert_eq!(profile.writes(Point::new(1, 1)), 1);
+        assert_eq!(profile.max_writes(), 3);
+        assert_eq!(profile.overdrawn_pixels(), 4 + 4);
+
+        let heatmap = profile.heatmap(&OutputSettings::default()).to_display();
+        assert_eq!(heatmap.get_pixel(Point::new(1, 1)), HEATMAP_COLORS[1]);
+        assert_eq!(heatmap.get_pixel(Point::new(3, 2)), HEATMAP_COLORS[3]);
+
+        display.reset_profile();
+        assert_eq!(display.profile().unwrap().pixels_written, 0);
+
+        display.set_profiling(false);
+        assert_eq!(display.profile(), None);
+    }
+}
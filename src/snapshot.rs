@@ -0,0 +1,231 @@
+use std::{
+    env, error, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+
+use crate::{
+    diff::{self, DiffStatistics, DiffTolerance},
+    display::SimulatorDisplay,
+    output_settings::OutputSettings,
+};
+
+/// Error returned by a failed snapshot comparison.
+///
+/// See [`SimulatorDisplay::assert_matches_png`] for more details.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The reference image couldn't be loaded or the updated reference image couldn't be saved.
+    Image(image::ImageError),
+    /// The display dimensions don't match the reference image dimensions.
+    SizeMismatch {
+        /// Size of the display.
+        display: Size,
+        /// Size of the reference image.
+        reference: Size,
+    },
+    /// The display content doesn't match the reference image.
+    ContentMismatch {
+        /// Statistics about the difference.
+        statistics: DiffStatistics,
+        /// Directory the actual, expected and difference images were written to.
+        ///
+        /// `None` if `EG_SIMULATOR_CHECK_OUTPUT_DIR` isn't set.
+        output_dir: Option<PathBuf>,
+    },
+    /// A tolerance environment variable contains an invalid value.
+    InvalidTolerance {
+        /// Name of the environment variable.
+        variable: &'static str,
+        /// Value of the environment variable.
+        value: String,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Image(error) => write!(f, "reference image error: {error}"),
+            SnapshotError::SizeMismatch { display, reference } => write!(
+                f,
+                "display dimensions don't match PNG dimensions (display: {}x{}, PNG: {}x{})",
+                display.width, display.height, reference.width, reference.height
+            ),
+            SnapshotError::ContentMismatch {
+                statistics,
+                output_dir,
+            } => {
+                write!(f, "display content doesn't match PNG file ({statistics})")?;
+
+                if let Some(output_dir) = output_dir {
+                    write!(f, ", images written to {}", output_dir.display())?;
+                }
+
+                Ok(())
+            }
+            SnapshotError::InvalidTolerance { variable, value } => {
+                let expected = match *variable {
+                    "EG_SIMULATOR_CHECK_TOLERANCE" => "0-255",
+                    _ => "pixel count or percentage",
+                };
+
+                write!(
+                    f,
+                    "invalid {variable} value {value:?} (expected {expected})"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SnapshotError::Image(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<image::ImageError> for SnapshotError {
+    fn from(error: image::ImageError) -> Self {
+        Self::Image(error)
+    }
+}
+
+/// Returns `true` if the `EG_SIMULATOR_UPDATE_SNAPSHOTS` environment variable is set.
+pub(crate) fn update_snapshots() -> bool {
+    env::var_os("EG_SIMULATOR_UPDATE_SNAPSHOTS")
+        .is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Compares a display with a reference PNG file.
+///
+/// If `update` is `true` the reference image is replaced by the display content if it is missing
+/// or doesn't match.
+pub(crate) fn check_png<C>(
+    path: &Path,
+    display: &SimulatorDisplay<C>,
+    tolerance: &DiffTolerance,
+    update: bool,
+) -> Result<(), SnapshotError>
+where
+    C: PixelColor + Into<Rgb888> + From<Rgb888>,
+{
+    let expected = match SimulatorDisplay::<C>::load_png(path) {
+        Ok(expected) => expected,
+        Err(_) if update => return save_png(path, display),
+        Err(error) => return Err(error.into()),
+    };
+
+    if display.size() != expected.size() {
+        if update {
+            return save_png(path, display);
+        }
+
+        return Err(SnapshotError::SizeMismatch {
+            display: display.size(),
+            reference: expected.size(),
+        });
+    }
+
+    let statistics = display.diff_with_tolerance(&expected, tolerance);
+    if statistics.within_tolerance {
+        Ok(())
+    } else if update {
+        save_png(path, display)
+    } else {
+        Err(SnapshotError::ContentMismatch {
+            statistics,
+            output_dir: diff::write_check_images(path, display, &expected, tolerance),
+        })
+    }
+}
+
+fn save_png<C>(path: &Path, display: &SimulatorDisplay<C>) -> Result<(), SnapshotError>
+where
+    C: PixelColor + Into<Rgb888>,
+{
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(image::ImageError::IoError)?;
+    }
+
+    display
+        .to_rgb_output_image(&OutputSettings::default())
+        .save_png(path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+
+    use crate::test_util::TempDir;
+
+    fn test_display() -> SimulatorDisplay<Rgb888> {
+        let mut display = SimulatorDisplay::new(Size::new(8, 6));
+
+        Rectangle::new(Point::new(1, 2), Size::new(3, 2))
+            .into_styled(PrimitiveStyle::with_fill(Rgb888::CSS_ORANGE))
+            .draw(&mut display)
+            .unwrap();
+
+        display
+    }
+
+    #[test]
+    fn missing_reference() {
+        let dir = TempDir::new("snapshot");
+        let path = dir.join("missing.png");
+
+        assert!(matches!(
+            check_png(&path, &test_display(), &DiffTolerance::default(), false),
+            Err(SnapshotError::Image(_))
+        ));
+    }
+
+    #[test]
+    fn update_and_compare() {
+        let dir = TempDir::new("snapshot");
+        let path = dir.join("update.png");
+        let mut display = test_display();
+
+        check_png(&path, &display, &DiffTolerance::default(), true).unwrap();
+        check_png(&path, &display, &DiffTolerance::default(), false).unwrap();
+
+        Pixel(Point::new(7, 5), Rgb888::WHITE)
+            .draw(&mut display)
+            .unwrap();
+
+        match check_png(&path, &display, &DiffTolerance::default(), false) {
+            Err(SnapshotError::ContentMismatch { statistics, .. }) => {
+                assert_eq!(statistics.differing_pixels, 1);
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+
+        check_png(&path, &display, &DiffTolerance::default(), true).unwrap();
+        check_png(&path, &display, &DiffTolerance::default(), false).unwrap();
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let dir = TempDir::new("snapshot");
+        let path = dir.join("size.png");
+
+        check_png(&path, &test_display(), &DiffTolerance::default(), true).unwrap();
+
+        let display = SimulatorDisplay::<Rgb888>::new(Size::new(4, 6));
+        match check_png(&path, &display, &DiffTolerance::default(), false) {
+            Err(error @ SnapshotError::SizeMismatch { .. }) => assert_eq!(
+                error.to_string(),
+                "display dimensions don't match PNG dimensions (display: 4x6, PNG: 8x6)"
+            ),
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use embedded_graphics::pixelcolor::{Rgb888, RgbColor};
 
 /// Color theme for binary displays
+///
+/// The themes map black pixels to the "off" color and white pixels to the "on" color of the
+/// theme. Gray levels, e.g. of a [`Gray4`](embedded_graphics::pixelcolor::Gray4) display, are
+/// interpolated between the "off" and "on" color and all other colors are shown in the "on"
+/// color.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum BinaryColorTheme {
     /// A simple on/off, non-styled display with black background and white pixels
@@ -25,6 +30,25 @@ pub enum BinaryColorTheme {
     /// An on/off OLED-like display with a dark blue background and light blue pixels
     OledBlue,
 
+    /// A black and white e-paper display with black ink on white paper
+    ///
+    /// Unlike the other themes, the e-paper themes show black pixels as ink and white pixels as
+    /// paper, which matches the colors of [`TriColor`](crate::TriColor). Other colors are mapped
+    /// to a mix of ink and paper based on their brightness.
+    EPaper,
+
+    /// A three color e-paper display with black and red ink on white paper
+    ///
+    /// Saturated colors, like the chromatic color of [`TriColor`](crate::TriColor), are shown as
+    /// red ink.
+    EPaperRed,
+
+    /// A three color e-paper display with black and yellow ink on white paper
+    ///
+    /// Saturated colors, like the chromatic color of [`TriColor`](crate::TriColor), are shown as
+    /// yellow ink.
+    EPaperYellow,
+
     /// Custom binary color theme/mapping
     Custom {
         /// The color used for the "off" state pixels.
@@ -32,12 +56,75 @@ pub enum BinaryColorTheme {
         /// The color used for the "on" state pixels.
         color_on: Rgb888,
     },
+
+    /// Custom palette for grayscale displays
+    ///
+    /// Gray levels are mapped to the palette entries, with black being mapped to the first and
+    /// white to the last entry. A palette with one entry per gray level, e.g. 16 entries for a
+    /// [`Gray4`](embedded_graphics::pixelcolor::Gray4) display, can be used to reproduce the
+    /// non-linear brightness of a panel. All other colors are shown in the color of the last
+    /// entry. An empty palette shows all colors unchanged, like the [`Default`](Self::Default)
+    /// theme.
+    Palette(&'static [Rgb888]),
+}
+
+/// Returns the gray level of a color or `None` if the color isn't gray.
+fn gray_level(color: Rgb888) -> Option<u8> {
+    (color.r() == color.g() && color.g() == color.b()).then_some(color.r())
+}
+
+/// Interpolates between two colors.
+pub(crate) fn interpolate(color_off: Rgb888, color_on: Rgb888, level: u8) -> Rgb888 {
+    let interpolate_channel = |off: u8, on: u8| {
+        let value = i32::from(off) + (i32::from(on) - i32::from(off)) * i32::from(level) / 255;
+
+        value as u8
+    };
+
+    Rgb888::new(
+        interpolate_channel(color_off.r(), color_on.r()),
+        interpolate_channel(color_off.g(), color_on.g()),
+        interpolate_channel(color_off.b(), color_on.b()),
+    )
 }
 
 fn map_color(color: Rgb888, color_off: Rgb888, color_on: Rgb888) -> Rgb888 {
-    match color {
-        Rgb888::BLACK => color_off,
-        _ => color_on,
+    match gray_level(color) {
+        Some(level) => interpolate(color_off, color_on, level),
+        None => color_on,
+    }
+}
+
+fn map_palette(color: Rgb888, palette: &[Rgb888]) -> Rgb888 {
+    let Some(last) = palette.len().checked_sub(1) else {
+        return color;
+    };
+
+    match gray_level(color) {
+        Some(level) => palette[(usize::from(level) * last + 127) / 255],
+        None => palette[last],
+    }
+}
+
+/// Paper color of e-paper displays.
+const EPAPER_PAPER: Rgb888 = Rgb888::new(232, 230, 222);
+/// Black ink color of e-paper displays.
+const EPAPER_BLACK: Rgb888 = Rgb888::new(38, 38, 42);
+/// Red ink color of three color e-paper displays.
+const EPAPER_RED: Rgb888 = Rgb888::new(186, 36, 40);
+/// Yellow ink color of three color e-paper displays.
+const EPAPER_YELLOW: Rgb888 = Rgb888::new(234, 190, 24);
+
+fn map_pigment(color: Rgb888, chromatic: Option<Rgb888>) -> Rgb888 {
+    let (r, g, b) = (color.r(), color.g(), color.b());
+
+    match chromatic {
+        Some(chromatic) if r.max(g).max(b) - r.min(g).min(b) >= 128 => chromatic,
+        _ => {
+            let luma = (u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114) / 1000;
+
+            interpolate(EPAPER_BLACK, EPAPER_PAPER, luma as u8)
+        }
     }
 }
 
@@ -66,6 +153,94 @@ impl BinaryColorTheme {
                 map_color(color, Rgb888::new(0, 20, 40), Rgb888::new(0, 210, 255))
             }
             BinaryColorTheme::OledWhite => map_color(color, Rgb888::new(20, 20, 20), Rgb888::WHITE),
+            BinaryColorTheme::EPaper => map_pigment(color, None),
+            BinaryColorTheme::EPaperRed => map_pigment(color, Some(EPAPER_RED)),
+            BinaryColorTheme::EPaperYellow => map_pigment(color, Some(EPAPER_YELLOW)),
+            BinaryColorTheme::Palette(palette) => map_palette(color, palette),
+        }
+    }
+
+    /// Returns the background color, which is used for the spacing between pixels.
+    pub(crate) fn background(self) -> Rgb888 {
+        match self {
+            BinaryColorTheme::EPaper
+            | BinaryColorTheme::EPaperRed
+            | BinaryColorTheme::EPaperYellow => EPAPER_PAPER,
+            _ => self.convert(Rgb888::BLACK),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::{BinaryColor, Gray2, Gray4, GrayColor};
+
+    use super::*;
+
+    #[test]
+    fn binary_colors() {
+        let theme = BinaryColorTheme::LcdGreen;
+
+        assert_eq!(
+            theme.convert(BinaryColor::Off.into()),
+            Rgb888::new(120, 185, 50)
+        );
+        assert_eq!(
+            theme.convert(BinaryColor::On.into()),
+            Rgb888::new(32, 32, 32)
+        );
+        assert_eq!(theme.convert(Rgb888::RED), Rgb888::new(32, 32, 32));
+    }
+
+    #[test]
+    fn grayscale_interpolation() {
+        let theme = BinaryColorTheme::OledBlue;
+
+        let colors = (0..4)
+            .map(|level| theme.convert(Gray2::new(level).into()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            colors,
+            [
+                Rgb888::new(0, 20, 40),
+                Rgb888::new(0, 83, 111),
+                Rgb888::new(0, 146, 183),
+                Rgb888::new(0, 210, 255),
+            ]
+        );
+    }
+
+    #[test]
+    fn palette() {
+        const PALETTE: [Rgb888; 16] = {
+            let mut palette = [Rgb888::BLACK; 16];
+
+            let mut i = 0;
+            while i < 16 {
+                palette[i] = Rgb888::new(i as u8, 0, 0);
+                i += 1;
+            }
+
+            palette
+        };
+        let theme = BinaryColorTheme::Palette(&PALETTE);
+
+        for level in 0..16 {
+            assert_eq!(
+                theme.convert(Gray4::new(level).into()),
+                Rgb888::new(level, 0, 0)
+            );
         }
+        assert_eq!(theme.convert(Rgb888::GREEN), Rgb888::new(15, 0, 0));
+
+        let theme = BinaryColorTheme::Palette(&[Rgb888::RED, Rgb888::GREEN]);
+        assert_eq!(theme.convert(Gray4::WHITE.into()), Rgb888::GREEN);
+        assert_eq!(theme.convert(Gray4::new(7).into()), Rgb888::RED);
+
+        let theme = BinaryColorTheme::Palette(&[]);
+        assert_eq!(theme.convert(Gray4::new(7).into()), Gray4::new(7).into());
+        assert_eq!(theme.convert(Rgb888::GREEN), Rgb888::GREEN);
+        assert_eq!(theme.background(), Rgb888::BLACK);
     }
 }
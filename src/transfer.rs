@@ -0,0 +1,252 @@
+use std::{fmt, time::Duration};
+
+use embedded_graphics::{pixelcolor::raw::RawData, prelude::*, primitives::Rectangle};
+
+use crate::{display::SimulatorDisplay, framebuffer::PixelOrder};
+
+/// Display bus transfer model.
+///
+/// The transfer model estimates the time that is required to send the content of a display to a
+/// real display controller. The pixel data is assumed to be packed in the
+/// [`pixel_order`](Self::pixel_order) of the controller, with the number of bits per pixel of the
+/// display color type, and each line is padded to a whole number of bytes. Each flushed region
+/// additionally transfers [`region_overhead`](Self::region_overhead) bytes, e.g. to set the
+/// address window of the controller.
+///
+/// The estimate can be shown in the window title by using [`Window::set_transfer_model`] or
+/// calculated directly by using [`estimate`](Self::estimate).
+///
+/// [`Window::set_transfer_model`]: crate::Window::set_transfer_model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransferModel {
+    /// Bus clock frequency in Hz.
+    pub clock_frequency: u32,
+    /// Number of clock cycles that are required to transfer one byte.
+    pub cycles_per_byte: u32,
+    /// Number of bytes that are transferred in addition to the pixel data of each region.
+    pub region_overhead: u32,
+    /// Order of the transferred pixels.
+    ///
+    /// Regions are extended to whole pages for [`PixelOrder::VerticalPages`].
+    pub pixel_order: PixelOrder,
+    /// Regions that are flushed each frame.
+    pub flush_mode: FlushMode,
+}
+
+impl TransferModel {
+    /// Creates a transfer model for a SPI bus.
+    ///
+    /// The model uses 8 clock cycles per byte and an overhead of 11 bytes per region, which are
+    /// used by MIPI DCS controllers, like the ILI9341 or ST7789, to set the address window and start
+    /// the memory write. The pixels are transferred in row major order and the full frame is
+    /// flushed each frame.
+    pub const fn spi(clock_frequency: u32) -> Self {
+        Self {
+            clock_frequency,
+            cycles_per_byte: 8,
+            region_overhead: 11,
+            pixel_order: PixelOrder::RowMajor,
+            flush_mode: FlushMode::FullFrame,
+        }
+    }
+
+    /// Creates a transfer model for an I2C bus.
+    ///
+    /// The model uses 9 clock cycles per byte, including the acknowledge bit, and an overhead of 10
+    /// bytes per region, which are used by a SSD1306 controller to address the device and set the
+    /// column and page address. The pixels are transferred in the vertical pages used by the
+    /// SSD1306 and the full frame is flushed each frame.
+    pub const fn i2c(clock_frequency: u32) -> Self {
+        Self {
+            clock_frequency,
+            cycles_per_byte: 9,
+            region_overhead: 10,
+            pixel_order: PixelOrder::VerticalPages,
+            flush_mode: FlushMode::FullFrame,
+        }
+    }
+
+    /// Estimates the transfer of the regions of a display that are flushed each frame.
+    ///
+    /// In [`FlushMode::DirtyArea`] mode the estimate is based on the area that was changed since
+    /// the display had the [`generation`](SimulatorDisplay::generation) `since`, which is usually
+    /// the generation of the previous frame.
+    pub fn estimate<C: PixelColor>(
+        &self,
+        display: &SimulatorDisplay<C>,
+        since: u64,
+    ) -> TransferEstimate {
+        let region = match self.flush_mode {
+            FlushMode::FullFrame => Some(display.bounding_box()),
+            FlushMode::DirtyArea => display.dirty_area_since(since),
+        };
+
+        self.estimate_regions(region, C::Raw::BITS_PER_PIXEL)
+    }
+
+    /// Estimates the transfer of a list of regions.
+    ///
+    /// This method can be used if the display driver flushes multiple regions each frame.
+    pub fn estimate_regions<I>(&self, regions: I, bits_per_pixel: usize) -> TransferEstimate
+    where
+        I: IntoIterator<Item = Rectangle>,
+    {
+        let mut estimate = TransferEstimate::default();
+
+        for region in regions {
+            estimate.regions += 1;
+            estimate.bytes +=
+                u64::from(self.region_overhead) + self.region_bytes(&region, bits_per_pixel);
+        }
+
+        let cycles = u128::from(estimate.bytes) * u128::from(self.cycles_per_byte);
+        let nanos = cycles * 1_000_000_000 / u128::from(self.clock_frequency.max(1));
+        estimate.duration = Duration::from_nanos(nanos.try_into().unwrap_or(u64::MAX));
+
+        estimate
+    }
+
+    /// Returns the number of bytes of the pixel data in a region.
+    fn region_bytes(&self, region: &Rectangle, bits_per_pixel: usize) -> u64 {
+        let (width, height) = (u64::from(region.size.width), u64::from(region.size.height));
+        let bits_per_pixel = bits_per_pixel as u64;
+
+        match self.pixel_order {
+            PixelOrder::RowMajor => (width * bits_per_pixel).div_ceil(8) * height,
+            PixelOrder::ColumnMajor => (height * bits_per_pixel).div_ceil(8) * width,
+            PixelOrder::VerticalPages => {
+                if height == 0 {
+                    return 0;
+                }
+
+                // Pages are always transferred completely, even if the region only covers a part
+                // of the page.
+                let pixels_per_page = (8 / bits_per_pixel).max(1) as i64;
+                let first_page = i64::from(region.top_left.y).div_euclid(pixels_per_page);
+                let last_page =
+                    (i64::from(region.top_left.y) + height as i64 - 1).div_euclid(pixels_per_page);
+
+                (last_page - first_page + 1) as u64 * width * bits_per_pixel.div_ceil(8)
+            }
+        }
+    }
+}
+
+/// Regions that are flushed each frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlushMode {
+    /// The full frame is transferred.
+    #[default]
+    FullFrame,
+    /// Only the area that was changed since the last frame is transferred.
+    ///
+    /// Nothing is transferred if the display wasn't changed.
+    DirtyArea,
+}
+
+/// Estimated transfer of a frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransferEstimate {
+    /// Number of transferred regions.
+    pub regions: u32,
+    /// Number of transferred bytes, including the overhead.
+    pub bytes: u64,
+    /// Transfer time.
+    pub duration: Duration,
+}
+
+impl TransferEstimate {
+    /// Returns the maximum frame rate that is limited by the transfer time.
+    ///
+    /// Returns infinity if nothing is transferred.
+    pub fn max_fps(&self) -> f64 {
+        1.0 / self.duration.as_secs_f64()
+    }
+}
+
+impl fmt::Display for TransferEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.regions == 0 {
+            return f.write_str("no transfer");
+        }
+
+        write!(
+            f,
+            "{} bytes in {:.2} ms, max {:.1} FPS",
+            self.bytes,
+            self.duration.as_secs_f64() * 1000.0,
+            self.max_fps(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_graphics::pixelcolor::{BinaryColor, Rgb565};
+
+    #[test]
+    fn spi_full_frame() {
+        let display = SimulatorDisplay::<Rgb565>::new(Size::new(320, 240));
+        let estimate = TransferModel::spi(10_000_000).estimate(&display, 0);
+
+        assert_eq!(estimate.regions, 1);
+        assert_eq!(estimate.bytes, 320 * 240 * 2 + 11);
+        assert_eq!(estimate.duration, Duration::from_nanos(122_888_800));
+        assert_eq!(
+            estimate.to_string(),
+            "153611 bytes in 122.89 ms, max 8.1 FPS"
+        );
+    }
+
+    #[test]
+    fn i2c_dirty_area() {
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(128, 64));
+        let model = TransferModel {
+            flush_mode: FlushMode::DirtyArea,
+            ..TransferModel::i2c(400_000)
+        };
+
+        assert_eq!(model.estimate(&display, 0).bytes, 128 * 64 / 8 + 10);
+
+        let generation = display.generation();
+        let estimate = model.estimate(&display, generation);
+        assert_eq!(estimate, TransferEstimate::default());
+        assert_eq!(estimate.max_fps(), f64::INFINITY);
+        assert_eq!(estimate.to_string(), "no transfer");
+
+        // the region is extended to the pages 0 and 1
+        display.mark_dirty(&Rectangle::new(Point::new(3, 6), Size::new(10, 4)));
+        let estimate = model.estimate(&display, generation);
+        assert_eq!(estimate.bytes, 2 * 10 + 10);
+        assert_eq!(estimate.duration, Duration::from_nanos(675_000));
+    }
+
+    #[test]
+    fn multiple_regions() {
+        let estimate = TransferModel::spi(1_000_000).estimate_regions(
+            [
+                Rectangle::new(Point::zero(), Size::new(5, 3)),
+                Rectangle::new(Point::new(10, 10), Size::new(1, 1)),
+            ],
+            18,
+        );
+
+        assert_eq!(estimate.regions, 2);
+        assert_eq!(estimate.bytes, 11 + 12 * 3 + 11 + 3);
+        assert_eq!(estimate.duration, Duration::from_micros(61 * 8));
+    }
+
+    #[test]
+    fn column_major() {
+        let model = TransferModel {
+            pixel_order: PixelOrder::ColumnMajor,
+            ..TransferModel::spi(1_000_000)
+        };
+        let estimate =
+            model.estimate_regions([Rectangle::new(Point::new(1, 2), Size::new(3, 5))], 4);
+
+        assert_eq!(estimate.bytes, 11 + 3 * 3);
+    }
+}
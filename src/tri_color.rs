@@ -0,0 +1,125 @@
+use embedded_graphics::{
+    pixelcolor::{
+        raw::{RawData, RawU2},
+        BinaryColor, Rgb888,
+    },
+    prelude::*,
+};
+
+/// Color of a three color e-paper display.
+///
+/// Three color e-paper displays can show white, black and a third chromatic color, which is
+/// usually red or yellow. The pigment colors that are used to draw the display can be selected by
+/// using the [`EPaper`], [`EPaperRed`] or [`EPaperYellow`] themes. Without a theme the chromatic
+/// color is shown as pure red.
+///
+/// The raw value of a color combines the bits that represent the color in the two bit planes of
+/// a typical e-paper controller, see
+/// [`SimulatorDisplay::to_bit_planes`](crate::SimulatorDisplay::to_bit_planes): bit 0 is the bit
+/// in the black/white plane (`1` for white) and bit 1 is the bit in the chromatic plane (`1` for
+/// the chromatic color).
+///
+/// [`EPaper`]: crate::BinaryColorTheme::EPaper
+/// [`EPaperRed`]: crate::BinaryColorTheme::EPaperRed
+/// [`EPaperYellow`]: crate::BinaryColorTheme::EPaperYellow
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TriColor {
+    /// White.
+    #[default]
+    White,
+    /// Black.
+    Black,
+    /// Chromatic color, e.g. red or yellow.
+    Chromatic,
+}
+
+impl PixelColor for TriColor {
+    type Raw = RawU2;
+}
+
+impl From<RawU2> for TriColor {
+    fn from(raw: RawU2) -> Self {
+        match raw.into_inner() {
+            0b00 => TriColor::Black,
+            0b01 => TriColor::White,
+            // The chromatic plane takes precedence over the black/white plane.
+            _ => TriColor::Chromatic,
+        }
+    }
+}
+
+impl From<TriColor> for RawU2 {
+    fn from(color: TriColor) -> Self {
+        RawU2::new(match color {
+            TriColor::Black => 0b00,
+            TriColor::White => 0b01,
+            TriColor::Chromatic => 0b11,
+        })
+    }
+}
+
+/// Converts a binary color into a tri-color.
+///
+/// `Off` is converted to white and `On` to black, which is the common convention for e-paper
+/// displays.
+impl From<BinaryColor> for TriColor {
+    fn from(color: BinaryColor) -> Self {
+        match color {
+            BinaryColor::Off => TriColor::White,
+            BinaryColor::On => TriColor::Black,
+        }
+    }
+}
+
+impl From<TriColor> for Rgb888 {
+    fn from(color: TriColor) -> Self {
+        match color {
+            TriColor::White => Rgb888::WHITE,
+            TriColor::Black => Rgb888::BLACK,
+            TriColor::Chromatic => Rgb888::RED,
+        }
+    }
+}
+
+/// Converts an RGB color into the closest tri-color.
+///
+/// Saturated colors are converted to the chromatic color and all other colors to black or white
+/// based on their brightness.
+impl From<Rgb888> for TriColor {
+    fn from(color: Rgb888) -> Self {
+        let (r, g, b) = (color.r(), color.g(), color.b());
+
+        if r.max(g).max(b) - r.min(g).min(b) >= 128 {
+            TriColor::Chromatic
+        } else if u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114 >= 128_000 {
+            TriColor::White
+        } else {
+            TriColor::Black
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_conversion() {
+        for color in [TriColor::White, TriColor::Black, TriColor::Chromatic] {
+            assert_eq!(TriColor::from(RawU2::from(color)), color);
+        }
+
+        assert_eq!(TriColor::from(RawU2::new(0b10)), TriColor::Chromatic);
+    }
+
+    #[test]
+    fn rgb_conversion() {
+        for color in [TriColor::White, TriColor::Black, TriColor::Chromatic] {
+            assert_eq!(TriColor::from(Rgb888::from(color)), color);
+        }
+
+        assert_eq!(TriColor::from(Rgb888::YELLOW), TriColor::Chromatic);
+        assert_eq!(TriColor::from(Rgb888::new(200, 200, 200)), TriColor::White);
+        assert_eq!(TriColor::from(Rgb888::new(50, 60, 70)), TriColor::Black);
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    env, thread,
+    time::{Duration, Instant},
+};
+
+/// Simulator clock.
+///
+/// The clock either follows the real time and limits the frame rate by sleeping between frames, or
+/// is a virtual clock that advances by a fixed time step for each frame without sleeping.
+pub(crate) struct Clock {
+    pub(crate) max_fps: u32,
+    /// Uses a virtual clock with a fixed time step if `true`.
+    pub(crate) virtual_clock: bool,
+    /// Start time of the first frame, which is only used by the real time clock.
+    start: Option<Instant>,
+    /// Start time of the current frame, which is only used to limit the frame rate.
+    frame_start: Instant,
+    /// Time of the current frame since the first frame.
+    now: Option<Duration>,
+    /// Time between the previous and the current frame.
+    frame_time: Duration,
+}
+
+impl Clock {
+    pub(crate) fn new(virtual_clock: bool) -> Self {
+        Self {
+            max_fps: 60,
+            virtual_clock,
+            start: None,
+            frame_start: Instant::now(),
+            now: None,
+            frame_time: Duration::ZERO,
+        }
+    }
+
+    /// Creates a new clock, which uses a virtual clock if `virtual_clock` is `true` or if the
+    /// `EG_SIMULATOR_VIRTUAL_CLOCK` environment variable is set.
+    pub(crate) fn from_env(virtual_clock: bool) -> Self {
+        let from_env = env::var_os("EG_SIMULATOR_VIRTUAL_CLOCK")
+            .is_some_and(|value| !value.is_empty() && value != "0");
+
+        Self::new(virtual_clock || from_env)
+    }
+
+    /// Returns the time step of the virtual clock.
+    fn time_step(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.max_fps as f64)
+    }
+
+    /// Returns the time of the current frame since the first frame.
+    pub(crate) fn now(&self) -> Duration {
+        self.now.unwrap_or_default()
+    }
+
+    /// Returns the time between the previous and the current frame.
+    pub(crate) fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+
+    /// Starts a new frame and returns its time since the first frame.
+    pub(crate) fn next_frame(&mut self) -> Duration {
+        let now = match (self.now, self.start) {
+            (Some(now), _) if self.virtual_clock => now + self.time_step(),
+            (Some(_), Some(start)) => start.elapsed(),
+            _ => {
+                self.start = Some(Instant::now());
+                Duration::ZERO
+            }
+        };
+
+        self.frame_time = now - self.now.unwrap_or_default();
+        self.now = Some(now);
+
+        now
+    }
+
+    /// Returns the time at which the current frame ends.
+    ///
+    /// This is used as the end time of recordings.
+    pub(crate) fn frame_end(&self) -> Duration {
+        match (self.now, self.start) {
+            (Some(now), _) if self.virtual_clock => now + self.time_step(),
+            (_, Some(start)) => start.elapsed(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Sleeps until the end of the current frame to limit the frame rate.
+    ///
+    /// The virtual clock doesn't sleep.
+    pub(crate) fn sleep(&mut self) {
+        if !self.virtual_clock {
+            let sleep_duration =
+                (self.frame_start + self.time_step()).saturating_duration_since(Instant::now());
+            thread::sleep(sleep_duration);
+        }
+
+        self.frame_start = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn virtual_clock() {
+        let mut clock = Clock::new(true);
+        clock.max_fps = 50;
+
+        assert_eq!(clock.now(), Duration::ZERO);
+        assert_eq!(clock.next_frame(), Duration::ZERO);
+        assert_eq!(clock.frame_time(), Duration::ZERO);
+
+        let start = Instant::now();
+        for _ in 0..500 {
+            clock.sleep();
+            clock.next_frame();
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        assert_eq!(clock.now(), Duration::from_secs(10));
+        assert_eq!(clock.frame_time(), MS * 20);
+        assert_eq!(clock.frame_end(), Duration::from_secs(10) + MS * 20);
+    }
+
+    #[test]
+    fn real_time_clock() {
+        let mut clock = Clock::new(false);
+
+        assert_eq!(clock.next_frame(), Duration::ZERO);
+        thread::sleep(MS * 10);
+
+        let now = clock.next_frame();
+        assert!(now >= MS * 10);
+        assert_eq!(clock.now(), now);
+        assert_eq!(clock.frame_time(), now);
+        assert!(clock.frame_end() >= now);
+    }
+}
@@ -0,0 +1,310 @@
+use std::{env, fmt, time::Duration};
+
+/// Screenshot settings read from the `EG_SIMULATOR_DUMP*` environment variables.
+pub(crate) struct Dump {
+    path: String,
+    /// Exports the unstyled display content if `true`.
+    pub(crate) raw: bool,
+    selector: FrameSelector,
+    /// Selects every frame if no selection criteria were given.
+    select_all: bool,
+    /// Elapsed time after which waiting for the selected frames is aborted.
+    timeout: Duration,
+}
+
+/// Default time the dump waits for selected frames, in addition to the latest selected time.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+impl Dump {
+    /// Reads the dump settings from the environment.
+    ///
+    /// Returns `None` if neither `EG_SIMULATOR_DUMP` nor `EG_SIMULATOR_DUMP_RAW` is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the frame selection variables contains an invalid value.
+    pub(crate) fn from_env() -> Option<Self> {
+        let (path, raw) = if let Ok(path) = env::var("EG_SIMULATOR_DUMP") {
+            (path, false)
+        } else if let Ok(path) = env::var("EG_SIMULATOR_DUMP_RAW") {
+            (path, true)
+        } else {
+            return None;
+        };
+
+        let selector = FrameSelector {
+            frames: env_list("EG_SIMULATOR_DUMP_FRAME", |value| value.parse().ok()),
+            times: env_list("EG_SIMULATOR_DUMP_TIME", parse_duration),
+            labels: env_list("EG_SIMULATOR_DUMP_LABEL", |value| Some(value.to_string())),
+        };
+
+        let timeout = env::var("EG_SIMULATOR_DUMP_TIMEOUT")
+            .map(|value| {
+                parse_duration(&value)
+                    .unwrap_or_else(|| panic!("invalid EG_SIMULATOR_DUMP_TIMEOUT value: {value}"))
+            })
+            .unwrap_or(DEFAULT_TIMEOUT);
+
+        Some(Self::new(path, raw, selector, timeout))
+    }
+
+    fn new(path: String, raw: bool, selector: FrameSelector, timeout: Duration) -> Self {
+        let latest_time = selector.times.iter().max().copied().unwrap_or_default();
+
+        Self {
+            path,
+            raw,
+            select_all: selector.is_empty(),
+            selector,
+            timeout: latest_time + timeout,
+        }
+    }
+
+    /// Returns `true` if multiple frames are exported.
+    fn is_multi_frame(&self) -> bool {
+        self.path.contains("{frame}")
+    }
+
+    /// Returns the output path if the given frame should be exported.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timeout has elapsed before all selected frames were found.
+    pub(crate) fn select(
+        &mut self,
+        frame: usize,
+        elapsed: Duration,
+        label: Option<&str>,
+    ) -> Option<String> {
+        let selected = self.select_all || self.selector.select(frame, elapsed, label);
+
+        if !selected && !self.select_all && elapsed >= self.timeout {
+            panic!(
+                "EG_SIMULATOR_DUMP timed out after {elapsed:?} waiting for {}",
+                self.selector
+            );
+        }
+
+        selected.then(|| self.path.replace("{frame}", &frame.to_string()))
+    }
+
+    /// Returns `true` if the dump waits for selected frames.
+    ///
+    /// Windows aren't shown while a dump is waiting.
+    #[cfg_attr(not(feature = "with-sdl"), allow(dead_code))]
+    pub(crate) fn is_waiting(&self) -> bool {
+        !self.select_all
+    }
+
+    /// Returns `true` if no more frames will be exported after a frame was exported.
+    pub(crate) fn is_done(&self) -> bool {
+        !self.is_multi_frame() || (!self.select_all && self.selector.is_empty())
+    }
+}
+
+/// Selects frames by index, elapsed time or label.
+#[derive(Debug, Default)]
+struct FrameSelector {
+    frames: Vec<usize>,
+    times: Vec<Duration>,
+    labels: Vec<String>,
+}
+
+impl FrameSelector {
+    /// Returns `true` if no selection criteria are left.
+    fn is_empty(&self) -> bool {
+        self.frames.is_empty() && self.times.is_empty() && self.labels.is_empty()
+    }
+
+    /// Returns `true` if the frame is selected.
+    ///
+    /// Each criterion only selects a single frame and is removed after it matched.
+    fn select(&mut self, frame: usize, elapsed: Duration, label: Option<&str>) -> bool {
+        let frames_len = self.frames.len();
+        self.frames.retain(|f| *f != frame);
+
+        let times_len = self.times.len();
+        self.times.retain(|t| *t > elapsed);
+
+        let labels_len = self.labels.len();
+        if let Some(label) = label {
+            self.labels.retain(|l| l != label);
+        }
+
+        frames_len != self.frames.len()
+            || times_len != self.times.len()
+            || labels_len != self.labels.len()
+    }
+}
+
+impl fmt::Display for FrameSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut items = Vec::new();
+        items.extend(self.frames.iter().map(|frame| format!("frame {frame}")));
+        items.extend(self.times.iter().map(|time| format!("time {time:?}")));
+        items.extend(self.labels.iter().map(|label| format!("label {label:?}")));
+
+        f.write_str(&items.join(", "))
+    }
+}
+
+/// Parses a comma separated list from an environment variable.
+fn env_list<T, F>(name: &str, parse: F) -> Vec<T>
+where
+    F: Fn(&str) -> Option<T>,
+{
+    env::var(name)
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| parse(item).unwrap_or_else(|| panic!("invalid {name} value: {item}")))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parses a duration with a `ms` or `s` unit suffix, e.g. `500ms` or `1.5s`.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+
+    let (number, scale) = if let Some(number) = value.strip_suffix("ms") {
+        (number, 1e-3)
+    } else if let Some(number) = value.strip_suffix('s') {
+        (number, 1.0)
+    } else {
+        return None;
+    };
+
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|number| Duration::try_from_secs_f64(number * scale).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2s"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_duration(" 1.5 s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("-1s"), None);
+        assert_eq!(parse_duration("ms"), None);
+    }
+
+    #[test]
+    fn first_frame() {
+        let mut dump = Dump::new(
+            "out.png".to_string(),
+            false,
+            FrameSelector::default(),
+            DEFAULT_TIMEOUT,
+        );
+
+        assert_eq!(
+            dump.select(0, Duration::ZERO, None),
+            Some("out.png".to_string())
+        );
+        assert!(dump.is_done());
+    }
+
+    #[test]
+    fn every_frame() {
+        let mut dump = Dump::new(
+            "{frame}.png".to_string(),
+            false,
+            FrameSelector::default(),
+            DEFAULT_TIMEOUT,
+        );
+
+        assert_eq!(
+            dump.select(0, Duration::ZERO, None),
+            Some("0.png".to_string())
+        );
+        assert!(!dump.is_done());
+        assert_eq!(dump.select(1, MS * 16, None), Some("1.png".to_string()));
+        assert!(!dump.is_done());
+        assert!(!dump.is_waiting());
+    }
+
+    #[test]
+    fn select_by_index() {
+        let selector = FrameSelector {
+            frames: vec![2, 4],
+            ..FrameSelector::default()
+        };
+        let mut dump = Dump::new(
+            "out-{frame}.png".to_string(),
+            false,
+            selector,
+            DEFAULT_TIMEOUT,
+        );
+        assert!(dump.is_waiting());
+
+        assert_eq!(dump.select(0, Duration::ZERO, None), None);
+        assert_eq!(dump.select(1, MS * 16, None), None);
+        assert_eq!(dump.select(2, MS * 32, None), Some("out-2.png".to_string()));
+        assert!(!dump.is_done());
+        assert_eq!(dump.select(3, MS * 48, None), None);
+        assert_eq!(dump.select(4, MS * 64, None), Some("out-4.png".to_string()));
+        assert!(dump.is_done());
+    }
+
+    #[test]
+    fn select_by_time() {
+        let selector = FrameSelector {
+            times: vec![MS * 20, MS * 25, MS * 100],
+            ..FrameSelector::default()
+        };
+        let mut dump = Dump::new("{frame}.png".to_string(), false, selector, DEFAULT_TIMEOUT);
+
+        assert_eq!(dump.select(0, Duration::ZERO, None), None);
+        assert_eq!(dump.select(1, MS * 16, None), None);
+        assert_eq!(dump.select(2, MS * 32, None), Some("2.png".to_string()));
+        assert_eq!(dump.select(3, MS * 48, None), None);
+        assert!(!dump.is_done());
+        assert_eq!(dump.select(4, MS * 150, None), Some("4.png".to_string()));
+        assert!(dump.is_done());
+    }
+
+    #[test]
+    fn select_by_label() {
+        let selector = FrameSelector {
+            labels: vec!["settings".to_string()],
+            ..FrameSelector::default()
+        };
+        let mut dump = Dump::new("settings.png".to_string(), false, selector, DEFAULT_TIMEOUT);
+
+        assert_eq!(dump.select(0, Duration::ZERO, Some("splash")), None);
+        assert_eq!(dump.select(1, MS * 16, None), None);
+        assert_eq!(
+            dump.select(2, MS * 32, Some("settings")),
+            Some("settings.png".to_string())
+        );
+        assert!(dump.is_done());
+    }
+
+    #[test]
+    #[should_panic(expected = "timed out after 1.1s waiting for frame 500, label \"setings\"")]
+    fn timeout() {
+        let selector = FrameSelector {
+            frames: vec![500],
+            times: vec![MS * 100],
+            labels: vec!["setings".to_string()],
+        };
+        let mut dump = Dump::new("{frame}.png".to_string(), false, selector, MS * 1000);
+
+        assert_eq!(dump.select(0, Duration::ZERO, None), None);
+        assert_eq!(dump.select(1, MS * 1000, None), Some("1.png".to_string()));
+        assert_eq!(dump.select(2, MS * 1050, Some("settings")), None);
+        dump.select(3, MS * 1100, None);
+    }
+}
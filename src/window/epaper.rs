@@ -0,0 +1,562 @@
+use std::{collections::VecDeque, time::Duration};
+
+use embedded_graphics::{
+    pixelcolor::{Rgb888, RgbColor},
+    prelude::*,
+    primitives::Rectangle,
+};
+
+use crate::{
+    display::{envelope, SimulatorDisplay},
+    output_image::OutputImage,
+    output_settings::OutputSettings,
+    theme::BinaryColorTheme,
+};
+
+/// E-paper display settings.
+///
+/// The default settings approximate a typical black and white e-paper panel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EPaperSettings {
+    /// Duration of a full refresh.
+    pub full_refresh_duration: Duration,
+    /// Number of times the display flashes black and white during a full refresh.
+    pub full_refresh_flashes: u32,
+    /// Duration of a partial refresh.
+    pub partial_refresh_duration: Duration,
+    /// Ghosting added to a pixel each time it is changed by a partial refresh.
+    ///
+    /// The ghosting is the fraction of the previous color that remains visible, in the range from
+    /// `0.0` to `1.0`.
+    pub ghosting: f32,
+    /// Maximum ghosting of a pixel.
+    pub max_ghosting: f32,
+}
+
+impl Default for EPaperSettings {
+    fn default() -> Self {
+        Self {
+            full_refresh_duration: Duration::from_secs(2),
+            full_refresh_flashes: 2,
+            partial_refresh_duration: Duration::from_millis(300),
+            ghosting: 0.05,
+            max_ghosting: 0.3,
+        }
+    }
+}
+
+/// Refresh type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Refresh {
+    Full,
+    Partial(Rectangle),
+}
+
+/// Refresh that is currently in progress.
+#[derive(Debug)]
+struct ActiveRefresh {
+    refresh: Refresh,
+    start: Duration,
+    /// New panel content.
+    content: Vec<Rgb888>,
+}
+
+/// E-paper display state.
+///
+/// The content is stored as unthemed colors. The theme is applied before ghosting and the
+/// intermediate colors of a refresh are added, because themes map colors to a limited set of
+/// output colors, which would remove the intermediate colors.
+///
+/// Only the changed area of the application display is read in each update and only the pixels
+/// that can change, e.g. the area of an active refresh, are rendered to the panel.
+#[derive(Debug)]
+pub(crate) struct EPaper {
+    settings: EPaperSettings,
+    theme: BinaryColorTheme,
+    size: Size,
+    /// History ID and generation of the display that was last copied to `source`.
+    source_display: Option<(usize, u64)>,
+    /// Content of the application display, which is shown after the next refresh.
+    source: Vec<Rgb888>,
+    /// Content that is shown on the panel, without ghosting.
+    content: Vec<Rgb888>,
+    /// Previous colors of pixels that were changed by partial refreshes.
+    ghost_colors: Vec<Rgb888>,
+    ghost_levels: Vec<f32>,
+    pending: VecDeque<Refresh>,
+    active: Option<ActiveRefresh>,
+    /// Colors that are currently shown on the panel, including ghosting and refresh effects.
+    pub(crate) panel: SimulatorDisplay<Rgb888>,
+}
+
+impl EPaper {
+    pub(crate) fn new(settings: EPaperSettings) -> Self {
+        Self {
+            settings,
+            theme: BinaryColorTheme::Default,
+            size: Size::zero(),
+            source_display: None,
+            source: Vec::new(),
+            content: Vec::new(),
+            ghost_colors: Vec::new(),
+            ghost_levels: Vec::new(),
+            pending: VecDeque::new(),
+            active: None,
+            panel: SimulatorDisplay::new(Size::zero()),
+        }
+    }
+
+    /// Requests a full refresh.
+    pub(crate) fn refresh_full(&mut self) {
+        self.pending.push_back(Refresh::Full);
+    }
+
+    /// Requests a partial refresh of an area.
+    pub(crate) fn refresh_partial(&mut self, area: Rectangle) {
+        self.pending.push_back(Refresh::Partial(area));
+    }
+
+    /// Returns the output settings that are used to draw the panel.
+    ///
+    /// The theme is already applied to the panel colors and is replaced by the default theme. The
+    /// background color of the configured theme is returned separately, because it is still used
+    /// for the pixel spacing.
+    pub(crate) fn panel_output_settings(
+        output_settings: &OutputSettings,
+    ) -> (OutputSettings, Rgb888) {
+        let panel_output_settings = OutputSettings {
+            theme: BinaryColorTheme::Default,
+            ..*output_settings
+        };
+
+        (panel_output_settings, output_settings.theme.background())
+    }
+
+    /// Converts the panel into a RGB output image.
+    pub(crate) fn to_rgb_output_image(
+        &self,
+        output_settings: &OutputSettings,
+    ) -> OutputImage<Rgb888> {
+        let (panel_output_settings, background) = Self::panel_output_settings(output_settings);
+
+        let mut output = OutputImage::new(self.panel.output_size(&panel_output_settings));
+        output.draw_display_area_with_background(
+            &self.panel,
+            &self.panel.bounding_box(),
+            Point::zero(),
+            &panel_output_settings,
+            background,
+        );
+
+        output
+    }
+
+    /// Returns `true` if a refresh is in progress or was requested.
+    pub(crate) fn is_refreshing(&self) -> bool {
+        self.active.is_some() || !self.pending.is_empty()
+    }
+
+    /// Updates the panel state.
+    ///
+    /// Requested refreshes are started with the current content of `display`.
+    pub(crate) fn update<C>(
+        &mut self,
+        display: &SimulatorDisplay<C>,
+        theme: BinaryColorTheme,
+        now: Duration,
+    ) where
+        C: PixelColor + Into<Rgb888>,
+    {
+        let bounding_box = display.bounding_box();
+
+        // Area of the panel that needs to be rendered.
+        let mut render_area = None;
+
+        let size = display.size();
+        if self.size != size {
+            let len = size.width as usize * size.height as usize;
+
+            self.size = size;
+            self.source_display = None;
+            self.source = vec![Rgb888::BLACK; len];
+            self.content = self.source.clone();
+            self.ghost_colors = self.source.clone();
+            self.ghost_levels = vec![0.0; len];
+            self.active = None;
+            self.panel = SimulatorDisplay::new(size);
+            render_area = Some(bounding_box);
+        }
+
+        if self.theme != theme {
+            self.theme = theme;
+            render_area = Some(bounding_box);
+        }
+
+        let changed_area = match self
+            .source_display
+            .replace((display.history_id(), display.generation()))
+        {
+            Some((history_id, generation)) if history_id == display.history_id() => {
+                display.dirty_area_since(generation)
+            }
+            _ => Some(bounding_box),
+        };
+        for p in changed_area.iter().flat_map(Rectangle::points) {
+            let index = self.index(p);
+            self.source[index] = display.output_color(p);
+        }
+
+        loop {
+            if let Some(active) = &self.active {
+                if now < active.start + self.duration(active.refresh) {
+                    break;
+                }
+
+                add_area(&mut render_area, self.refresh_area(active.refresh));
+                self.finish_refresh();
+            } else if let Some(refresh) = self.pending.pop_front() {
+                self.active = Some(ActiveRefresh {
+                    refresh,
+                    start: now,
+                    content: self.source.clone(),
+                });
+            } else {
+                break;
+            }
+        }
+
+        if let Some(active) = &self.active {
+            add_area(&mut render_area, self.refresh_area(active.refresh));
+        }
+
+        if let Some(area) = render_area {
+            self.render(&area, now);
+        }
+    }
+
+    fn duration(&self, refresh: Refresh) -> Duration {
+        match refresh {
+            Refresh::Full => self.settings.full_refresh_duration,
+            Refresh::Partial(_) => self.settings.partial_refresh_duration,
+        }
+    }
+
+    fn index(&self, point: Point) -> usize {
+        point.y as usize * self.size.width as usize + point.x as usize
+    }
+
+    /// Returns the area that is updated by a refresh.
+    fn refresh_area(&self, refresh: Refresh) -> Rectangle {
+        let bounding_box = Rectangle::new(Point::zero(), self.size);
+
+        match refresh {
+            Refresh::Full => bounding_box,
+            Refresh::Partial(area) => area.intersection(&bounding_box),
+        }
+    }
+
+    /// Returns the ghost level a pixel has after it was changed by a partial refresh.
+    fn increased_ghost_level(&self, index: usize) -> f32 {
+        (self.ghost_levels[index] + self.settings.ghosting).min(self.settings.max_ghosting)
+    }
+
+    fn finish_refresh(&mut self) {
+        let Some(active) = self.active.take() else {
+            return;
+        };
+
+        for p in self.refresh_area(active.refresh).points() {
+            let index = self.index(p);
+            let new_color = active.content[index];
+
+            match active.refresh {
+                Refresh::Full => self.ghost_levels[index] = 0.0,
+                Refresh::Partial(_) if new_color != self.content[index] => {
+                    self.ghost_levels[index] = self.increased_ghost_level(index);
+                    self.ghost_colors[index] = self.content[index];
+                }
+                Refresh::Partial(_) => {}
+            }
+
+            self.content[index] = new_color;
+        }
+    }
+
+    /// Returns the themed color of a pixel including the ghosting.
+    fn ghosted_color(&self, index: usize) -> Rgb888 {
+        mix(
+            self.theme.convert(self.content[index]),
+            self.theme.convert(self.ghost_colors[index]),
+            self.ghost_levels[index],
+        )
+    }
+
+    /// Returns the color of a pixel including the effects of the active refresh.
+    fn panel_color(&self, point: Point, now: Duration) -> Rgb888 {
+        let index = self.index(point);
+        let color = self.ghosted_color(index);
+
+        let Some(active) = &self.active else {
+            return color;
+        };
+        let elapsed = now.saturating_sub(active.start);
+
+        match active.refresh {
+            Refresh::Full => {
+                let phases = self.settings.full_refresh_flashes * 2;
+                let phase = (elapsed.as_secs_f64() * f64::from(phases)
+                    / self.settings.full_refresh_duration.as_secs_f64())
+                    as u32;
+
+                // Each flash shows the color of white pixels, followed by black pixels.
+                if phase < phases {
+                    self.theme
+                        .convert([Rgb888::WHITE, Rgb888::BLACK][phase as usize % 2])
+                } else {
+                    color
+                }
+            }
+            Refresh::Partial(area) => {
+                let new_color = active.content[index];
+                if !area.contains(point) || new_color == self.content[index] {
+                    return color;
+                }
+
+                let progress = (elapsed.as_secs_f64()
+                    / self.settings.partial_refresh_duration.as_secs_f64())
+                .min(1.0) as f32;
+                let target = mix(
+                    self.theme.convert(new_color),
+                    self.theme.convert(self.content[index]),
+                    self.increased_ghost_level(index),
+                );
+
+                mix(color, target, progress)
+            }
+        }
+    }
+
+    /// Renders an area of the panel.
+    ///
+    /// Only pixels that have changed are drawn, to keep the dirty area of the panel small.
+    fn render(&mut self, area: &Rectangle, now: Duration) {
+        let pixels = area
+            .intersection(&self.panel.bounding_box())
+            .points()
+            .map(|p| Pixel(p, self.panel_color(p, now)))
+            .filter(|Pixel(p, color)| self.panel.get_pixel(*p) != *color)
+            .collect::<Vec<_>>();
+
+        self.panel.draw_iter(pixels).unwrap();
+    }
+}
+
+/// Extends an optional area to include another area.
+fn add_area(area: &mut Option<Rectangle>, other: Rectangle) {
+    *area = Some(match area {
+        Some(area) => envelope(area, &other),
+        None => other,
+    });
+}
+
+/// Mixes two colors.
+///
+/// Returns `a` if `amount` is `0.0` and `b` if `amount` is `1.0`.
+fn mix(a: Rgb888, b: Rgb888, amount: f32) -> Rgb888 {
+    let mix_channel =
+        |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * amount).round() as u8;
+
+    Rgb888::new(
+        mix_channel(a.r(), b.r()),
+        mix_channel(a.g(), b.g()),
+        mix_channel(a.b(), b.b()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    use super::*;
+
+    const PAPER: Rgb888 = Rgb888::new(245, 245, 245);
+    const INK: Rgb888 = Rgb888::new(32, 32, 32);
+    const THEME: BinaryColorTheme = BinaryColorTheme::LcdWhite;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn panel_pixel(epaper: &EPaper, point: Point) -> Rgb888 {
+        epaper.panel.get_pixel(point)
+    }
+
+    fn display() -> SimulatorDisplay<BinaryColor> {
+        SimulatorDisplay::new(Size::new(4, 2))
+    }
+
+    #[test]
+    fn content_is_only_shown_after_refresh() {
+        let mut display = display();
+        let mut epaper = EPaper::new(EPaperSettings::default());
+
+        epaper.update(&display, THEME, Duration::ZERO);
+        Pixel(Point::new(1, 1), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+        epaper.update(&display, THEME, MS * 100);
+
+        assert!(!epaper.is_refreshing());
+        assert_eq!(panel_pixel(&epaper, Point::new(1, 1)), PAPER);
+    }
+
+    #[test]
+    fn full_refresh() {
+        let mut display = display();
+        Pixel(Point::new(1, 1), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+
+        let mut epaper = EPaper::new(EPaperSettings::default());
+        epaper.refresh_full();
+
+        // 2 flashes in 2 seconds
+        for (time, color) in [(0, INK), (600, PAPER), (1100, INK), (1600, PAPER)] {
+            epaper.update(&display, THEME, MS * time);
+
+            assert!(epaper.is_refreshing());
+            assert!(epaper
+                .panel
+                .bounding_box()
+                .points()
+                .all(|p| epaper.panel.get_pixel(p) == color));
+        }
+
+        epaper.update(&display, THEME, MS * 2000);
+        assert!(!epaper.is_refreshing());
+        assert_eq!(panel_pixel(&epaper, Point::new(1, 1)), INK);
+        assert_eq!(panel_pixel(&epaper, Point::new(0, 1)), PAPER);
+    }
+
+    #[test]
+    fn partial_refresh_and_ghosting() {
+        let mut display = display();
+        let mut epaper = EPaper::new(EPaperSettings {
+            ghosting: 0.1,
+            max_ghosting: 0.25,
+            ..EPaperSettings::default()
+        });
+
+        let p = Point::new(2, 0);
+        let area = Rectangle::new(Point::new(2, 0), Size::new(2, 2));
+        let mut time = Duration::ZERO;
+
+        for (color, expected) in [
+            (BinaryColor::On, Rgb888::new(53, 53, 53)),
+            (BinaryColor::Off, Rgb888::new(202, 202, 202)),
+            (BinaryColor::On, Rgb888::new(85, 85, 85)),
+            (BinaryColor::Off, Rgb888::new(192, 192, 192)),
+        ] {
+            Pixel(p, color).draw(&mut display).unwrap();
+            // pixels outside of the refreshed area aren't changed
+            Pixel(Point::new(0, 0), color).draw(&mut display).unwrap();
+
+            epaper.refresh_partial(area);
+            epaper.update(&display, THEME, time);
+            assert!(epaper.is_refreshing());
+
+            time += MS * 300;
+            epaper.update(&display, THEME, time);
+            assert!(!epaper.is_refreshing());
+
+            assert_eq!(panel_pixel(&epaper, p), expected);
+            assert_eq!(panel_pixel(&epaper, Point::new(0, 0)), PAPER);
+            assert_eq!(panel_pixel(&epaper, Point::new(3, 0)), PAPER);
+        }
+
+        // a full refresh removes the ghosting
+        epaper.refresh_full();
+        epaper.update(&display, THEME, time);
+        epaper.update(&display, THEME, time + MS * 2000);
+
+        assert_eq!(panel_pixel(&epaper, p), PAPER);
+    }
+
+    #[test]
+    fn partial_refresh_transition() {
+        let mut display = display();
+        let mut epaper = EPaper::new(EPaperSettings {
+            ghosting: 0.0,
+            ..EPaperSettings::default()
+        });
+
+        Pixel(Point::zero(), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+        epaper.refresh_partial(display.bounding_box());
+        epaper.update(&display, THEME, Duration::ZERO);
+        epaper.update(&display, THEME, MS * 150);
+
+        assert_eq!(panel_pixel(&epaper, Point::zero()), mix(PAPER, INK, 0.5));
+    }
+
+    #[test]
+    fn queued_refreshes() {
+        let mut display = display();
+        let mut epaper = EPaper::new(EPaperSettings::default());
+
+        epaper.refresh_full();
+        epaper.refresh_partial(display.bounding_box());
+        epaper.update(&display, THEME, Duration::ZERO);
+
+        // the partial refresh starts after the full refresh and uses the content at that time
+        Pixel(Point::zero(), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+        epaper.update(&display, THEME, MS * 2000);
+        assert!(epaper.is_refreshing());
+        assert_eq!(panel_pixel(&epaper, Point::zero()), PAPER);
+
+        epaper.update(&display, THEME, MS * 2300);
+        assert!(!epaper.is_refreshing());
+        assert_eq!(panel_pixel(&epaper, Point::zero()), mix(INK, PAPER, 0.05));
+    }
+
+    #[test]
+    fn only_changed_areas_are_rendered() {
+        let mut display = display();
+        let mut epaper = EPaper::new(EPaperSettings::default());
+
+        epaper.update(&display, THEME, Duration::ZERO);
+        let generation = epaper.panel.generation();
+
+        // changes without a refresh and idle updates don't render the panel
+        Pixel(Point::new(3, 1), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+        epaper.update(&display, THEME, MS * 100);
+        epaper.update(&display, THEME, MS * 200);
+        assert_eq!(epaper.panel.generation(), generation);
+
+        // a partial refresh only changes the panel inside the refreshed area
+        let area = Rectangle::new(Point::new(2, 0), Size::new(2, 2));
+        epaper.refresh_partial(area);
+        epaper.update(&display, THEME, MS * 300);
+        epaper.update(&display, THEME, MS * 450);
+        epaper.update(&display, THEME, MS * 600);
+        assert_eq!(
+            epaper.panel.dirty_area_since(generation),
+            Some(Rectangle::new(Point::new(3, 1), Size::new(1, 1)))
+        );
+        assert_eq!(
+            panel_pixel(&epaper, Point::new(3, 1)),
+            mix(INK, PAPER, 0.05)
+        );
+
+        // changing the theme renders the complete panel
+        let generation = epaper.panel.generation();
+        epaper.update(&display, BinaryColorTheme::Inverted, MS * 700);
+        assert_eq!(
+            epaper.panel.dirty_area_since(generation),
+            Some(epaper.panel.bounding_box())
+        );
+    }
+}
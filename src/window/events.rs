@@ -0,0 +1,98 @@
+use std::{cell::RefMut, collections::VecDeque};
+
+use embedded_graphics::prelude::Size;
+
+#[cfg(feature = "with-sdl")]
+use sdl2::EventPump;
+
+use crate::{input::SimulatorEvent, window::input_log::InputLog, OutputSettings};
+
+/// Iterator over simulator events.
+///
+/// Events that were added by `push_event` are returned before the events received from the
+/// window backend. If the display size isn't known yet and the output settings rotate or mirror
+/// the display, the added events are kept in the queue until the size is known.
+///
+/// See [`Window::events`](crate::Window::events) and `MultiWindow::events` for more details.
+pub struct SimulatorEventsIter<'a> {
+    queue: RefMut<'a, VecDeque<SimulatorEvent>>,
+    #[cfg(feature = "with-sdl")]
+    event_pump: Option<RefMut<'a, EventPump>>,
+    output_settings: OutputSettings,
+    display_size: Option<Size>,
+    input_log: Option<RefMut<'a, InputLog>>,
+}
+
+impl<'a> SimulatorEventsIter<'a> {
+    pub(crate) fn new(
+        queue: RefMut<'a, VecDeque<SimulatorEvent>>,
+        output_settings: &OutputSettings,
+        display_size: Option<Size>,
+    ) -> Self {
+        Self {
+            queue,
+            #[cfg(feature = "with-sdl")]
+            event_pump: None,
+            output_settings: *output_settings,
+            display_size,
+            input_log: None,
+        }
+    }
+
+    pub(crate) fn with_input_log(mut self, input_log: Option<RefMut<'a, InputLog>>) -> Self {
+        self.input_log = input_log;
+        self
+    }
+
+    #[cfg(feature = "with-sdl")]
+    pub(crate) fn with_event_pump(mut self, event_pump: RefMut<'a, EventPump>) -> Self {
+        self.event_pump = Some(event_pump);
+        self
+    }
+}
+
+impl SimulatorEventsIter<'_> {
+    fn next_event(&mut self) -> Option<SimulatorEvent> {
+        if !self.queue.is_empty() {
+            // Mouse positions can't be translated to display coordinates before the display size
+            // is known, if the display is rotated or mirrored.
+            let display_size = match self.display_size {
+                Some(display_size) => display_size,
+                None if self.output_settings.depends_on_display_size() => return None,
+                None => Size::zero(),
+            };
+
+            let event = self.queue.pop_front().unwrap();
+            return Some(event.output_to_display(&self.output_settings, display_size));
+        }
+
+        #[cfg(feature = "with-sdl")]
+        if let Some(event_pump) = &mut self.event_pump {
+            while let Some(event) = event_pump.poll_event() {
+                if let Some(event) = super::sdl_window::map_event(
+                    event,
+                    &self.output_settings,
+                    self.display_size.unwrap_or_default(),
+                ) {
+                    return Some(event);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Iterator for SimulatorEventsIter<'_> {
+    type Item = SimulatorEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.next_event()?;
+
+        if let Some(input_log) = &mut self.input_log {
+            input_log.log(event);
+        }
+
+        Some(event)
+    }
+}
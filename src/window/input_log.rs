@@ -0,0 +1,131 @@
+use std::{
+    env,
+    fs::File,
+    io::{self, Write},
+    time::Duration,
+};
+
+use crate::{input::SimulatorEvent, window::script::Step};
+
+/// Writes all events returned by `Window::events` to the file set in `EG_SIMULATOR_RECORD_INPUT`.
+///
+/// The log uses the same format as `EG_SIMULATOR_SCRIPT` scripts and can be replayed by using it
+/// as a script.
+pub(crate) struct InputLog<W = File> {
+    writer: W,
+    /// Index of the frame that is currently shown.
+    frame: usize,
+    /// Time since the first frame was shown.
+    elapsed: Duration,
+    /// Index of the frame that was written to the log last.
+    logged_frame: Option<usize>,
+}
+
+impl InputLog {
+    /// Creates an input log if the `EG_SIMULATOR_RECORD_INPUT` environment variable is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the log file couldn't be created.
+    pub(crate) fn from_env() -> Option<Self> {
+        let path = env::var_os("EG_SIMULATOR_RECORD_INPUT")?;
+        let file = File::create(&path).unwrap_or_else(|error| {
+            panic!(
+                "failed to create input log {}: {error}",
+                path.to_string_lossy()
+            )
+        });
+
+        Some(Self::new(file))
+    }
+}
+
+impl<W: Write> InputLog<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            frame: 0,
+            elapsed: Duration::ZERO,
+            logged_frame: None,
+        }
+    }
+
+    /// Sets the index and time of the frame that is currently shown.
+    pub(crate) fn set_frame(&mut self, frame: usize, elapsed: Duration) {
+        self.frame = frame;
+        self.elapsed = elapsed;
+    }
+
+    /// Writes an event to the log.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event couldn't be written.
+    pub(crate) fn log(&mut self, event: SimulatorEvent) {
+        self.write(event)
+            .unwrap_or_else(|error| panic!("failed to write input log: {error}"));
+    }
+
+    fn write(&mut self, event: SimulatorEvent) -> io::Result<()> {
+        if self.logged_frame != Some(self.frame) {
+            self.logged_frame = Some(self.frame);
+
+            writeln!(
+                self.writer,
+                "{} # {:.3}s",
+                Step::Frame(self.frame),
+                self.elapsed.as_secs_f64()
+            )?;
+        }
+
+        // Mouse positions are logged in display coordinates, which are translated back to
+        // window coordinates when the log is replayed.
+        writeln!(self.writer, "{}", Step::Event(event))?;
+
+        // The log is flushed after each event, because the process might be terminated by
+        // `process::exit` which doesn't run destructors.
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_graphics::prelude::*;
+
+    use crate::input::{Keycode, Mod, MouseButton};
+
+    #[test]
+    fn log_events() {
+        let mut log = InputLog::new(Vec::new());
+
+        log.log(SimulatorEvent::MouseMove {
+            point: Point::new(1, 2),
+        });
+        log.set_frame(5, Duration::from_millis(80));
+        log.log(SimulatorEvent::KeyDown {
+            keycode: Keycode::DOWN,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        });
+        log.log(SimulatorEvent::MouseButtonUp {
+            mouse_btn: MouseButton::Left,
+            point: Point::new(3, 4),
+        });
+        log.set_frame(6, Duration::from_millis(96));
+        log.set_frame(7, Duration::from_millis(112));
+        log.log(SimulatorEvent::Quit);
+
+        assert_eq!(
+            String::from_utf8(log.writer).unwrap(),
+            "frame 0 # 0.000s\n\
+             mousemove 1,2\n\
+             frame 5 # 0.080s\n\
+             keydown DOWN\n\
+             mouseup left 3,4\n\
+             frame 7 # 0.112s\n\
+             quit\n"
+        );
+    }
+}
@@ -1,23 +1,39 @@
-use std::{
-    env,
-    fs::File,
-    io::BufReader,
-    ops::Deref,
-    process, thread,
-    time::{Duration, Instant},
-};
+use std::{cell::RefCell, collections::VecDeque, env, path::Path, process, time::Duration};
+
+#[cfg(feature = "with-sdl")]
+use std::thread;
 
-use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+use embedded_graphics::{pixelcolor::Rgb888, prelude::*, primitives::Rectangle};
 
 use crate::{
-    display::SimulatorDisplay, output_image::OutputImage, output_settings::OutputSettings,
+    display::SimulatorDisplay,
+    input::SimulatorEvent,
+    output_image::OutputImage,
+    output_settings::OutputSettings,
+    snapshot::SnapshotError,
+    transfer::{TransferEstimate, TransferModel},
+    window::{
+        clock::Clock, dump::Dump, epaper::EPaper, input_log::InputLog, recorder::Recorder,
+        script::Script,
+    },
 };
 
+mod clock;
+mod dump;
+mod epaper;
+mod events;
+mod input_log;
+mod recorder;
+mod script;
+
+pub use epaper::EPaperSettings;
+pub use events::SimulatorEventsIter;
+
 #[cfg(feature = "with-sdl")]
 mod sdl_window;
 
 #[cfg(feature = "with-sdl")]
-pub use sdl_window::{SdlWindow, SimulatorEvent, SimulatorEventsIter};
+pub use sdl_window::SdlWindow;
 
 #[cfg(feature = "with-sdl")]
 mod multi_window;
@@ -25,185 +41,728 @@ mod multi_window;
 #[cfg(feature = "with-sdl")]
 pub use multi_window::MultiWindow;
 
-pub(crate) struct FpsLimiter {
-    max_fps: u32,
-    frame_start: Instant,
-}
-
-impl FpsLimiter {
-    pub(crate) fn new() -> Self {
-        Self {
-            max_fps: 60,
-            frame_start: Instant::now(),
-        }
-    }
-
-    fn desired_loop_duration(&self) -> Duration {
-        Duration::from_secs_f32(1.0 / self.max_fps as f32)
-    }
-
-    fn sleep(&mut self) {
-        let sleep_duration = (self.frame_start + self.desired_loop_duration())
-            .saturating_duration_since(Instant::now());
-        thread::sleep(sleep_duration);
-
-        self.frame_start = Instant::now();
-    }
-}
-
 /// Simulator window
 #[allow(dead_code)]
 pub struct Window {
     framebuffer: Option<OutputImage<Rgb888>>,
+    /// History ID and generation of the display that was last drawn to the framebuffer.
+    shown_display: Option<(usize, u64)>,
     #[cfg(feature = "with-sdl")]
     sdl_window: Option<SdlWindow>,
+    headless: bool,
+    event_queue: RefCell<VecDeque<SimulatorEvent>>,
     title: String,
     output_settings: OutputSettings,
-    fps_limiter: FpsLimiter,
+    /// Size of the display passed to the last `update` call.
+    display_size: Option<Size>,
+    clock: Clock,
+    dump: Option<Dump>,
+    frame: usize,
+    frame_label: Option<String>,
+    recorder: Option<Recorder>,
+    script: Option<Script>,
+    input_log: Option<RefCell<InputLog>>,
+    epaper: Option<EPaper>,
+    transfer_model: Option<TransferModel>,
+    /// History ID and generation of the display that was used for the last transfer estimate.
+    ///
+    /// This is tracked separately from `shown_display`, because the framebuffer shows the panel
+    /// instead of the application display in e-paper mode.
+    estimated_display: Option<(usize, u64)>,
+    transfer_estimate: Option<TransferEstimate>,
 }
 
 impl Window {
     /// Creates a new simulator window.
     pub fn new(title: &str, output_settings: &OutputSettings) -> Self {
+        Self::new_common(title, output_settings, false)
+    }
+
+    /// Creates a new headless simulator window.
+    ///
+    /// A headless window never opens a window, even if the `with-sdl` feature is enabled. The only
+    /// events returned by [`events`](Self::events) are the events added by
+    /// [`push_event`](Self::push_event), which makes it possible to test input handling code.
+    ///
+    /// Without the `with-sdl` feature windows created by [`new`](Self::new) are also headless.
+    pub fn new_headless(output_settings: &OutputSettings) -> Self {
+        Self::new_common("", output_settings, true)
+    }
+
+    fn new_common(title: &str, output_settings: &OutputSettings, headless: bool) -> Self {
         Self {
             framebuffer: None,
+            shown_display: None,
             #[cfg(feature = "with-sdl")]
             sdl_window: None,
+            headless,
+            event_queue: RefCell::new(VecDeque::new()),
             title: String::from(title),
-            output_settings: output_settings.clone(),
-            fps_limiter: FpsLimiter::new(),
+            output_settings: *output_settings,
+            display_size: None,
+            clock: Clock::from_env(headless),
+            dump: Dump::from_env(),
+            frame: 0,
+            frame_label: None,
+            recorder: recorder_from_env(),
+            script: Script::from_env(),
+            input_log: InputLog::from_env().map(RefCell::new),
+            epaper: None,
+            transfer_model: None,
+            estimated_display: None,
+            transfer_estimate: None,
         }
     }
 
     /// Updates the window.
     pub fn update<C>(&mut self, display: &SimulatorDisplay<C>)
     where
-        C: PixelColor + Into<Rgb888> + From<Rgb888>,
+        C: PixelColor + Into<Rgb888>,
     {
-        if let Ok(path) = env::var("EG_SIMULATOR_CHECK") {
-            let output = display.to_rgb_output_image(&self.output_settings);
-
-            let png_file = BufReader::new(File::open(path).unwrap());
-            let expected = image::load(png_file, image::ImageFormat::Png)
-                .unwrap()
-                .to_rgb8();
-
-            let png_size = Size::new(expected.width(), expected.height());
-
-            assert!(
-                output.size().eq(&png_size),
-                "display dimensions don't match PNG dimensions (display: {}x{}, PNG: {}x{})",
-                output.size().width,
-                output.size().height,
-                png_size.width,
-                png_size.height
-            );
+        self.display_size = Some(display.size());
 
-            assert!(
-                output
-                    .as_image_buffer()
-                    .as_raw()
-                    .eq(&expected.as_raw().deref()),
-                "display content doesn't match PNG file",
-            );
+        if let Ok(path) = env::var("EG_SIMULATOR_CHECK") {
+            if let Err(error) = display.assert_matches_png(path, &self.output_settings) {
+                panic!("{error}");
+            }
 
-            process::exit(0);
+            self.exit();
         }
 
         if let Ok(path) = env::var("EG_SIMULATOR_CHECK_RAW") {
-            let expected = SimulatorDisplay::load_png(path).unwrap();
-
-            assert!(
-                display.size().eq(&expected.size()),
-                "display dimensions don't match PNG dimensions (display: {}x{}, PNG: {}x{})",
-                display.size().width,
-                display.size().height,
-                expected.size().width,
-                expected.size().height
-            );
+            if let Err(error) = check_raw(display, &path) {
+                panic!("{error}");
+            }
 
-            assert!(
-                display.pixels.eq(&expected.pixels),
-                "display content doesn't match PNG file",
-            );
+            self.exit();
+        }
 
-            process::exit(0);
+        let frame = self.frame;
+        let frame_label = self.frame_label.take();
+        let elapsed = self.clock.next_frame();
+        self.frame += 1;
+
+        if let Some(input_log) = &mut self.input_log {
+            input_log.get_mut().set_frame(frame, elapsed);
         }
 
-        if let Ok(path) = env::var("EG_SIMULATOR_DUMP") {
-            display
-                .to_rgb_output_image(&self.output_settings)
-                .save_png(path)
-                .unwrap();
-            process::exit(0);
+        if let Some(script) = &mut self.script {
+            // The process is terminated one frame after the last step was executed, to give the
+            // application a chance to handle events added by the last step.
+            if script.is_done() {
+                self.exit();
+            }
+
+            script.update(
+                display,
+                frame,
+                elapsed,
+                &self.output_settings,
+                self.event_queue.get_mut(),
+            );
         }
 
-        if let Ok(path) = env::var("EG_SIMULATOR_DUMP_RAW") {
-            display
-                .to_rgb_output_image(&OutputSettings::default())
-                .save_png(path)
-                .unwrap();
-            process::exit(0);
+        let estimated_generation = self
+            .estimated_display
+            .replace((display.history_id(), display.generation()))
+            .filter(|(history_id, _)| *history_id == display.history_id())
+            .map_or(0, |(_, generation)| generation);
+        self.transfer_estimate = self
+            .transfer_model
+            .map(|model| model.estimate(display, estimated_generation));
+
+        if let Some(epaper) = &mut self.epaper {
+            epaper.update(display, self.output_settings.theme, elapsed);
         }
 
-        #[cfg(feature = "with-sdl")]
-        {
-            let size = display.output_size(&self.output_settings);
+        if let Some(dump) = &mut self.dump {
+            if let Some(path) = dump.select(frame, elapsed, frame_label.as_deref()) {
+                let image = match &self.epaper {
+                    _ if dump.raw => display.to_rgb_output_image(&OutputSettings::default()),
+                    Some(epaper) => epaper.to_rgb_output_image(&self.output_settings),
+                    None => display.to_rgb_output_image(&self.output_settings),
+                };
 
-            if self.framebuffer.is_none() {
-                self.framebuffer = Some(OutputImage::new(size));
-            }
+                image.save_png(path).unwrap();
 
-            if self.sdl_window.is_none() {
-                self.sdl_window = Some(SdlWindow::new(&self.title, size));
+                if dump.is_done() {
+                    self.exit();
+                }
             }
+        }
 
-            let framebuffer = self.framebuffer.as_mut().unwrap();
-            let sdl_window = self.sdl_window.as_mut().unwrap();
+        #[cfg_attr(not(feature = "with-sdl"), allow(unused_variables))]
+        let output_area = if let Some(epaper) = self.epaper.take() {
+            let (output_settings, background) =
+                EPaper::panel_output_settings(&self.output_settings);
+            let output_area = self.update_framebuffer(&epaper.panel, &output_settings, background);
+
+            self.epaper = Some(epaper);
+            output_area
+        } else {
+            let output_settings = self.output_settings;
+            self.update_framebuffer(
+                display,
+                &output_settings,
+                output_settings.theme.background(),
+            )
+        };
+        let framebuffer = self.framebuffer.as_ref().unwrap();
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.add_frame(framebuffer, elapsed);
+        }
 
-            framebuffer.draw_display(display, Point::zero(), &self.output_settings);
-            sdl_window.update(framebuffer);
+        // No window is opened while a dump waits for the selected frames, to make it possible to
+        // create screenshots in environments without a display.
+        #[cfg(feature = "with-sdl")]
+        if !self.headless && !self.dump.as_ref().is_some_and(Dump::is_waiting) {
+            let title = match &self.transfer_estimate {
+                Some(estimate) => format!("{} - {estimate}", self.title),
+                None => self.title.clone(),
+            };
+
+            let sdl_window = self
+                .sdl_window
+                .get_or_insert_with(|| SdlWindow::new(&title, framebuffer.size()));
+
+            sdl_window.set_title(&title);
+            sdl_window.update(framebuffer, &output_area);
         }
 
-        self.fps_limiter.sleep();
+        self.clock.sleep();
+    }
+
+    /// Draws the display to the framebuffer and returns the changed area.
+    ///
+    /// `background` is used for the spacing between pixels.
+    fn update_framebuffer<C>(
+        &mut self,
+        display: &SimulatorDisplay<C>,
+        output_settings: &OutputSettings,
+        background: Rgb888,
+    ) -> Rectangle
+    where
+        C: PixelColor + Into<Rgb888>,
+    {
+        let framebuffer = self
+            .framebuffer
+            .get_or_insert_with(|| OutputImage::new(display.output_size(output_settings)));
+
+        // Only the changed area of the display is redrawn, unless the previous update was
+        // called with a different display.
+        let dirty_area = match self
+            .shown_display
+            .replace((display.history_id(), display.generation()))
+        {
+            Some((history_id, generation)) if history_id == display.history_id() => {
+                display.dirty_area_since(generation)
+            }
+            _ => Some(display.bounding_box()),
+        };
+
+        dirty_area
+            .map(|area| {
+                framebuffer.draw_display_area_with_background(
+                    display,
+                    &area,
+                    Point::zero(),
+                    output_settings,
+                    background,
+                )
+            })
+            .unwrap_or_else(Rectangle::zero)
     }
 
     /// Shows a static display.
     ///
     /// This methods updates the window once and loops until the simulator window
-    /// is closed.
+    /// is closed. Headless windows return after the update. In e-paper mode the window is updated
+    /// until all requested refreshes are finished.
     pub fn show_static<C>(&mut self, display: &SimulatorDisplay<C>)
     where
-        C: PixelColor + Into<Rgb888> + From<Rgb888>,
+        C: PixelColor + Into<Rgb888>,
     {
         self.update(display);
+        while self.is_refreshing() {
+            self.update(display);
+        }
 
         #[cfg(feature = "with-sdl")]
-        'running: loop {
-            if self.events().any(|e| e == SimulatorEvent::Quit) {
-                break 'running;
+        if self.sdl_window.is_some() {
+            'running: loop {
+                if self.events().any(|e| e == SimulatorEvent::Quit) {
+                    break 'running;
+                }
+                thread::sleep(Duration::from_millis(20));
             }
-            thread::sleep(Duration::from_millis(20));
         }
     }
 
     /// Returns an iterator of all captured simulator events.
     ///
+    /// Events added by [`push_event`](Self::push_event) are returned first, followed by the
+    /// events received by the SDL window. Before [`update`](Self::update) was called for the first
+    /// time only the pushed events are returned. If the output settings rotate or mirror the
+    /// display, the pushed events are only returned after the first update, because the display
+    /// size is required to translate mouse positions.
+    ///
     /// # Panics
     ///
-    /// Panics if called before [`update`](Self::update) is called at least
-    /// once. Also panics if multiple instances of the iterator are used at the
-    /// same time.
-    #[cfg(feature = "with-sdl")]
+    /// Panics if multiple instances of the iterator are used at the same time.
     pub fn events(&self) -> SimulatorEventsIter<'_> {
-        self.sdl_window
-            .as_ref()
-            .unwrap()
-            .events(&self.output_settings)
+        let events = SimulatorEventsIter::new(
+            self.event_queue.borrow_mut(),
+            &self.output_settings,
+            self.display_size,
+        )
+        .with_input_log(self.input_log.as_ref().map(RefCell::borrow_mut));
+
+        #[cfg(feature = "with-sdl")]
+        if let Some(sdl_window) = &self.sdl_window {
+            return events.with_event_pump(sdl_window.event_pump());
+        }
+
+        events
+    }
+
+    /// Adds a synthetic event to the event queue.
+    ///
+    /// The event is returned by the next call to [`events`](Self::events). Mouse positions are
+    /// interpreted as window coordinates and are translated to display coordinates by using the
+    /// window's output settings, in the same way as events that are received by the SDL window.
+    ///
+    /// ```
+    /// use embedded_graphics::prelude::*;
+    /// use embedded_graphics_simulator::{
+    ///     input::MouseButton, OutputSettingsBuilder, SimulatorEvent, Window,
+    /// };
+    ///
+    /// let output_settings = OutputSettingsBuilder::new().scale(2).build();
+    /// let mut window = Window::new_headless(&output_settings);
+    ///
+    /// window.push_event(SimulatorEvent::MouseButtonUp {
+    ///     mouse_btn: MouseButton::Left,
+    ///     point: Point::new(20, 10),
+    /// });
+    ///
+    /// assert_eq!(
+    ///     window.events().collect::<Vec<_>>(),
+    ///     [SimulatorEvent::MouseButtonUp {
+    ///         mouse_btn: MouseButton::Left,
+    ///         point: Point::new(10, 5),
+    ///     }]
+    /// );
+    /// ```
+    pub fn push_event(&mut self, event: SimulatorEvent) {
+        self.event_queue.get_mut().push_back(event);
     }
 
     /// Sets the FPS limit of the window.
+    ///
+    /// If the virtual clock is used the FPS limit also sets the time step of the clock.
     pub fn set_max_fps(&mut self, max_fps: u32) {
-        self.fps_limiter.max_fps = max_fps;
+        self.clock.max_fps = max_fps;
+    }
+
+    /// Changes the output settings of the window.
+    ///
+    /// The complete display is redrawn with the new settings by the next call to
+    /// [`update`](Self::update). This can be used to change the theme while the window is open,
+    /// e.g. to emulate the contrast setting of a display controller. If the scale or pixel spacing
+    /// is changed the SDL window is recreated with the new size.
+    pub fn set_output_settings(&mut self, output_settings: &OutputSettings) {
+        if self.output_settings == *output_settings {
+            return;
+        }
+
+        #[cfg(feature = "with-sdl")]
+        if self.output_settings.scale != output_settings.scale
+            || self.output_settings.pixel_spacing != output_settings.pixel_spacing
+            || self.output_settings.rotation != output_settings.rotation
+        {
+            self.sdl_window = None;
+        }
+
+        self.output_settings = *output_settings;
+        self.framebuffer = None;
+        self.shown_display = None;
+    }
+
+    /// Enables or disables the virtual clock.
+    ///
+    /// By default the simulator clock follows the real time and [`update`](Self::update) sleeps to
+    /// limit the frame rate. The virtual clock instead advances by a fixed time step of `1 /
+    /// max_fps` for each update and doesn't sleep. This makes the timing of an application
+    /// independent of the execution speed, which is useful in tests: a 10 second animation can be
+    /// checked in a fraction of that time and every run produces the same frames, as long as the
+    /// application uses [`now`](Self::now) or [`frame_time`](Self::frame_time) instead of the system
+    /// time.
+    ///
+    /// The virtual clock is enabled by default for headless windows and can also be enabled by
+    /// setting the `EG_SIMULATOR_VIRTUAL_CLOCK` environment variable to `1`.
+    pub fn set_virtual_clock(&mut self, virtual_clock: bool) {
+        self.clock.virtual_clock = virtual_clock;
+    }
+
+    /// Returns the simulator time.
+    ///
+    /// The time of a frame is measured relative to the first [`update`](Self::update) call and
+    /// doesn't change until the next frame is passed to `update`. Returns zero before the first
+    /// `update` call.
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// Returns the simulator time between the last two [`update`](Self::update) calls.
+    pub fn frame_time(&self) -> Duration {
+        self.clock.frame_time()
+    }
+
+    /// Enables or disables the e-paper mode.
+    ///
+    /// In e-paper mode [`update`](Self::update) doesn't show changes to the display immediately.
+    /// New content is only shown after a refresh was requested by calling
+    /// [`refresh_full`](Self::refresh_full) or [`refresh_partial`](Self::refresh_partial). Before
+    /// the first refresh the window shows a blank display with the color of black pixels.
+    ///
+    /// The refreshes take the time defined in the `settings` and need to be animated by calling
+    /// `update` regularly, like in a typical event loop. A full refresh flashes the display
+    /// between black and white before the new content is shown. Partial refreshes show the new
+    /// content without flashing, but each pixel that is changed by a partial refresh accumulates
+    /// ghosting, which means that a fraction of the previous color remains visible. The ghosting
+    /// is removed by the next full refresh.
+    ///
+    /// ```
+    /// use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+    /// use embedded_graphics_simulator::{
+    ///     BinaryColorTheme, EPaperSettings, OutputSettingsBuilder, SimulatorDisplay, Window,
+    /// };
+    ///
+    /// let output_settings = OutputSettingsBuilder::new()
+    ///     .theme(BinaryColorTheme::LcdWhite)
+    ///     .build();
+    /// let mut window = Window::new_headless(&output_settings);
+    /// window.set_epaper(Some(EPaperSettings::default()));
+    ///
+    /// let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(200, 200));
+    /// // draw to the display
+    ///
+    /// window.refresh_full();
+    /// while window.is_refreshing() {
+    ///     window.update(&display);
+    /// }
+    /// ```
+    pub fn set_epaper(&mut self, settings: Option<EPaperSettings>) {
+        self.epaper = settings.map(EPaper::new);
+        self.shown_display = None;
+    }
+
+    /// Requests a full refresh of the e-paper display.
+    ///
+    /// The refresh is started by the next call to [`update`](Self::update), or after the refresh
+    /// that is currently in progress has finished. The content of the display at that time is
+    /// shown after the refresh.
+    ///
+    /// Does nothing if the e-paper mode isn't enabled by [`set_epaper`](Self::set_epaper).
+    pub fn refresh_full(&mut self) {
+        if let Some(epaper) = &mut self.epaper {
+            epaper.refresh_full();
+        }
+    }
+
+    /// Requests a partial refresh of an area of the e-paper display.
+    ///
+    /// The refresh is started in the same way as a full refresh started by
+    /// [`refresh_full`](Self::refresh_full), but only updates the pixels inside `area`.
+    ///
+    /// Does nothing if the e-paper mode isn't enabled by [`set_epaper`](Self::set_epaper).
+    pub fn refresh_partial(&mut self, area: Rectangle) {
+        if let Some(epaper) = &mut self.epaper {
+            epaper.refresh_partial(area);
+        }
+    }
+
+    /// Returns `true` if a refresh of the e-paper display is in progress or was requested.
+    ///
+    /// This is the equivalent of the busy signal of an e-paper controller.
+    pub fn is_refreshing(&self) -> bool {
+        self.epaper.as_ref().is_some_and(EPaper::is_refreshing)
+    }
+
+    /// Sets the display bus transfer model.
+    ///
+    /// If a transfer model is set, [`update`](Self::update) estimates the time that is required
+    /// to transfer each frame to a real display and shows the estimate in the window title. The
+    /// estimate for the last frame is returned by [`transfer_estimate`](Self::transfer_estimate),
+    /// which can be used to check the frame budget of an application in tests.
+    ///
+    /// ```
+    /// use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+    /// use embedded_graphics_simulator::{
+    ///     FlushMode, OutputSettings, SimulatorDisplay, TransferModel, Window,
+    /// };
+    ///
+    /// let mut window = Window::new_headless(&OutputSettings::default());
+    /// window.set_transfer_model(Some(TransferModel {
+    ///     flush_mode: FlushMode::DirtyArea,
+    ///     ..TransferModel::spi(10_000_000)
+    /// }));
+    ///
+    /// let mut display = SimulatorDisplay::<Rgb565>::new(Size::new(320, 240));
+    /// window.update(&display);
+    ///
+    /// display.clear(Rgb565::BLUE).unwrap();
+    /// window.update(&display);
+    /// assert!(window.transfer_estimate().unwrap().max_fps() >= 8.0);
+    /// ```
+    pub fn set_transfer_model(&mut self, transfer_model: Option<TransferModel>) {
+        self.transfer_model = transfer_model;
+        self.estimated_display = None;
+        self.transfer_estimate = None;
+    }
+
+    /// Returns the transfer estimate for the last frame.
+    ///
+    /// Returns `None` if no transfer model is set by
+    /// [`set_transfer_model`](Self::set_transfer_model) or `update` wasn't called since the
+    /// model was set.
+    pub fn transfer_estimate(&self) -> Option<TransferEstimate> {
+        self.transfer_estimate
+    }
+
+    /// Starts recording the window content.
+    ///
+    /// All frames shown by [`update`](Self::update) are recorded until
+    /// [`stop_recording`](Self::stop_recording) is called or the window is dropped. The recording
+    /// is saved as an animated GIF if `path` has a `.gif` extension or as an animated PNG if it has
+    /// a `.png` or `.apng` extension. Any previous recording that wasn't stopped is discarded.
+    ///
+    /// Returns an error if `path` has an unsupported extension.
+    pub fn start_recording<P: AsRef<Path>>(&mut self, path: P) -> image::ImageResult<()> {
+        self.recorder = Some(Recorder::new(path)?);
+
+        Ok(())
+    }
+
+    /// Stops the recording and saves it to the file passed to
+    /// [`start_recording`](Self::start_recording).
+    ///
+    /// Does nothing if no recording is active.
+    pub fn stop_recording(&mut self) -> image::ImageResult<()> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(self.clock.frame_end()),
+            None => Ok(()),
+        }
+    }
+
+    /// Labels the frame passed to the next [`update`](Self::update) call.
+    ///
+    /// Labels can be used to select the frame that is exported by `EG_SIMULATOR_DUMP` by setting
+    /// `EG_SIMULATOR_DUMP_LABEL`. See the [crate level documentation](crate) for more details.
+    pub fn mark_frame(&mut self, label: &str) {
+        self.frame_label = Some(label.to_string());
+    }
+
+    /// Saves the active recording and terminates the process.
+    ///
+    /// `process::exit` doesn't run destructors, which would otherwise save the recording.
+    fn exit(&mut self) -> ! {
+        if let Err(error) = self.stop_recording() {
+            eprintln!("failed to save recording: {error}");
+        }
+
+        process::exit(0);
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        if let Err(error) = self.stop_recording() {
+            eprintln!("failed to save recording: {error}");
+        }
+    }
+}
+
+/// Creates a recorder if the `EG_SIMULATOR_RECORD` environment variable is set.
+///
+/// # Panics
+///
+/// Panics if the variable contains a path with an unsupported extension.
+pub(crate) fn recorder_from_env() -> Option<Recorder> {
+    env::var_os("EG_SIMULATOR_RECORD")
+        .map(|path| Recorder::new(path).expect("invalid EG_SIMULATOR_RECORD value"))
+}
+
+/// Compares the unstyled display content with a reference PNG file.
+///
+/// The display colors are converted to `Rgb888` before they are compared with the reference image,
+/// which makes it possible to check displays with color types that don't implement `From<Rgb888>`.
+fn check_raw<C>(display: &SimulatorDisplay<C>, path: &str) -> Result<(), SnapshotError>
+where
+    C: PixelColor + Into<Rgb888>,
+{
+    display.assert_matches_png(path, &OutputSettings::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_graphics::pixelcolor::{raw::RawU4, BinaryColor, Rgb565};
+
+    use crate::{
+        test_util::TempDir, transfer::FlushMode, BinaryColorTheme, IndexedColor,
+        OutputSettingsBuilder, Rotation,
+    };
+
+    #[test]
+    fn check_raw_rgb565() {
+        let dir = TempDir::new("check_raw");
+        let path = dir.join("reference.png");
+        let path = path.to_str().unwrap();
+
+        let mut display = SimulatorDisplay::<Rgb565>::new(Size::new(2, 1));
+        Pixel(Point::new(1, 0), Rgb565::new(30, 0, 0))
+            .draw(&mut display)
+            .unwrap();
+
+        // reference images created by `EG_SIMULATOR_DUMP_RAW` match
+        display
+            .to_rgb_output_image(&OutputSettings::default())
+            .save_png(path)
+            .unwrap();
+        check_raw(&display, path).unwrap();
+
+        // colors are compared after they were converted to `Rgb888`, so a reference color that
+        // only differs in the bits that are lost in `Rgb565` doesn't match
+        let mut reference = SimulatorDisplay::<Rgb888>::new(Size::new(2, 1));
+        Pixel(Point::new(1, 0), Rgb888::new(250, 0, 0))
+            .draw(&mut reference)
+            .unwrap();
+        reference
+            .to_rgb_output_image(&OutputSettings::default())
+            .save_png(path)
+            .unwrap();
+        assert!(check_raw(&display, path).is_err());
+    }
+
+    #[test]
+    fn indexed_display() {
+        let mut window = Window::new_headless(&OutputSettings::default());
+
+        let mut display = SimulatorDisplay::<IndexedColor<RawU4>>::new(Size::new(4, 2));
+        display.set_palette(&[Rgb888::BLACK, Rgb888::RED, Rgb888::GREEN]);
+        Pixel(Point::new(1, 1), IndexedColor::new(2))
+            .draw(&mut display)
+            .unwrap();
+        window.update(&display);
+
+        let framebuffer = window.framebuffer.as_ref().unwrap().to_display();
+        assert_eq!(framebuffer.get_pixel(Point::new(1, 1)), Rgb888::GREEN);
+        assert_eq!(framebuffer.get_pixel(Point::new(0, 0)), Rgb888::BLACK);
+
+        // changing the palette redraws the display
+        display.palette_mut().unwrap()[2] = Rgb888::BLUE;
+        window.update(&display);
+
+        let framebuffer = window.framebuffer.as_ref().unwrap().to_display();
+        assert_eq!(framebuffer.get_pixel(Point::new(1, 1)), Rgb888::BLUE);
+    }
+
+    #[test]
+    fn diverged_clone() {
+        let mut window = Window::new_headless(&OutputSettings::default());
+
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(4, 2));
+        let mut clone = display.clone();
+        Pixel(Point::new(1, 1), BinaryColor::On)
+            .draw(&mut clone)
+            .unwrap();
+        window.update(&clone);
+
+        // The original display has a lower generation than the clone, but needs to be redrawn.
+        Pixel(Point::new(3, 0), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+        window.update(&display);
+
+        let framebuffer = window.framebuffer.as_ref().unwrap().to_display();
+        assert_eq!(framebuffer.get_pixel(Point::new(1, 1)), Rgb888::BLACK);
+        assert_eq!(framebuffer.get_pixel(Point::new(3, 0)), Rgb888::WHITE);
+    }
+
+    #[test]
+    fn events_before_first_update() {
+        let output_settings = OutputSettingsBuilder::new()
+            .rotation(Rotation::Deg90)
+            .build();
+        let mut window = Window::new_headless(&output_settings);
+
+        let event = SimulatorEvent::MouseMove {
+            point: Point::new(0, 0),
+        };
+        window.push_event(event);
+        assert_eq!(window.events().count(), 0);
+
+        window.update(&SimulatorDisplay::<BinaryColor>::new(Size::new(16, 8)));
+        assert_eq!(
+            window.events().collect::<Vec<_>>(),
+            [SimulatorEvent::MouseMove {
+                point: Point::new(0, 7)
+            }]
+        );
+    }
+
+    #[test]
+    fn epaper_pixel_spacing() {
+        let output_settings = OutputSettingsBuilder::new()
+            .theme(BinaryColorTheme::LcdWhite)
+            .scale(2)
+            .pixel_spacing(1)
+            .build();
+        let mut window = Window::new_headless(&output_settings);
+        window.set_epaper(Some(EPaperSettings::default()));
+
+        let display = SimulatorDisplay::<BinaryColor>::new(Size::new(4, 2));
+        window.update(&display);
+
+        let paper = Rgb888::new(245, 245, 245);
+        let framebuffer = window.framebuffer.as_ref().unwrap().to_display();
+        assert_eq!(framebuffer.get_pixel(Point::new(0, 0)), paper);
+        // the spacing uses the background color of the theme
+        assert_eq!(framebuffer.get_pixel(Point::new(2, 0)), paper);
+        assert_eq!(framebuffer.get_pixel(Point::new(0, 2)), paper);
+
+        let image = window
+            .epaper
+            .as_ref()
+            .unwrap()
+            .to_rgb_output_image(&output_settings)
+            .to_display();
+        assert_eq!(image, framebuffer);
+    }
+
+    #[test]
+    fn epaper_dirty_area_transfer() {
+        let mut window = Window::new_headless(&OutputSettings::default());
+        window.set_epaper(Some(EPaperSettings::default()));
+        window.set_transfer_model(Some(TransferModel {
+            flush_mode: FlushMode::DirtyArea,
+            ..TransferModel::i2c(400_000)
+        }));
+
+        let mut display = SimulatorDisplay::<BinaryColor>::new(Size::new(16, 8));
+        window.update(&display);
+        assert_eq!(window.transfer_estimate().unwrap().bytes, 16 * 8 / 8 + 10);
+
+        window.update(&display);
+        assert_eq!(window.transfer_estimate().unwrap().regions, 0);
+
+        Pixel(Point::new(3, 2), BinaryColor::On)
+            .draw(&mut display)
+            .unwrap();
+        window.update(&display);
+        assert_eq!(window.transfer_estimate().unwrap().bytes, 1 + 10);
     }
 }
@@ -1,9 +1,19 @@
-use std::collections::HashMap;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    path::Path,
+    time::Duration,
+};
 
-use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+use embedded_graphics::{pixelcolor::Rgb888, prelude::*, primitives::Rectangle};
 
 use crate::{
-    window::{sdl_window::SimulatorEventsIter, FpsLimiter, SdlWindow},
+    display::envelope,
+    input::SimulatorEvent,
+    window::{
+        clock::Clock, input_log::InputLog, recorder::Recorder, recorder_from_env, SdlWindow,
+        SimulatorEventsIter,
+    },
     OutputImage, OutputSettings, SimulatorDisplay,
 };
 
@@ -17,11 +27,20 @@ use crate::{
 /// To determine if the mouse pointer is over one of the displays the
 /// [`translate_mouse_position`](Self::translate_mouse_position) can be used to
 /// translate window coordinates into display coordinates.
+///
+/// Events are written to the `EG_SIMULATOR_RECORD_INPUT` log in window coordinates. Input scripts
+/// set by `EG_SIMULATOR_SCRIPT` aren't supported, because script steps refer to a single display.
 pub struct MultiWindow {
     sdl_window: SdlWindow,
     framebuffer: OutputImage<Rgb888>,
+    /// Area of the framebuffer that was changed since the last flush.
+    dirty_area: Option<Rectangle>,
     displays: HashMap<usize, DisplaySettings>,
-    fps_limiter: FpsLimiter,
+    clock: Clock,
+    recorder: Option<Recorder>,
+    event_queue: RefCell<VecDeque<SimulatorEvent>>,
+    frame: usize,
+    input_log: Option<RefCell<InputLog>>,
 }
 
 impl MultiWindow {
@@ -31,13 +50,18 @@ impl MultiWindow {
 
         let framebuffer = OutputImage::new(size);
 
-        sdl_window.update(&framebuffer);
+        sdl_window.update(&framebuffer, &framebuffer.bounding_box());
 
         Self {
-            sdl_window: sdl_window,
+            sdl_window,
             framebuffer,
+            dirty_area: None,
             displays: HashMap::new(),
-            fps_limiter: FpsLimiter::new(),
+            clock: Clock::from_env(false),
+            recorder: recorder_from_env(),
+            event_queue: RefCell::new(VecDeque::new()),
+            frame: 0,
+            input_log: InputLog::from_env().map(RefCell::new),
         }
     }
 
@@ -52,7 +76,8 @@ impl MultiWindow {
             display.id,
             DisplaySettings {
                 offset,
-                output_settings: output_settings.clone(),
+                output_settings: *output_settings,
+                shown_display: None,
             },
         );
     }
@@ -63,6 +88,19 @@ impl MultiWindow {
     /// the window that aren't covered by a display.
     pub fn clear(&mut self, color: Rgb888) {
         self.framebuffer.clear(color).unwrap();
+        self.add_dirty_area(self.framebuffer.bounding_box());
+
+        // The displays need to be redrawn completely, because they were overwritten.
+        for display_settings in self.displays.values_mut() {
+            display_settings.shown_display = None;
+        }
+    }
+
+    fn add_dirty_area(&mut self, area: Rectangle) {
+        self.dirty_area = Some(match self.dirty_area {
+            Some(dirty_area) => envelope(&dirty_area, &area),
+            None => area,
+        });
     }
 
     /// Updates one display.
@@ -72,38 +110,79 @@ impl MultiWindow {
     /// update the window.
     pub fn update_display<C>(&mut self, display: &SimulatorDisplay<C>)
     where
-        C: PixelColor + Into<Rgb888> + From<Rgb888>,
+        C: PixelColor + Into<Rgb888>,
     {
         let display_settings = self
             .displays
-            .get(&display.id)
+            .get_mut(&display.id)
             .expect("update_display called for a display that hasn't been added with add_display");
 
-        self.framebuffer.draw_display(
-            display,
-            display_settings.offset,
-            &display_settings.output_settings,
-        );
+        // Only the area of the display that was changed since the last update is redrawn.
+        let area = match display_settings
+            .shown_display
+            .replace((display.history_id(), display.generation()))
+        {
+            Some((history_id, generation)) if history_id == display.history_id() => {
+                display.dirty_area_since(generation)
+            }
+            _ => Some(display.bounding_box()),
+        };
+
+        if let Some(area) = area {
+            let output_area = self.framebuffer.draw_display_area(
+                display,
+                &area,
+                display_settings.offset,
+                &display_settings.output_settings,
+            );
+            self.add_dirty_area(output_area);
+        }
     }
 
     /// Updates the window from the internal framebuffer.
     pub fn flush(&mut self) {
-        self.sdl_window.update(&self.framebuffer);
+        let now = self.clock.next_frame();
 
-        self.fps_limiter.sleep();
+        if let Some(input_log) = &mut self.input_log {
+            input_log.get_mut().set_frame(self.frame, now);
+        }
+        self.frame += 1;
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.add_frame(&self.framebuffer, now);
+        }
+
+        let dirty_area = self.dirty_area.take().unwrap_or_else(Rectangle::zero);
+        self.sdl_window.update(&self.framebuffer, &dirty_area);
+
+        self.clock.sleep();
     }
 
     /// Returns an iterator of all captured simulator events.
     ///
     /// The coordinates in mouse events are in raw window coordinates, use
     /// [`translate_mouse_position`](Self::translate_mouse_position) to
-    /// translate them into display coordinates.
+    /// translate them into display coordinates. Events added by
+    /// [`push_event`](Self::push_event) are returned first.
     ///
     /// # Panics
     ///
     /// Panics if multiple instances of the iterator are used at the same time.
     pub fn events(&self) -> SimulatorEventsIter<'_> {
-        self.sdl_window.events(&crate::OutputSettings::default())
+        SimulatorEventsIter::new(
+            self.event_queue.borrow_mut(),
+            &OutputSettings::default(),
+            None,
+        )
+        .with_input_log(self.input_log.as_ref().map(RefCell::borrow_mut))
+        .with_event_pump(self.sdl_window.event_pump())
+    }
+
+    /// Adds a synthetic event to the event queue.
+    ///
+    /// The event is returned by the next call to [`events`](Self::events).
+    pub fn push_event(&mut self, event: SimulatorEvent) {
+        self.event_queue.get_mut().push_back(event);
     }
 
     /// Translate a mouse position into display coordinates.
@@ -120,18 +199,72 @@ impl MultiWindow {
         );
 
         let delta = position - display_settings.offset;
-        let p = display_settings.output_settings.output_to_display(delta);
+        let p = display_settings
+            .output_settings
+            .output_to_display(delta, display.size());
 
         display.bounding_box().contains(p).then_some(p)
     }
 
     /// Sets the FPS limit of the window.
     pub fn set_max_fps(&mut self, max_fps: u32) {
-        self.fps_limiter.max_fps = max_fps;
+        self.clock.max_fps = max_fps;
+    }
+
+    /// Enables or disables the virtual clock.
+    ///
+    /// See [`Window::set_virtual_clock`](crate::Window::set_virtual_clock) for more details.
+    pub fn set_virtual_clock(&mut self, virtual_clock: bool) {
+        self.clock.virtual_clock = virtual_clock;
+    }
+
+    /// Returns the simulator time.
+    ///
+    /// The time is measured relative to the first [`flush`](Self::flush) call.
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// Returns the simulator time between the last two [`flush`](Self::flush) calls.
+    pub fn frame_time(&self) -> Duration {
+        self.clock.frame_time()
+    }
+
+    /// Starts recording the window content.
+    ///
+    /// See [`Window::start_recording`](crate::Window::start_recording) for more details.
+    pub fn start_recording<P: AsRef<Path>>(&mut self, path: P) -> image::ImageResult<()> {
+        self.recorder = Some(Recorder::new(path)?);
+
+        Ok(())
+    }
+
+    /// Stops the recording and saves it to the file passed to
+    /// [`start_recording`](Self::start_recording).
+    ///
+    /// Does nothing if no recording is active.
+    pub fn stop_recording(&mut self) -> image::ImageResult<()> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(self.clock.frame_end()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for MultiWindow {
+    fn drop(&mut self) {
+        if let Err(error) = self.stop_recording() {
+            eprintln!("failed to save recording: {error}");
+        }
     }
 }
 
 struct DisplaySettings {
     offset: Point,
     output_settings: OutputSettings,
+    /// History ID and generation of the display that was last drawn to the framebuffer.
+    ///
+    /// Clones of a display share the display ID, which is used to find the display settings, but
+    /// have a different history ID.
+    shown_display: Option<(usize, u64)>,
 }
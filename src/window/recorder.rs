@@ -0,0 +1,469 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    error::{
+        EncodingError, ImageFormatHint, ParameterError, ParameterErrorKind, UnsupportedError,
+        UnsupportedErrorKind,
+    },
+    Delay, Frame, ImageError, ImageFormat, ImageResult, RgbImage,
+};
+
+use crate::output_image::OutputImage;
+
+/// Animation file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecordingFormat {
+    Gif,
+    Apng,
+}
+
+impl RecordingFormat {
+    /// Determines the format based on the file extension.
+    fn from_path(path: &Path) -> ImageResult<Self> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("gif") => Ok(Self::Gif),
+            Some("png") | Some("apng") => Ok(Self::Apng),
+            _ => Err(ImageError::Unsupported(
+                UnsupportedError::from_format_and_kind(
+                    ImageFormatHint::PathExtension(path.extension().unwrap_or_default().into()),
+                    UnsupportedErrorKind::GenericFeature(
+                        "recordings must be saved as .gif, .png or .apng".to_string(),
+                    ),
+                ),
+            )),
+        }
+    }
+
+    /// Returns the resolution of frame delays.
+    fn delay_resolution(self) -> Duration {
+        match self {
+            RecordingFormat::Gif => Duration::from_millis(10),
+            RecordingFormat::Apng => Duration::from_millis(1),
+        }
+    }
+}
+
+/// Frame that is shown until the next frame is added.
+struct PendingFrame {
+    size: Size,
+    data: Box<[u8]>,
+    /// Start time in units of the delay resolution, relative to the first frame.
+    ticks: u32,
+}
+
+/// Encoder that frames are written to as they are added.
+enum Encoder {
+    Gif(GifEncoder<BufWriter<File>>),
+    Apng {
+        writer: png::Writer<BufWriter<File>>,
+        size: Size,
+        frames: u32,
+    },
+}
+
+/// Records the frames shown in a window.
+///
+/// Frames are written to the file as soon as their duration is known, which means that only the
+/// last frame needs to be kept in memory.
+pub(crate) struct Recorder {
+    path: PathBuf,
+    format: RecordingFormat,
+    start: Option<Duration>,
+    pending: Option<PendingFrame>,
+    encoder: Option<Encoder>,
+    error: Option<ImageError>,
+}
+
+impl Recorder {
+    /// Creates a new recorder.
+    ///
+    /// Returns an error if the file extension doesn't correspond to a supported format.
+    pub(crate) fn new<P: AsRef<Path>>(path: P) -> ImageResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let format = RecordingFormat::from_path(&path)?;
+
+        Ok(Self {
+            path,
+            format,
+            start: None,
+            pending: None,
+            encoder: None,
+            error: None,
+        })
+    }
+
+    /// Adds a frame to the recording.
+    ///
+    /// Frames that are identical to the previous frame aren't stored, which extends the duration
+    /// of the previous frame instead.
+    ///
+    /// `timestamp` is the simulator time at which the frame is shown. Errors that occur while the
+    /// previous frame is written are returned by [`finish`](Self::finish).
+    pub(crate) fn add_frame(&mut self, framebuffer: &OutputImage<Rgb888>, timestamp: Duration) {
+        if let Some(pending) = &self.pending {
+            if pending.size == framebuffer.size() && pending.data == framebuffer.data {
+                return;
+            }
+        }
+
+        let ticks = self.ticks(timestamp);
+        self.write_pending(ticks);
+
+        self.pending = Some(PendingFrame {
+            size: framebuffer.size(),
+            data: framebuffer.data.clone(),
+            ticks,
+        });
+    }
+
+    /// Stops the recording and saves the animation.
+    ///
+    /// The last frame is shown until `end`. No file is written if no frames were recorded.
+    pub(crate) fn finish(mut self, end: Duration) -> ImageResult<()> {
+        // Make sure the last frame is visible, even if the recording was stopped right after it
+        // was shown.
+        if let Some(pending_ticks) = self.pending.as_ref().map(|pending| pending.ticks) {
+            let ticks = self.ticks(end).max(pending_ticks + 1);
+            self.write_pending(ticks);
+        }
+
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        match self.encoder {
+            Some(Encoder::Gif(encoder)) => {
+                // The GIF trailer is written when the encoder is dropped.
+                drop(encoder);
+                Ok(())
+            }
+            Some(Encoder::Apng { writer, frames, .. }) => {
+                writer.finish().map_err(png_error)?;
+                set_apng_frame_count(&self.path, frames)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Converts a timestamp into units of the delay resolution, relative to the first frame.
+    ///
+    /// The timestamps are rounded before the delays are calculated to prevent rounding errors
+    /// from accumulating.
+    fn ticks(&mut self, timestamp: Duration) -> u32 {
+        let start = *self.start.get_or_insert(timestamp);
+        let resolution = self.format.delay_resolution();
+
+        (timestamp.saturating_sub(start).as_secs_f64() / resolution.as_secs_f64()).round() as u32
+    }
+
+    /// Writes the pending frame, which is shown until `end_ticks`.
+    ///
+    /// Frames with a delay of `0` are skipped.
+    fn write_pending(&mut self, end_ticks: u32) {
+        let Some(frame) = self.pending.take() else {
+            return;
+        };
+
+        let delay = end_ticks.saturating_sub(frame.ticks);
+        if delay == 0 || self.error.is_some() {
+            return;
+        }
+
+        if let Err(error) = self.write_frame(&frame, delay) {
+            self.error = Some(error);
+        }
+    }
+
+    fn write_frame(&mut self, frame: &PendingFrame, delay: u32) -> ImageResult<()> {
+        let encoder = match &mut self.encoder {
+            Some(encoder) => encoder,
+            None => {
+                let encoder = create_encoder(&self.path, self.format, frame.size)?;
+                self.encoder.insert(encoder)
+            }
+        };
+
+        match encoder {
+            Encoder::Gif(encoder) => {
+                let image =
+                    RgbImage::from_raw(frame.size.width, frame.size.height, frame.data.to_vec())
+                        .unwrap();
+                let image = image::DynamicImage::ImageRgb8(image).into_rgba8();
+
+                encoder.encode_frame(Frame::from_parts(
+                    image,
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(delay * 10, 1),
+                ))
+            }
+            Encoder::Apng {
+                writer,
+                size,
+                frames,
+            } => {
+                if frame.size != *size {
+                    return Err(ImageError::Parameter(ParameterError::from_kind(
+                        ParameterErrorKind::DimensionMismatch,
+                    )));
+                }
+
+                let delay = u16::try_from(delay).unwrap_or(u16::MAX);
+                writer.set_frame_delay(delay, 1000).map_err(png_error)?;
+                writer.write_image_data(&frame.data).map_err(png_error)?;
+                *frames += 1;
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn create_encoder(path: &Path, format: RecordingFormat, size: Size) -> ImageResult<Encoder> {
+    let file = BufWriter::new(File::create(path)?);
+
+    match format {
+        RecordingFormat::Gif => {
+            let mut encoder = GifEncoder::new_with_speed(file, 10);
+            encoder.set_repeat(Repeat::Infinite)?;
+
+            Ok(Encoder::Gif(encoder))
+        }
+        RecordingFormat::Apng => {
+            let mut encoder = png::Encoder::new(file, size.width, size.height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            // The number of frames isn't known until the recording is finished. The frame count
+            // is set to the maximum value to make sure that all frames are written as animation
+            // frames and is corrected by `set_apng_frame_count`.
+            encoder.set_animated(u32::MAX >> 1, 0).map_err(png_error)?;
+
+            Ok(Encoder::Apng {
+                writer: encoder.write_header().map_err(png_error)?,
+                size,
+                frames: 0,
+            })
+        }
+    }
+}
+
+/// Updates the number of frames in the `acTL` chunk of an APNG file.
+///
+/// The chunks are parsed from the start of the file, and an error is returned if no `acTL` chunk
+/// is found before the image data.
+fn set_apng_frame_count(path: &Path, frames: u32) -> ImageResult<()> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+    let mut signature = [0; 8];
+    file.read_exact(&mut signature)?;
+    if signature != SIGNATURE {
+        return Err(invalid_apng("invalid PNG signature"));
+    }
+
+    loop {
+        let mut header = [0; 8];
+        file.read_exact(&mut header)?;
+        let length = u32::from_be_bytes(header[0..4].try_into().unwrap());
+        let chunk_type = &header[4..8];
+
+        if chunk_type == b"acTL" {
+            if length != 8 {
+                return Err(invalid_apng("invalid acTL chunk length"));
+            }
+
+            // The CRC covers the chunk type and the chunk data.
+            let mut chunk = [0; 12];
+            chunk[0..4].copy_from_slice(chunk_type);
+            file.read_exact(&mut chunk[4..12])?;
+            chunk[4..8].copy_from_slice(&frames.to_be_bytes());
+            let crc = crc32fast::hash(&chunk);
+
+            file.seek(SeekFrom::Current(-8))?;
+            file.write_all(&frames.to_be_bytes())?;
+            file.seek(SeekFrom::Current(4))?;
+            file.write_all(&crc.to_be_bytes())?;
+
+            return Ok(());
+        }
+
+        if chunk_type == b"IDAT" || chunk_type == b"fdAT" || chunk_type == b"IEND" {
+            return Err(invalid_apng("missing acTL chunk"));
+        }
+
+        // Skip the chunk data and CRC.
+        file.seek(SeekFrom::Current(i64::from(length) + 4))?;
+    }
+}
+
+fn invalid_apng(message: &str) -> ImageError {
+    ImageError::Encoding(EncodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Png),
+        message.to_string(),
+    ))
+}
+
+fn png_error(error: png::EncodingError) -> ImageError {
+    ImageError::Encoding(EncodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Png),
+        error,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::AnimationDecoder;
+
+    use crate::test_util::TempDir;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn record_frames(path: &Path) {
+        let mut recorder = Recorder::new(path).unwrap();
+        let mut framebuffer = OutputImage::<Rgb888>::new(Size::new(4, 3));
+
+        let start = Duration::from_secs(1);
+        recorder.add_frame(&framebuffer, start);
+        // identical frames are merged
+        recorder.add_frame(&framebuffer, start + MS * 20);
+
+        framebuffer.clear(Rgb888::RED).unwrap();
+        recorder.add_frame(&framebuffer, start + MS * 40);
+
+        framebuffer.clear(Rgb888::GREEN).unwrap();
+        recorder.add_frame(&framebuffer, start + MS * 60);
+
+        recorder.finish(start + MS * 100).unwrap();
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(
+            RecordingFormat::from_path(Path::new("out.gif")).unwrap(),
+            RecordingFormat::Gif
+        );
+        assert_eq!(
+            RecordingFormat::from_path(Path::new("out.PNG")).unwrap(),
+            RecordingFormat::Apng
+        );
+        assert_eq!(
+            RecordingFormat::from_path(Path::new("out.apng")).unwrap(),
+            RecordingFormat::Apng
+        );
+        assert!(RecordingFormat::from_path(Path::new("out.mp4")).is_err());
+        assert!(RecordingFormat::from_path(Path::new("out")).is_err());
+    }
+
+    fn gif_delays(path: &Path) -> Vec<Duration> {
+        let decoder =
+            image::codecs::gif::GifDecoder::new(std::io::BufReader::new(File::open(path).unwrap()))
+                .unwrap();
+
+        decoder
+            .into_frames()
+            .map(|frame| Duration::from(frame.unwrap().delay()))
+            .collect()
+    }
+
+    fn record_timestamps(path: &Path, timestamps: &[Duration], end: Duration) {
+        let mut recorder = Recorder::new(path).unwrap();
+        let mut framebuffer = OutputImage::<Rgb888>::new(Size::new(1, 1));
+
+        for (i, timestamp) in timestamps.iter().enumerate() {
+            framebuffer.clear(Rgb888::new(i as u8, 0, 0)).unwrap();
+            recorder.add_frame(&framebuffer, *timestamp);
+        }
+
+        recorder.finish(end).unwrap();
+    }
+
+    #[test]
+    fn delays() {
+        let dir = TempDir::new("recorder");
+        let path = dir.join("delays.gif");
+
+        record_timestamps(&path, &[Duration::ZERO, MS * 16, MS * 33, MS * 50], MS * 50);
+        assert_eq!(gif_delays(&path), vec![MS * 20, MS * 10, MS * 20, MS * 10]);
+
+        // frames with a rounded delay of 0 are skipped
+        record_timestamps(&path, &[Duration::ZERO, MS * 4, MS * 8, MS * 12], MS * 12);
+        assert_eq!(gif_delays(&path), vec![MS * 10, MS * 10]);
+    }
+
+    #[test]
+    fn record_gif() {
+        let dir = TempDir::new("recorder");
+        let path = dir.join("recording.gif");
+        record_frames(&path);
+
+        assert_eq!(gif_delays(&path), vec![MS * 40, MS * 20, MS * 40]);
+
+        let decoder = image::codecs::gif::GifDecoder::new(std::io::BufReader::new(
+            File::open(&path).unwrap(),
+        ))
+        .unwrap();
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(frames[1].buffer().get_pixel(0, 0).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn record_apng() {
+        let dir = TempDir::new("recorder");
+        let path = dir.join("recording.png");
+        record_frames(&path);
+
+        let decoder = png::Decoder::new(std::io::BufReader::new(File::open(&path).unwrap()));
+        let mut reader = decoder.read_info().unwrap();
+        let animation_control = reader.info().animation_control.unwrap();
+
+        assert_eq!(animation_control.num_frames, 3);
+        assert_eq!(animation_control.num_plays, 0);
+
+        let mut buffer = vec![0; reader.output_buffer_size().unwrap()];
+        let mut delays = Vec::new();
+        for _ in 0..3 {
+            reader.next_frame(&mut buffer).unwrap();
+            delays.push(reader.info().frame_control.unwrap().delay_num);
+        }
+        assert_eq!(delays, vec![40, 20, 40]);
+        assert_eq!(&buffer[0..3], &[0, 255, 0]);
+    }
+
+    #[test]
+    fn set_frame_count_without_actl() {
+        let dir = TempDir::new("recorder");
+        let path = dir.join("static.png");
+
+        RgbImage::new(2, 2).save(&path).unwrap();
+        let before = std::fs::read(&path).unwrap();
+
+        assert!(set_apng_frame_count(&path, 3).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn no_frames() {
+        let dir = TempDir::new("recorder");
+        let path = dir.join("empty.gif");
+
+        Recorder::new(&path)
+            .unwrap()
+            .finish(Duration::ZERO)
+            .unwrap();
+        assert!(!path.exists());
+    }
+}
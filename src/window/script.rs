@@ -0,0 +1,628 @@
+use std::{
+    collections::VecDeque,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use embedded_graphics::{pixelcolor::Rgb888, prelude::*};
+
+use crate::{
+    display::SimulatorDisplay,
+    input::{Keycode, Mod, MouseButton, MouseWheelDirection, SimulatorEvent},
+    output_settings::OutputSettings,
+    window::dump::parse_duration,
+};
+
+/// A single script step.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Step {
+    /// Waits until the given time has elapsed.
+    Wait(Duration),
+    /// Waits until the frame with the given index is shown.
+    Frame(usize),
+    /// Presses and releases a key.
+    Key(Keycode),
+    /// Clicks the left mouse button at the given display position.
+    Click(Point),
+    /// Adds an event with a mouse position in display coordinates.
+    Event(SimulatorEvent),
+    /// Compares the display with a reference image.
+    Expect(PathBuf),
+    /// Saves the display content as a PNG file.
+    Dump(PathBuf),
+}
+
+impl Step {
+    /// Parses a script step.
+    ///
+    /// Relative paths are resolved relative to `base_dir`.
+    fn parse(line: &str, base_dir: &Path) -> Result<Self, String> {
+        let (command, argument) = line
+            .split_once(char::is_whitespace)
+            .map(|(command, argument)| (command, argument.trim()))
+            .unwrap_or((line, ""));
+
+        if command == "quit" {
+            return Ok(Step::Event(SimulatorEvent::Quit));
+        }
+
+        if argument.is_empty() {
+            return Err(format!("missing argument for \"{command}\""));
+        }
+
+        match command {
+            "wait" => parse_duration(argument)
+                .map(Step::Wait)
+                .ok_or_else(|| format!("invalid duration: {argument}")),
+            "frame" => argument
+                .parse()
+                .map(Step::Frame)
+                .map_err(|_| format!("invalid frame: {argument}")),
+            "key" => parse_keycode(argument).map(Step::Key),
+            "click" => parse_point(argument).map(Step::Click),
+            "keydown" | "keyup" => {
+                let mut arguments = argument.split_whitespace();
+                let keycode = parse_keycode(arguments.next().unwrap())?;
+                let mut keymod = Mod::NOMOD;
+                let mut repeat = false;
+                for argument in arguments {
+                    if argument == "repeat" {
+                        repeat = true;
+                    } else {
+                        keymod |= parse_keymod(argument)?;
+                    }
+                }
+
+                Ok(Step::Event(if command == "keydown" {
+                    SimulatorEvent::KeyDown {
+                        keycode,
+                        keymod,
+                        repeat,
+                    }
+                } else {
+                    SimulatorEvent::KeyUp {
+                        keycode,
+                        keymod,
+                        repeat,
+                    }
+                }))
+            }
+            "mousedown" | "mouseup" => {
+                let (button, point) = argument
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| format!("missing position: {argument}"))?;
+                let mouse_btn = MOUSE_BUTTONS
+                    .iter()
+                    .find(|(name, _)| *name == button)
+                    .map(|(_, button)| *button)
+                    .ok_or_else(|| format!("unknown mouse button: {button}"))?;
+                let point = parse_point(point)?;
+
+                Ok(Step::Event(if command == "mousedown" {
+                    SimulatorEvent::MouseButtonDown { mouse_btn, point }
+                } else {
+                    SimulatorEvent::MouseButtonUp { mouse_btn, point }
+                }))
+            }
+            "mousemove" => {
+                parse_point(argument).map(|point| Step::Event(SimulatorEvent::MouseMove { point }))
+            }
+            "wheel" => {
+                let (delta, direction) = argument
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((argument, "normal"));
+                let direction = match direction.trim() {
+                    "normal" => MouseWheelDirection::Normal,
+                    "flipped" => MouseWheelDirection::Flipped,
+                    direction => direction
+                        .parse()
+                        .map(MouseWheelDirection::Unknown)
+                        .map_err(|_| format!("invalid wheel direction: {direction}"))?,
+                };
+
+                Ok(Step::Event(SimulatorEvent::MouseWheel {
+                    scroll_delta: parse_point(delta)?,
+                    direction,
+                }))
+            }
+            "expect" => Ok(Step::Expect(base_dir.join(argument))),
+            "dump" => Ok(Step::Dump(base_dir.join(argument))),
+            _ => Err(format!("unknown command: {command}")),
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Wait(duration) => write!(f, "wait {}ms", duration.as_millis()),
+            Step::Frame(frame) => write!(f, "frame {frame}"),
+            Step::Key(keycode) => write!(f, "key {}", KeyName(*keycode)),
+            Step::Click(point) => write!(f, "click {}", Position(*point)),
+            Step::Event(event) => match event {
+                SimulatorEvent::KeyDown {
+                    keycode,
+                    keymod,
+                    repeat,
+                }
+                | SimulatorEvent::KeyUp {
+                    keycode,
+                    keymod,
+                    repeat,
+                } => {
+                    let command = if matches!(event, SimulatorEvent::KeyDown { .. }) {
+                        "keydown"
+                    } else {
+                        "keyup"
+                    };
+                    write!(f, "{command} {}", KeyName(*keycode))?;
+
+                    let modifiers = MODIFIERS
+                        .iter()
+                        .filter(|(_, modifier)| keymod.contains(*modifier))
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>();
+                    if !modifiers.is_empty() {
+                        write!(f, " {}", modifiers.join("+"))?;
+                    }
+
+                    if *repeat {
+                        write!(f, " repeat")?;
+                    }
+
+                    Ok(())
+                }
+                SimulatorEvent::MouseButtonDown { mouse_btn, point }
+                | SimulatorEvent::MouseButtonUp { mouse_btn, point } => {
+                    let command = if matches!(event, SimulatorEvent::MouseButtonDown { .. }) {
+                        "mousedown"
+                    } else {
+                        "mouseup"
+                    };
+                    let button = MOUSE_BUTTONS
+                        .iter()
+                        .find(|(_, button)| button == mouse_btn)
+                        .map(|(name, _)| *name)
+                        .unwrap();
+
+                    write!(f, "{command} {button} {}", Position(*point))
+                }
+                SimulatorEvent::MouseMove { point } => write!(f, "mousemove {}", Position(*point)),
+                SimulatorEvent::MouseWheel {
+                    scroll_delta,
+                    direction,
+                } => {
+                    write!(f, "wheel {}", Position(*scroll_delta))?;
+
+                    match direction {
+                        MouseWheelDirection::Normal => Ok(()),
+                        MouseWheelDirection::Flipped => write!(f, " flipped"),
+                        MouseWheelDirection::Unknown(direction) => write!(f, " {direction}"),
+                    }
+                }
+                SimulatorEvent::Quit => write!(f, "quit"),
+            },
+            Step::Expect(path) => write!(f, "expect {}", path.display()),
+            Step::Dump(path) => write!(f, "dump {}", path.display()),
+        }
+    }
+}
+
+/// Modifier names used in `keydown` and `keyup` steps.
+const MODIFIERS: &[(&str, Mod)] = &[
+    ("LSHIFT", Mod::LSHIFTMOD),
+    ("RSHIFT", Mod::RSHIFTMOD),
+    ("LCTRL", Mod::LCTRLMOD),
+    ("RCTRL", Mod::RCTRLMOD),
+    ("LALT", Mod::LALTMOD),
+    ("RALT", Mod::RALTMOD),
+    ("LGUI", Mod::LGUIMOD),
+    ("RGUI", Mod::RGUIMOD),
+    ("NUM", Mod::NUMMOD),
+    ("CAPS", Mod::CAPSMOD),
+    ("MODE", Mod::MODEMOD),
+    ("RESERVED", Mod::RESERVEDMOD),
+];
+
+/// Mouse button names used in `mousedown` and `mouseup` steps.
+const MOUSE_BUTTONS: &[(&str, MouseButton)] = &[
+    ("left", MouseButton::Left),
+    ("middle", MouseButton::Middle),
+    ("right", MouseButton::Right),
+    ("x1", MouseButton::X1),
+    ("x2", MouseButton::X2),
+    ("unknown", MouseButton::Unknown),
+];
+
+/// Parses a key name or a numeric key code.
+fn parse_keycode(value: &str) -> Result<Keycode, String> {
+    Keycode::from_name(value)
+        .or_else(|| value.parse().ok().and_then(Keycode::from_i32))
+        .ok_or_else(|| format!("unknown key: {value}"))
+}
+
+/// Parses a list of modifiers separated by `+`, e.g. `LSHIFT+LCTRL`.
+fn parse_keymod(value: &str) -> Result<Mod, String> {
+    value.split('+').try_fold(Mod::NOMOD, |keymod, name| {
+        MODIFIERS
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, modifier)| keymod | *modifier)
+            .ok_or_else(|| format!("unknown modifier: {name}"))
+    })
+}
+
+/// Parses a point in the `x,y` format.
+fn parse_point(value: &str) -> Result<Point, String> {
+    value
+        .split_once(',')
+        .and_then(|(x, y)| Some(Point::new(x.trim().parse().ok()?, y.trim().parse().ok()?)))
+        .ok_or_else(|| format!("invalid position: {value}"))
+}
+
+/// Formats a key code by its name, or by its numeric value if it has no name.
+struct KeyName(Keycode);
+
+impl fmt::Display for KeyName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", self.0.into_i32()),
+        }
+    }
+}
+
+/// Formats a point in the `x,y` format.
+struct Position(Point);
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.0.x, self.0.y)
+    }
+}
+
+/// Error returned if a script couldn't be parsed.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ScriptError {
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Input script read from the file set in the `EG_SIMULATOR_SCRIPT` environment variable.
+pub(crate) struct Script {
+    steps: VecDeque<Step>,
+    /// End of the currently active `wait` step.
+    wait_until: Option<Duration>,
+}
+
+impl Script {
+    /// Reads the script from the file set in `EG_SIMULATOR_SCRIPT`.
+    ///
+    /// Returns `None` if the variable isn't set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file couldn't be read or contains an invalid step.
+    pub(crate) fn from_env() -> Option<Self> {
+        let path = PathBuf::from(env::var_os("EG_SIMULATOR_SCRIPT")?);
+
+        let script = fs::read_to_string(&path)
+            .unwrap_or_else(|error| panic!("failed to read {}: {error}", path.display()));
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+
+        Some(
+            Self::parse(&script, base_dir)
+                .unwrap_or_else(|error| panic!("invalid script {}: {error}", path.display())),
+        )
+    }
+
+    /// Parses a script.
+    ///
+    /// Each line contains one step. Empty lines and text after a `#` are ignored.
+    fn parse(script: &str, base_dir: &Path) -> Result<Self, ScriptError> {
+        let steps = script
+            .lines()
+            .enumerate()
+            .map(|(index, line)| {
+                let line = line.split_once('#').map_or(line, |(line, _)| line);
+                (index + 1, line.trim())
+            })
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(line, step)| {
+                Step::parse(step, base_dir).map_err(|message| ScriptError { line, message })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            steps,
+            wait_until: None,
+        })
+    }
+
+    /// Returns `true` if all steps have been executed.
+    pub(crate) fn is_done(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Executes the script steps for the current frame.
+    ///
+    /// Steps are executed until a `wait` or `frame` step is reached that hasn't elapsed yet or until
+    /// a `key` or `click` step was executed. Stopping after these steps gives the application a
+    /// chance to handle the events before the next frame is checked or dumped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an `expect` step fails or if a `dump` step couldn't save the image.
+    pub(crate) fn update<C>(
+        &mut self,
+        display: &SimulatorDisplay<C>,
+        frame: usize,
+        elapsed: Duration,
+        output_settings: &OutputSettings,
+        events: &mut VecDeque<SimulatorEvent>,
+    ) where
+        C: PixelColor + Into<Rgb888>,
+    {
+        while let Some(step) = self.steps.front() {
+            match step {
+                Step::Wait(duration) => {
+                    let wait_until = *self.wait_until.get_or_insert(elapsed + *duration);
+                    if elapsed < wait_until {
+                        return;
+                    }
+                    self.wait_until = None;
+                }
+                Step::Frame(index) => {
+                    if frame < *index {
+                        return;
+                    }
+                }
+                Step::Key(keycode) => {
+                    for event in key_events(*keycode) {
+                        events.push_back(event);
+                    }
+                }
+                Step::Click(point) => {
+                    // Events in the queue use window coordinates.
+                    for event in click_events(*point) {
+                        events.push_back(event.display_to_output(output_settings, display.size()));
+                    }
+                }
+                Step::Event(event) => {
+                    events.push_back(event.display_to_output(output_settings, display.size()));
+                }
+                Step::Expect(path) => {
+                    if let Err(error) = display.assert_matches_png(path, output_settings) {
+                        panic!("script expectation failed: {error}");
+                    }
+                }
+                Step::Dump(path) => {
+                    display
+                        .to_rgb_output_image(output_settings)
+                        .save_png(path)
+                        .unwrap();
+                }
+            }
+
+            let step = self.steps.pop_front().unwrap();
+            if matches!(step, Step::Key(_) | Step::Click(_)) {
+                return;
+            }
+        }
+    }
+}
+
+fn key_events(keycode: Keycode) -> [SimulatorEvent; 2] {
+    [
+        SimulatorEvent::KeyDown {
+            keycode,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        },
+        SimulatorEvent::KeyUp {
+            keycode,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        },
+    ]
+}
+
+fn click_events(point: Point) -> [SimulatorEvent; 2] {
+    [
+        SimulatorEvent::MouseButtonDown {
+            mouse_btn: MouseButton::Left,
+            point,
+        },
+        SimulatorEvent::MouseButtonUp {
+            mouse_btn: MouseButton::Left,
+            point,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    use crate::{test_util::TempDir, OutputSettingsBuilder};
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn parse() {
+        let script = Script::parse(
+            "# open the menu\n\
+             wait 500ms # skip the splash screen\n\
+             key Down\n\
+             \n\
+             click 10, 20\n\
+             expect screen.png\n\
+             dump out/menu.png\n",
+            Path::new("tests"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Vec::from(script.steps),
+            vec![
+                Step::Wait(MS * 500),
+                Step::Key(Keycode::DOWN),
+                Step::Click(Point::new(10, 20)),
+                Step::Expect(PathBuf::from("tests/screen.png")),
+                Step::Dump(PathBuf::from("tests/out/menu.png")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |script| {
+            Script::parse(script, Path::new(""))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(error("wait 1s\nwait 10"), "line 2: invalid duration: 10");
+        assert_eq!(error("key Foo"), "line 1: unknown key: Foo");
+        assert_eq!(error("click 10"), "line 1: invalid position: 10");
+        assert_eq!(error("dump"), "line 1: missing argument for \"dump\"");
+        assert_eq!(error("press A"), "line 1: unknown command: press");
+    }
+
+    #[test]
+    fn update() {
+        let mut script = Script::parse("wait 100ms\nkey Space\nclick 1,2", Path::new("")).unwrap();
+        let display = SimulatorDisplay::<BinaryColor>::new(Size::new(16, 8));
+        let output_settings = OutputSettingsBuilder::new().scale(2).build();
+        let mut events = VecDeque::new();
+
+        script.update(&display, 0, Duration::ZERO, &output_settings, &mut events);
+        script.update(&display, 1, MS * 50, &output_settings, &mut events);
+        assert!(events.is_empty());
+
+        script.update(&display, 2, MS * 100, &output_settings, &mut events);
+        assert_eq!(Vec::from(events.clone()), key_events(Keycode::SPACE));
+        assert!(!script.is_done());
+
+        events.clear();
+        script.update(&display, 3, MS * 116, &output_settings, &mut events);
+        assert_eq!(
+            Vec::from(events),
+            click_events(Point::new(1, 2))
+                .map(|event| event.display_to_output(&output_settings, display.size()))
+        );
+        assert!(script.is_done());
+    }
+
+    #[test]
+    fn update_frames() {
+        let mut script = Script::parse(
+            "frame 2\nkeydown A\nkeyup A\nframe 3\nmousemove 1,2",
+            Path::new(""),
+        )
+        .unwrap();
+        let display = SimulatorDisplay::<BinaryColor>::new(Size::new(16, 8));
+        let output_settings = OutputSettingsBuilder::new().scale(2).build();
+        let mut events = VecDeque::new();
+
+        script.update(&display, 0, Duration::ZERO, &output_settings, &mut events);
+        script.update(&display, 1, MS * 16, &output_settings, &mut events);
+        assert!(events.is_empty());
+
+        script.update(&display, 2, MS * 32, &output_settings, &mut events);
+        assert_eq!(Vec::from(events.clone()), key_events(Keycode::A));
+
+        events.clear();
+        script.update(&display, 3, MS * 48, &output_settings, &mut events);
+        assert_eq!(
+            Vec::from(events),
+            [SimulatorEvent::MouseMove {
+                point: Point::new(2, 4)
+            }]
+        );
+        assert!(script.is_done());
+    }
+
+    #[test]
+    fn format_and_parse_events() {
+        let steps = [
+            Step::Frame(12),
+            Step::Event(SimulatorEvent::KeyDown {
+                keycode: Keycode::KP_ENTER,
+                keymod: Mod::LSHIFTMOD | Mod::CAPSMOD,
+                repeat: true,
+            }),
+            Step::Event(SimulatorEvent::KeyUp {
+                keycode: Keycode::from_i32(0x1234).unwrap(),
+                keymod: Mod::NOMOD,
+                repeat: false,
+            }),
+            Step::Event(SimulatorEvent::MouseButtonDown {
+                mouse_btn: MouseButton::Right,
+                point: Point::new(-1, 2),
+            }),
+            Step::Event(SimulatorEvent::MouseButtonUp {
+                mouse_btn: MouseButton::Left,
+                point: Point::new(3, 4),
+            }),
+            Step::Event(SimulatorEvent::MouseMove {
+                point: Point::new(5, 6),
+            }),
+            Step::Event(SimulatorEvent::MouseWheel {
+                scroll_delta: Point::new(0, -1),
+                direction: MouseWheelDirection::Flipped,
+            }),
+            Step::Event(SimulatorEvent::Quit),
+        ];
+
+        let script = steps
+            .iter()
+            .map(|step| format!("{step}\n"))
+            .collect::<String>();
+        assert_eq!(
+            script,
+            "frame 12\n\
+             keydown KP_ENTER LSHIFT+CAPS repeat\n\
+             keyup 4660\n\
+             mousedown right -1,2\n\
+             mouseup left 3,4\n\
+             mousemove 5,6\n\
+             wheel 0,-1 flipped\n\
+             quit\n"
+        );
+
+        let parsed = Script::parse(&script, Path::new("")).unwrap();
+        assert_eq!(Vec::from(parsed.steps), steps);
+    }
+
+    #[test]
+    fn expect_and_dump() {
+        let dir = TempDir::new("script");
+        let script = format!(
+            "dump {0}\nexpect {0}",
+            dir.join("dump.png").to_string_lossy()
+        );
+        let mut script = Script::parse(&script, Path::new("")).unwrap();
+        let display = SimulatorDisplay::<BinaryColor>::new(Size::new(16, 8));
+        let output_settings = OutputSettingsBuilder::new().scale(2).build();
+
+        script.update(
+            &display,
+            0,
+            Duration::ZERO,
+            &output_settings,
+            &mut VecDeque::new(),
+        );
+        assert!(script.is_done());
+    }
+}
@@ -2,146 +2,84 @@ use std::cell::{RefCell, RefMut};
 
 use embedded_graphics::{
     pixelcolor::Rgb888,
-    prelude::{Point, Size},
+    prelude::{Dimensions, Point, Size},
+    primitives::Rectangle,
 };
 use sdl2::{
     event::Event,
-    keyboard::{Keycode, Mod},
-    mouse::{MouseButton, MouseWheelDirection},
+    keyboard::Keycode,
     pixels::PixelFormatEnum,
+    rect::Rect,
     render::{Canvas, Texture, TextureCreator},
     video::WindowContext,
     EventPump,
 };
 
-use crate::{OutputImage, OutputSettings};
-
-/// A derivation of [`sdl2::event::Event`] mapped to embedded-graphics coordinates
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum SimulatorEvent {
-    /// A keypress event, fired on keyUp
-    KeyUp {
-        /// The key being released
-        keycode: Keycode,
-        /// Any modifier being held at the time of keyup
-        keymod: Mod,
-        /// Whether the key is repeating
-        repeat: bool,
-    },
-    /// A keypress event, fired on keyDown
-    KeyDown {
-        /// The key being pressed
-        keycode: Keycode,
-        /// Any modifier being held at the time of keydown
-        keymod: Mod,
-        /// Whether the key is repeating
-        repeat: bool,
-    },
-    /// A mouse click event, fired on mouseUp
-    MouseButtonUp {
-        /// The mouse button being released
-        mouse_btn: MouseButton,
-        /// The location of the mouse in Simulator coordinates
-        point: Point,
-    },
-    /// A mouse click event, fired on mouseDown
-    MouseButtonDown {
-        /// The mouse button being pressed
-        mouse_btn: MouseButton,
-        /// The location of the mouse in Simulator coordinates
-        point: Point,
-    },
-    /// A mouse wheel event
-    MouseWheel {
-        /// The scroll wheel delta in the x and y direction
-        scroll_delta: Point,
-        /// The directionality of the scroll (normal or flipped)
-        direction: MouseWheelDirection,
-    },
-    /// Mouse move event
-    MouseMove {
-        /// The current mouse position
-        point: Point,
-    },
-    /// An exit event
-    Quit,
-}
+use crate::{input::SimulatorEvent, OutputImage, OutputSettings};
 
-/// Iterator over simulator events.
+/// Maps a SDL event to a simulator event.
 ///
-/// See [`Window::events`](crate::Window::events) and
-/// [`MultiWindow::events`](crate::MultiWindow::events) for more details.
-pub struct SimulatorEventsIter<'a> {
-    event_pump: RefMut<'a, EventPump>,
-    output_settings: OutputSettings,
-}
-
-impl Iterator for SimulatorEventsIter<'_> {
-    type Item = SimulatorEvent;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        while let Some(event) = self.event_pump.poll_event() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => return Some(SimulatorEvent::Quit),
-                Event::KeyDown {
-                    keycode,
-                    keymod,
-                    repeat,
-                    ..
-                } => {
-                    return keycode.map(|valid_keycode| SimulatorEvent::KeyDown {
-                        keycode: valid_keycode,
-                        keymod,
-                        repeat,
-                    })
-                }
-                Event::KeyUp {
-                    keycode,
-                    keymod,
-                    repeat,
-                    ..
-                } => {
-                    return keycode.map(|valid_keycode| SimulatorEvent::KeyUp {
-                        keycode: valid_keycode,
-                        keymod,
-                        repeat,
-                    })
-                }
-                Event::MouseButtonUp {
-                    x, y, mouse_btn, ..
-                } => {
-                    let point = self.output_settings.output_to_display(Point::new(x, y));
-                    return Some(SimulatorEvent::MouseButtonUp { point, mouse_btn });
-                }
-                Event::MouseButtonDown {
-                    x, y, mouse_btn, ..
-                } => {
-                    let point = self.output_settings.output_to_display(Point::new(x, y));
-                    return Some(SimulatorEvent::MouseButtonDown { point, mouse_btn });
-                }
-                Event::MouseMotion { x, y, .. } => {
-                    let point = self.output_settings.output_to_display(Point::new(x, y));
-                    return Some(SimulatorEvent::MouseMove { point });
-                }
-                Event::MouseWheel {
-                    x, y, direction, ..
-                } => {
-                    return Some(SimulatorEvent::MouseWheel {
-                        scroll_delta: Point::new(x, y),
-                        direction,
-                    })
-                }
-                _ => {
-                    // ignore other events and check next event
-                }
-            }
+/// Returns `None` for events that aren't supported by the simulator.
+pub(crate) fn map_event(
+    event: Event,
+    output_settings: &OutputSettings,
+    display_size: Size,
+) -> Option<SimulatorEvent> {
+    match event {
+        Event::Quit { .. }
+        | Event::KeyDown {
+            keycode: Some(Keycode::Escape),
+            ..
+        } => Some(SimulatorEvent::Quit),
+        Event::KeyDown {
+            keycode,
+            keymod,
+            repeat,
+            ..
+        } => keycode.map(|valid_keycode| SimulatorEvent::KeyDown {
+            keycode: valid_keycode.into(),
+            keymod: keymod.into(),
+            repeat,
+        }),
+        Event::KeyUp {
+            keycode,
+            keymod,
+            repeat,
+            ..
+        } => keycode.map(|valid_keycode| SimulatorEvent::KeyUp {
+            keycode: valid_keycode.into(),
+            keymod: keymod.into(),
+            repeat,
+        }),
+        Event::MouseButtonUp {
+            x, y, mouse_btn, ..
+        } => {
+            let point = output_settings.output_to_display(Point::new(x, y), display_size);
+            Some(SimulatorEvent::MouseButtonUp {
+                point,
+                mouse_btn: mouse_btn.into(),
+            })
         }
-
-        None
+        Event::MouseButtonDown {
+            x, y, mouse_btn, ..
+        } => {
+            let point = output_settings.output_to_display(Point::new(x, y), display_size);
+            Some(SimulatorEvent::MouseButtonDown {
+                point,
+                mouse_btn: mouse_btn.into(),
+            })
+        }
+        Event::MouseMotion { x, y, .. } => {
+            let point = output_settings.output_to_display(Point::new(x, y), display_size);
+            Some(SimulatorEvent::MouseMove { point })
+        }
+        Event::MouseWheel {
+            x, y, direction, ..
+        } => Some(SimulatorEvent::MouseWheel {
+            scroll_delta: Point::new(x, y),
+            direction: direction.into(),
+        }),
+        _ => None,
     }
 }
 
@@ -184,17 +122,29 @@ impl SdlWindow {
         }
     }
 
-    pub fn update(&mut self, framebuffer: &OutputImage<Rgb888>) {
-        self.window_texture.with_mut(|fields| {
-            fields
-                .texture
-                .update(
-                    None,
-                    framebuffer.data.as_ref(),
-                    self.size.width as usize * 3,
-                )
-                .unwrap();
-        });
+    /// Updates the window.
+    ///
+    /// Only the pixels inside `area` are uploaded from the framebuffer to the window texture.
+    pub fn update(&mut self, framebuffer: &OutputImage<Rgb888>, area: &Rectangle) {
+        let area = area.intersection(&framebuffer.bounding_box());
+
+        if !area.is_zero_sized() {
+            let pitch = self.size.width as usize * 3;
+            let start = area.top_left.y as usize * pitch + area.top_left.x as usize * 3;
+            let rect = Rect::new(
+                area.top_left.x,
+                area.top_left.y,
+                area.size.width,
+                area.size.height,
+            );
+
+            self.window_texture.with_mut(|fields| {
+                fields
+                    .texture
+                    .update(rect, &framebuffer.data[start..], pitch)
+                    .unwrap();
+            });
+        }
 
         self.canvas
             .copy(self.window_texture.borrow_texture(), None, None)
@@ -202,14 +152,20 @@ impl SdlWindow {
         self.canvas.present();
     }
 
-    /// Handle events
-    /// Return an iterator of all captured SimulatorEvent
-    pub fn events(&self, output_settings: &OutputSettings) -> SimulatorEventsIter<'_> {
-        SimulatorEventsIter {
-            event_pump: self.event_pump.borrow_mut(),
-            output_settings: output_settings.clone(),
+    /// Sets the window title.
+    pub fn set_title(&mut self, title: &str) {
+        let window = self.canvas.window_mut();
+
+        // Titles that contain a NUL byte can't be passed to SDL and are ignored.
+        if window.title() != title {
+            let _ = window.set_title(title);
         }
     }
+
+    /// Returns the SDL event pump.
+    pub fn event_pump(&self) -> RefMut<'_, EventPump> {
+        self.event_pump.borrow_mut()
+    }
 }
 
 #[ouroboros::self_referencing]